@@ -64,8 +64,8 @@ pub(crate) fn introspect(
     let out = File::create(file_path.clone()).map_err(IntrospectionError::IoError)?;
 
     let request_body = QueryBody {
-        query: query::QUERY,
-        operation_name: query::OPERATION_NAME,
+        query: query::QUERY.into(),
+        operation_name: query::OPERATION_NAME.into(),
         variables: query::Variables
     };
 
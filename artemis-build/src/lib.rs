@@ -22,7 +22,8 @@
 //! `http://localhost:8080/graphql`, with no authorization header and no extra headers - but if
 //! you don't call `add_query` at least once the code generator won't do much. The out dir specifies
 //! the directory to output your query module to. It will generate a `mod.rs` in this directory,
-//! along with a file for each query and a global query enum for WASM support. **Make sure this
+//! along with a file for each query, a `types.rs` with the enums, input objects and custom scalars
+//! shared by all queries, and a global query enum for WASM support. **Make sure this
 //! directory doesn't already have a `mod.rs` or it will be overridden.**
 //!
 //! The output directory defaults to `OUT_DIR`, but for good autocomplete I recommend putting the
@@ -33,15 +34,14 @@
 #![warn(missing_docs)]
 
 use artemis_codegen::{
-    deprecation::DeprecationStrategy, generate_module_token_stream, generate_root_token_stream,
-    CodegenError, CodegenMode, GraphQLClientCodegenOptions
+    deprecation::DeprecationStrategy, generate_fragments_token_stream, generate_module_token_stream,
+    generate_root_token_stream, generate_types_token_stream, CodegenError, CodegenMode, GraphQLClientCodegenOptions
 };
 use std::{
+    collections::BTreeMap,
     env,
     error::Error,
-    fmt,
-    fs::File,
-    io::Write,
+    fmt, fs,
     path::{Path, PathBuf}
 };
 use syn::Token;
@@ -92,11 +92,16 @@ impl From<std::io::Error> for BuildError {
 #[derive(Debug, Default)]
 pub struct CodegenBuilder {
     query_paths: Vec<PathBuf>,
+    query_dirs: Vec<(PathBuf, String)>,
+    fragment_paths: Vec<PathBuf>,
     variable_derives: Option<String>,
     response_derives: Option<String>,
     deprecation_strategy: Option<DeprecationStrategy>,
     output_directory: Option<PathBuf>,
-    schema_path: Option<PathBuf>
+    schema_path: Option<PathBuf>,
+    client_schema_paths: Vec<PathBuf>,
+    scalars: BTreeMap<String, String>,
+    maybe_undefined: bool
 }
 
 impl CodegenBuilder {
@@ -106,22 +111,52 @@ impl CodegenBuilder {
     pub fn new() -> Self {
         Self {
             query_paths: Vec::new(),
+            query_dirs: Vec::new(),
+            fragment_paths: Vec::new(),
             variable_derives: None,
             response_derives: None,
             deprecation_strategy: None,
             output_directory: None,
-            schema_path: None
+            schema_path: None,
+            client_schema_paths: Vec::new(),
+            scalars: BTreeMap::new(),
+            maybe_undefined: false
         }
     }
 
     /// Add a query to have the code generator generate a module for it.
-    /// This is currently opt-in for each file to prevent accidentally generating unneeded code,
-    /// but a directory based approach may be added later.
+    /// To add all queries in a directory, use `add_queries_from_dir`.
     pub fn add_query<T: AsRef<Path>>(mut self, query_path: T) -> Self {
         self.query_paths.push(query_path.as_ref().to_path_buf());
         self
     }
 
+    /// Add all queries in a directory and its subdirectories whose path relative to the directory
+    /// matches a glob pattern, e.g. `add_queries_from_dir("src/queries", "*.graphql")`.
+    /// Files added with `add_fragments` are skipped. Each query still gets a module named after
+    /// its file, so the file names must be unique across subdirectories.
+    pub fn add_queries_from_dir<T: AsRef<Path>, G: Into<String>>(
+        mut self,
+        query_dir: T,
+        glob: G
+    ) -> Self {
+        self.query_dirs
+            .push((query_dir.as_ref().to_path_buf(), glob.into()));
+        self
+    }
+
+    /// Add a file of fragments shared between queries. Each fragment in it is generated once into
+    /// a `fragments` module in the output directory, and queries can spread it without defining
+    /// it themselves. A fragment defined in the query file takes precedence over a shared one
+    /// with the same name.
+    ///
+    /// Shared fragments can't use variables, since they're not tied to a single operation.
+    pub fn add_fragments<T: AsRef<Path>>(mut self, fragments_path: T) -> Self {
+        self.fragment_paths
+            .push(fragments_path.as_ref().to_path_buf());
+        self
+    }
+
     /// A comma-separated list of derives to add to the generated `Variables` and input structs.
     /// The default derives are `Serialize` and `Clone`, with `Deserialize` added if the target
     /// arch is `wasm32`. Adding these here won't break anything, but it's redundant.
@@ -160,6 +195,45 @@ impl CodegenBuilder {
         self
     }
 
+    /// Add a client-only schema extension file in `.graphql` format. The types and type extensions
+    /// (e.g. `extend type Query { selectedTab: String! }`) in it are merged into the schema, so
+    /// queries can select local fields by marking them with the `@client` directive.
+    /// These fields are resolved by the `LocalStateExchange` instead of the server.
+    pub fn add_client_schema<T: AsRef<Path>>(mut self, client_schema_path: T) -> Self {
+        self.client_schema_paths
+            .push(client_schema_path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Map a custom scalar to a Rust type, e.g.
+    /// `with_scalar("DateTime", "chrono::DateTime<chrono::Utc>")`. The type must be
+    /// (de)serializable and is referred to by path, so use a path that's valid from the output
+    /// directory's module, like one starting with `crate::`.
+    ///
+    /// Mappings for some common scalars can be enabled with cargo features instead:
+    ///
+    /// * `chrono` - `DateTime` and `Date` to `chrono::DateTime<chrono::Utc>` and `chrono::NaiveDate`
+    /// * `uuid` - `UUID` to `uuid::Uuid`
+    /// * `json` - `JSON` to `serde_json::Value`
+    /// * `bigint` - `BigInt` to `String`
+    /// * `decimal` - `Decimal` to `rust_decimal::Decimal`
+    /// * `url` - `URL` to `url::Url`
+    ///
+    /// Codegen fails if a query uses a custom scalar that isn't mapped.
+    pub fn with_scalar<N: Into<String>, T: Into<String>>(mut self, name: N, rust_type: T) -> Self {
+        self.scalars.insert(name.into(), rust_type.into());
+        self
+    }
+
+    /// Use `artemis::MaybeUndefined` instead of `Option` for nullable variables and input object
+    /// fields. It can be `Undefined` as well as `Null`, so a field can be left out of the request
+    /// instead of being sent as `null`, e.g. to leave it unchanged in a partial update.
+    /// Undefined is the default, and the fields are optional in the TypeScript definitions.
+    pub fn with_maybe_undefined(mut self, maybe_undefined: bool) -> Self {
+        self.maybe_undefined = maybe_undefined;
+        self
+    }
+
     /// Introspect a schema from a remote server. This will download the introspection result
     /// and save it in a temporary schema file in the `OUT_DIR`.
     /// Returns an `IntrospectionError` if the request fails for any reason.
@@ -204,6 +278,7 @@ impl CodegenBuilder {
         }
 
         let schema_path = self.schema_path.unwrap();
+
         let output_directory: PathBuf = self
             .output_directory
             .map(Ok)
@@ -214,15 +289,34 @@ impl CodegenBuilder {
                 )
             })?;
 
+        let mut query_paths = self.query_paths;
+        for (query_dir, glob) in &self.query_dirs {
+            let pattern = glob::Pattern::new(glob).map_err(|e| {
+                BuildError::ArgumentError(format!("Invalid glob pattern `{}`: {}", glob, e))
+            })?;
+            watch_query_dir(query_dir, &output_directory)?;
+            for query_path in find_files(query_dir, &pattern)? {
+                if !self.fragment_paths.iter().any(|path| same_file(path, &query_path))
+                    && !query_paths.iter().any(|path| same_file(path, &query_path))
+                {
+                    query_paths.push(query_path);
+                }
+            }
+        }
+
         let params = CodegenParams {
             schema_path,
+            client_schema_paths: self.client_schema_paths,
+            fragment_paths: self.fragment_paths,
             selected_operation: None,
             variables_derives: self.variable_derives.clone(),
             response_derives: self.response_derives.clone(),
             deprecation_strategy: self.deprecation_strategy.clone(),
+            scalars: self.scalars,
+            maybe_undefined: self.maybe_undefined,
             output_directory
         };
-        generate_code(self.query_paths, params)?;
+        generate_code(query_paths, params)?;
         Ok(())
     }
 }
@@ -230,10 +324,14 @@ impl CodegenBuilder {
 #[derive(Debug)]
 pub(crate) struct CodegenParams {
     pub schema_path: PathBuf,
+    pub client_schema_paths: Vec<PathBuf>,
+    pub fragment_paths: Vec<PathBuf>,
     pub selected_operation: Option<String>,
     pub variables_derives: Option<String>,
     pub response_derives: Option<String>,
     pub deprecation_strategy: Option<DeprecationStrategy>,
+    pub scalars: BTreeMap<String, String>,
+    pub maybe_undefined: bool,
     pub output_directory: PathBuf
 }
 
@@ -245,11 +343,24 @@ pub(crate) fn generate_code(
         variables_derives,
         response_derives,
         deprecation_strategy,
+        scalars,
+        maybe_undefined,
         output_directory,
         schema_path,
+        client_schema_paths,
+        fragment_paths,
         selected_operation
     } = params;
 
+    // Only rerun the build script when one of its inputs changes
+    for path in std::iter::once(&schema_path)
+        .chain(&client_schema_paths)
+        .chain(&fragment_paths)
+        .chain(&query_paths)
+    {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+
     let mut options = GraphQLClientCodegenOptions::new(CodegenMode::Cli);
 
     options.set_module_visibility(
@@ -275,9 +386,38 @@ pub(crate) fn generate_code(
         options.set_deprecation_strategy(deprecation_strategy);
     }
 
+    for client_schema_path in client_schema_paths {
+        options.add_client_schema_file(client_schema_path);
+    }
+
+    for (name, rust_type) in scalars {
+        options.add_scalar(name, rust_type);
+    }
+
+    for fragment_path in fragment_paths.iter() {
+        options.add_fragment_file(fragment_path.clone());
+    }
+
+    options.maybe_undefined = maybe_undefined;
+
+    // Every query uses the same enums and input objects, so values can be passed between them.
+    options.shared_types = true;
+
     let mut all_queries = Vec::new();
     let mut modules = Vec::new();
 
+    let types = generate_types_token_stream(&query_paths, &schema_path, options.clone())?;
+    let dest_file_path: PathBuf = output_directory.join("types").with_extension("rs");
+    write_if_changed(&dest_file_path, &types.to_string())?;
+    modules.push("types".to_string());
+
+    if !fragment_paths.is_empty() {
+        let fragments = generate_fragments_token_stream(&schema_path, options.clone())?;
+        let dest_file_path: PathBuf = output_directory.join("fragments").with_extension("rs");
+        write_if_changed(&dest_file_path, &fragments.to_string())?;
+        modules.push("fragments".to_string());
+    }
+
     for query_path in query_paths {
         let (module, variants) =
             generate_module_token_stream(query_path.clone(), &schema_path, options.clone())?;
@@ -293,20 +433,129 @@ pub(crate) fn generate_code(
         })?;
         let module_name = query_file_name.clone().into_string().unwrap();
         let module_name = module_name.splitn(2, '.').next().unwrap().to_string();
+        if modules.contains(&module_name) {
+            return Err(BuildError::ArgumentError(format!(
+                "More than one module named `{}`. Query file names must be unique and can't be \
+                 `types` or `fragments`.",
+                module_name
+            )));
+        }
         modules.push(module_name);
 
         let dest_file_path: PathBuf = output_directory.join(query_file_name).with_extension("rs");
-
-        let mut file = File::create(dest_file_path)?;
-        write!(file, "{}", module)?;
+        write_if_changed(&dest_file_path, &module)?;
 
         all_queries.extend(variants)
     }
 
     let tokens = generate_root_token_stream(modules, all_queries, options);
     let dest_file_path: PathBuf = output_directory.join("mod").with_extension("rs");
-    let mut file = File::create(dest_file_path)?;
-    write!(file, "{}", tokens.to_string())?;
+    write_if_changed(&dest_file_path, &tokens.to_string())?;
 
     Ok(())
 }
+
+/// Writes a generated file, unless it already has the same content. Rewriting it would change its
+/// modification time and cause the crate to be recompiled for nothing.
+fn write_if_changed(path: &Path, contents: &str) -> Result<(), std::io::Error> {
+    match fs::read_to_string(path) {
+        Ok(existing) if existing == contents => Ok(()),
+        _ => fs::write(path, contents)
+    }
+}
+
+/// Recursively finds the files in `dir` whose path relative to it matches `pattern`, sorted so the
+/// generated code doesn't depend on the directory listing order.
+fn find_files(dir: &Path, pattern: &glob::Pattern) -> Result<Vec<PathBuf>, std::io::Error> {
+    fn visit(
+        root: &Path,
+        dir: &Path,
+        pattern: &glob::Pattern,
+        files: &mut Vec<PathBuf>
+    ) -> Result<(), std::io::Error> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                visit(root, &path, pattern, files)?;
+            } else if path
+                .strip_prefix(root)
+                .map(|relative| pattern.matches_path(relative))
+                .unwrap_or(false)
+            {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    visit(dir, dir, pattern, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Reruns the build script when a query is added to `dir`. If the generated files are written to
+/// it too, only its other entries are watched, since Cargo would otherwise see every run's output
+/// as a change. New queries are then only picked up with the next change to one of them.
+fn watch_query_dir(dir: &Path, output_directory: &Path) -> Result<(), std::io::Error> {
+    let output_directory = fs::canonicalize(output_directory).ok();
+    let contains_output = match (&output_directory, fs::canonicalize(dir)) {
+        (Some(output_directory), Ok(dir)) => output_directory.starts_with(dir),
+        _ => false
+    };
+    if !contains_output {
+        println!("cargo:rerun-if-changed={}", dir.display());
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_output = match fs::canonicalize(&path) {
+            Ok(path) => {
+                Some(&path) == output_directory.as_ref()
+                    || (path.parent() == output_directory.as_deref()
+                        && path.extension() == Some("rs".as_ref()))
+            }
+            Err(_) => false
+        };
+        if !is_output {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Whether two paths point to the same file, falling back to comparing them as written if either
+/// doesn't exist.
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_queries_matching_glob() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../artemis-test/src/queries");
+        let pattern = glob::Pattern::new("get_*.graphql").unwrap();
+
+        let files: Vec<_> = find_files(&dir, &pattern)
+            .unwrap()
+            .into_iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+
+        assert_eq!(
+            files,
+            vec![
+                "get_conference.graphql",
+                "get_conference_summary.graphql",
+                "get_conferences.graphql"
+            ]
+        );
+    }
+}
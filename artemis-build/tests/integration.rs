@@ -33,3 +33,38 @@ fn check_code_gen() {
     assert_eq!(query.operation_name, "GetConference");
     // assert_eq!(meta.key, 1354603040u32); Apparently this is OS specific
 }
+
+#[test]
+fn check_shared_fragments() {
+    use artemis_test::{
+        fragments::ConferenceSummary,
+        get_conference_summary::get_conference_summary::{
+            GetConferenceSummaryConference, ResponseData
+        }
+    };
+
+    let summary = ConferenceSummary {
+        id: "1".to_string(),
+        name: "test_conf_name".to_string(),
+        city: None
+    };
+    let response_data = ResponseData {
+        conference: Some(GetConferenceSummaryConference {
+            conference_summary: summary.clone()
+        })
+    };
+
+    assert_eq!(
+        response_data.conference.unwrap().conference_summary,
+        summary
+    );
+
+    let (query, _) = artemis_test::get_conference_summary::GetConferenceSummary::build_query(
+        artemis_test::get_conference_summary::get_conference_summary::Variables {
+            id: "1".to_string()
+        }
+    );
+    assert!(query
+        .query
+        .contains("fragment ConferenceSummary on Conference"));
+}
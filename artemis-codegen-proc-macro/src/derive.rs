@@ -0,0 +1,129 @@
+use artemis_codegen::{generate_module_token_stream, CodegenMode, GraphQLClientCodegenOptions};
+use proc_macro2::TokenStream;
+use std::path::PathBuf;
+use syn::{Attribute, DeriveInput, Lit, Meta, NestedMeta};
+
+/// The options passed to the derive with `#[artemis(...)]`.
+#[derive(Default)]
+struct Attributes {
+    schema_path: Option<String>,
+    query_path: Option<String>,
+    variables_derives: Option<String>,
+    response_derives: Option<String>
+}
+
+impl Attributes {
+    fn parse(attr: &Attribute, attributes: &mut Attributes) -> syn::Result<()> {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    r#"expected `#[artemis(schema_path = "...", query_path = "...")]`"#
+                ))
+            }
+        };
+
+        for nested in list.nested {
+            let name_value = match nested {
+                NestedMeta::Meta(Meta::NameValue(name_value)) => name_value,
+                nested => {
+                    return Err(syn::Error::new_spanned(
+                        nested,
+                        "expected a `key = \"value\"` pair"
+                    ))
+                }
+            };
+            let value = match &name_value.lit {
+                Lit::Str(value) => value.value(),
+                lit => return Err(syn::Error::new_spanned(lit, "expected a string literal"))
+            };
+            let key = name_value
+                .path
+                .get_ident()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            let field = match key.as_str() {
+                "schema_path" => &mut attributes.schema_path,
+                "query_path" => &mut attributes.query_path,
+                "variables_derives" => &mut attributes.variables_derives,
+                "response_derives" => &mut attributes.response_derives,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &name_value.path,
+                        "unknown attribute, expected one of `schema_path`, `query_path`, \
+                         `variables_derives` or `response_derives`"
+                    ))
+                }
+            };
+            if field.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &name_value.path,
+                    format!("duplicate attribute `{}`", key)
+                ));
+            }
+            *field = Some(value);
+        }
+
+        Ok(())
+    }
+}
+
+/// Paths in the attribute are relative to the root of the crate using the derive.
+fn resolve_path(path: &str) -> PathBuf {
+    let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(root).join(path)
+}
+
+pub(crate) fn derive_graphql_query(input: DeriveInput) -> syn::Result<TokenStream> {
+    let artemis_attrs: Vec<_> = input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("artemis"))
+        .collect();
+
+    let mut attributes = Attributes::default();
+    for attr in &artemis_attrs {
+        Attributes::parse(attr, &mut attributes)?;
+    }
+
+    // Errors are reported on the attribute, since they're caused by the files it references.
+    let error = |message: String| match artemis_attrs.first() {
+        Some(attr) => syn::Error::new_spanned(attr, message),
+        None => syn::Error::new(input.ident.span(), message)
+    };
+    let missing = |name: &str| {
+        error(format!(
+            r#"missing `{}`, add it with `#[artemis({} = "...")]`"#,
+            name, name
+        ))
+    };
+    let schema_path = attributes
+        .schema_path
+        .ok_or_else(|| missing("schema_path"))?;
+    let query_path = attributes.query_path.ok_or_else(|| missing("query_path"))?;
+    let schema_path = resolve_path(&schema_path);
+    let query_path = resolve_path(&query_path);
+
+    let mut options = GraphQLClientCodegenOptions::new(CodegenMode::Derive);
+    options.set_struct_ident(input.ident.clone());
+    options.set_operation_name(input.ident.to_string());
+    options.set_module_visibility(input.vis.clone());
+    options.set_query_file(query_path.clone());
+    options.set_schema_file(schema_path.clone());
+    options.wasm_bindgen = false;
+    options.crate_scalars = true;
+    if let Some(variables_derives) = attributes.variables_derives {
+        options.set_variables_derives(variables_derives);
+    }
+    // `GraphQLQuery::ResponseData` has to be serializable, duplicate derives are removed by codegen.
+    let response_derives = match attributes.response_derives {
+        Some(response_derives) => format!("Serialize,{}", response_derives),
+        None => "Serialize".to_string()
+    };
+    options.set_response_derives(response_derives);
+
+    generate_module_token_stream(query_path, &schema_path, options)
+        .map(|(tokens, _)| tokens)
+        .map_err(|e| error(e.to_string()))
+}
@@ -1,5 +1,11 @@
-use artemis_codegen::wasm::{wasm_client as generate, WasmClientInput};
+use artemis_codegen::{
+    inline::{inline_query, InlineQueryInput},
+    wasm::{wasm_client as generate, WasmClientInput}
+};
 use proc_macro::TokenStream;
+use syn::DeriveInput;
+
+mod derive;
 
 /// Generate a WASM client wrapper that gets exported in your WASM binary
 /// This is required because WASM doesn't support generics and the regular
@@ -35,3 +41,86 @@ pub fn wasm_client(tokens: TokenStream) -> TokenStream {
     let input = syn::parse_macro_input!(tokens as WasmClientInput);
     generate(input).into()
 }
+
+/// Generate the query module and `GraphQLQuery` implementation for a struct, like
+/// [artemis-build](../artemis_build/index.html) does, but without a build script.
+///
+/// The struct name must match the name of an operation in the query file. The module is named
+/// after it in snake case and has the same visibility as the struct.
+///
+/// # Attributes
+///
+/// * `schema_path` - The schema in JSON or GraphQL format. Required.
+/// * `query_path` - The file containing the operation. Required.
+/// * `variables_derives` - A comma-separated list of additional derives for `Variables` and inputs.
+/// * `response_derives` - A comma-separated list of additional derives for `ResponseData` and outputs.
+///
+/// Paths are relative to the root of the crate (the directory containing `Cargo.toml`).
+/// The generated code uses `serde`, so it must be a dependency of your crate.
+///
+/// # Usage
+///
+/// ```ignore
+/// use artemis::GraphQLQuery;
+///
+/// #[derive(GraphQLQuery)]
+/// #[artemis(
+///     schema_path = "schema.json",
+///     query_path = "src/queries/get_conference.graphql",
+///     response_derives = "Debug,PartialEq"
+/// )]
+/// pub struct GetConference;
+///
+/// let result = client
+///     .query(GetConference, get_conference::Variables { id: "1".to_string() })
+///     .await?;
+/// ```
+#[proc_macro_derive(GraphQLQuery, attributes(artemis))]
+pub fn derive_graphql_query(tokens: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(tokens as DeriveInput);
+    derive::derive_graphql_query(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Generate the query modules for a GraphQL document written inline, instead of in a `.graphql`
+/// file added to [artemis-build](../artemis_build/index.html). The output is the same as for a
+/// query file generated by `CodegenBuilder`: a public struct for each operation, along with a
+/// module containing its `Variables` and `ResponseData`.
+///
+/// The document is parsed and checked against the schema at compile time. Errors point at the
+/// offending token in the macro input.
+///
+/// # Options
+///
+/// Options come before the document, each followed by a comma.
+///
+/// * `schema` - The schema in JSON or GraphQL format, relative to the root of the crate. Required.
+/// * `variables_derives` - A comma-separated list of additional derives for `Variables` and inputs.
+/// * `response_derives` - A comma-separated list of additional derives for `ResponseData` and outputs.
+///
+/// Since the document is tokenized as Rust code, comments must use `//` and block strings
+/// (`"""`) aren't supported.
+///
+/// # Usage
+///
+/// ```ignore
+/// artemis::graphql!(
+///     schema = "schema.json",
+///     query GetConference($id: Long!) {
+///         conference(id: $id) {
+///             id
+///             name
+///         }
+///     }
+/// );
+///
+/// let result = client
+///     .query(GetConference, get_conference::Variables { id: "1".to_string() })
+///     .await?;
+/// ```
+#[proc_macro]
+pub fn graphql(tokens: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(tokens as InlineQueryInput);
+    inline_query(input).into()
+}
@@ -0,0 +1,58 @@
+use artemis::{exchange::OperationType, GraphQLQuery};
+
+type Long = String;
+
+#[derive(GraphQLQuery)]
+#[artemis(
+    schema_path = "../artemis-test/api-schema.json",
+    query_path = "../artemis-test/src/queries/get_conference.graphql",
+    response_derives = "Debug,PartialEq"
+)]
+pub struct GetConference;
+
+#[derive(GraphQLQuery)]
+#[artemis(schema_path = "../artemis-test/api-schema.json")]
+#[artemis(query_path = "../artemis-test/src/queries/add_conference.graphql")]
+struct AddConference;
+
+#[test]
+fn test_derive_query() {
+    let variables = get_conference::Variables {
+        id: "1".to_string()
+    };
+    let (body, meta) = GetConference::build_query(variables);
+
+    assert_eq!(body.operation_name, "GetConference");
+    assert_eq!(
+        body.query,
+        "query GetConference($id:Long!){conference(id:$id){id name city talks{id title speakers{name}}}}"
+    );
+    assert_eq!(meta.operation_type, OperationType::Query);
+    assert!(meta.involved_types.contains(&"Conference".into()));
+
+    let data: get_conference::ResponseData = serde_json::from_str(
+        r#"{
+            "conference": {
+                "id": "1",
+                "name": "Nextbuild 2018",
+                "city": "Eindhoven",
+                "talks": []
+            }
+        }"#
+    )
+    .unwrap();
+    let conference = data.conference.unwrap();
+    assert_eq!(conference.name, "Nextbuild 2018");
+    assert_eq!(conference.talks, Some(Vec::new()));
+}
+
+#[test]
+fn test_derive_mutation() {
+    let (body, meta) = AddConference::build_query(add_conference::Variables {
+        name: "Test Conference".to_string(),
+        city: None
+    });
+
+    assert_eq!(body.operation_name, "AddConference");
+    assert_eq!(meta.operation_type, OperationType::Mutation);
+}
@@ -0,0 +1,75 @@
+use artemis::codegen::{FieldSelector, QueryInfo};
+
+artemis::graphql!(
+    schema = "../artemis_codegen/src/tests/star_wars_schema.graphql",
+    response_derives = "Debug,PartialEq",
+    query HumanQuery($withDetails: Boolean!, $skipPlanet: Boolean) {
+        human(id: "1000") {
+            name @include(if: $withDetails)
+            homePlanet @skip(if: $skipPlanet)
+            ...HumanDetails @include(if: $withDetails)
+        }
+    }
+
+    fragment HumanDetails on Human {
+        id
+    }
+);
+
+fn human_fields(selection: &[FieldSelector]) -> Vec<&str> {
+    match &selection[0] {
+        FieldSelector::Object(name, _, typename, selection)
+            if name == "human" && typename == "Human" =>
+        {
+            selection
+                .iter()
+                .map(|selector| match selector {
+                    FieldSelector::Scalar(name, _)
+                    | FieldSelector::Object(name, _, _, _)
+                    | FieldSelector::Union(name, _, _) => name.as_ref()
+                })
+                .collect()
+        }
+        selector => panic!("expected an object selector, got {:?}", selector)
+    }
+}
+
+#[test]
+fn test_directives_response() {
+    use human_query::*;
+
+    let data: ResponseData = serde_json::from_str(
+        r#"{
+            "human": {
+                "homePlanet": null
+            }
+        }"#
+    )
+    .unwrap();
+    let human = data.human.unwrap();
+
+    assert_eq!(human.name, None);
+    assert_eq!(human.home_planet, Some(None));
+    assert_eq!(human.human_details, None);
+
+    // Skipped fields are left out when serializing, so the cache sees the same shape.
+    let json = serde_json::to_value(&human).unwrap();
+    assert_eq!(json, serde_json::json!({ "homePlanet": null }));
+}
+
+#[test]
+fn test_directives_selection() {
+    use human_query::*;
+
+    let selection = ResponseData::selection(&Variables {
+        with_details: true,
+        skip_planet: None
+    });
+    assert_eq!(human_fields(&selection), vec!["name", "homePlanet", "id"]);
+
+    let selection = ResponseData::selection(&Variables {
+        with_details: false,
+        skip_planet: Some(false)
+    });
+    assert_eq!(human_fields(&selection), vec!["homePlanet"]);
+}
@@ -0,0 +1,87 @@
+use artemis::{exchange::OperationType, GraphQLQuery};
+
+type Long = String;
+
+artemis::graphql!(
+    schema = "../artemis-test/api-schema.json",
+    response_derives = "Debug,PartialEq",
+    query GetConferenceName($id: Long!) {
+        conference(id: $id) {
+            id
+            name
+        }
+    }
+
+    mutation AddConference($name: String!, $city: String = "Test City") {
+        addConference(conference: { name: $name, city: $city }) {
+            id
+        }
+    }
+
+    mutation AddBuildConference($build: String!, $builder: String) {
+        addConference(conference: { name: $build, city: $builder }) {
+            id
+        }
+    }
+);
+
+#[test]
+fn test_inline_query() {
+    let (body, meta) = GetConferenceName::build_query(get_conference_name::Variables {
+        id: "1".to_string()
+    });
+
+    assert_eq!(body.operation_name, "GetConferenceName");
+    assert_eq!(
+        body.query,
+        "query GetConferenceName($id:Long!){conference(id:$id){id name}}"
+    );
+    assert_eq!(meta.operation_type, OperationType::Query);
+
+    let data: get_conference_name::ResponseData =
+        serde_json::from_str(r#"{ "conference": { "id": "1", "name": "Nextbuild 2018" } }"#)
+            .unwrap();
+    assert_eq!(data.conference.unwrap().name, "Nextbuild 2018");
+}
+
+#[test]
+fn test_inline_mutation() {
+    let (body, meta) = AddConference::build_query(add_conference::Variables {
+        name: "Test Conference".to_string(),
+        city: None
+    });
+
+    assert_eq!(body.operation_name, "AddConference");
+    // Only the operation itself is sent, not the other ones in the document
+    assert!(!body.query.contains("GetConferenceName"));
+    assert_eq!(meta.operation_type, OperationType::Mutation);
+}
+
+#[test]
+fn test_variables_builder() {
+    let variables = add_conference::Variables::builder()
+        .name("Test Conference")
+        .build()
+        .unwrap();
+
+    assert_eq!(variables.name, "Test Conference");
+    assert_eq!(variables.city, Some("Test City".to_string()));
+
+    let error = add_conference::Variables::builder().build().err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "missing required field `name` of `Variables`"
+    );
+}
+
+#[test]
+fn test_builder_setter_collisions() {
+    let variables = add_build_conference::Variables::builder()
+        .set_build("Build Conference")
+        .set_builder("Build City".to_string())
+        .build()
+        .unwrap();
+
+    assert_eq!(variables.build, "Build Conference");
+    assert_eq!(variables.builder, Some("Build City".to_string()));
+}
@@ -0,0 +1,117 @@
+use artemis::codegen::{FieldSelector, QueryInfo};
+
+artemis::graphql!(
+    schema = "../artemis_codegen/src/tests/star_wars_schema.graphql",
+    response_derives = "Debug,PartialEq",
+    query HeroQuery {
+        hero {
+            __typename
+            name
+            ... on Character {
+                id
+            }
+            ... on Droid {
+                primaryFunction
+            }
+            ...HumanFields
+        }
+        human(id: "1000") {
+            name
+            ... on Human {
+                homePlanet
+            }
+            ...CharacterFields
+        }
+    }
+
+    fragment HumanFields on Human {
+        homePlanet
+    }
+
+    fragment CharacterFields on Character {
+        __typename
+        ... on Human {
+            height
+        }
+        ... on Droid {
+            primaryFunction
+        }
+    }
+);
+
+fn field_names(selection: &[FieldSelector]) -> Vec<&str> {
+    selection
+        .iter()
+        .map(|selector| match selector {
+            FieldSelector::Scalar(name, _)
+            | FieldSelector::Object(name, _, _, _)
+            | FieldSelector::Union(name, _, _) => name.as_ref()
+        })
+        .collect()
+}
+
+#[test]
+fn test_inline_fragments_response() {
+    use hero_query::*;
+
+    let data: ResponseData = serde_json::from_str(
+        r#"{
+            "hero": {
+                "__typename": "Droid",
+                "name": "R2-D2",
+                "id": "2001",
+                "primaryFunction": "Astromech"
+            },
+            "human": {
+                "__typename": "Human",
+                "name": "Luke Skywalker",
+                "homePlanet": "Tatooine",
+                "height": 1.72
+            }
+        }"#
+    )
+    .unwrap();
+
+    let hero = data.hero.unwrap();
+    assert_eq!(hero.id, "2001");
+    assert_eq!(
+        hero.on,
+        HeroQueryHeroOn::Droid(HeroQueryHeroOnDroid {
+            primary_function: Some("Astromech".to_string())
+        })
+    );
+
+    let human = data.human.unwrap();
+    assert_eq!(human.home_planet, Some("Tatooine".to_string()));
+    assert_eq!(
+        human.character_fields.on,
+        CharacterFieldsOn::Human(CharacterFieldsOnHuman { height: Some(1.72) })
+    );
+}
+
+#[test]
+fn test_inline_fragments_selection() {
+    let selection = hero_query::ResponseData::selection(&hero_query::Variables);
+
+    let hero_selection = match &selection[0] {
+        FieldSelector::Union(name, _, selection) if name == "hero" => selection,
+        selector => panic!("expected a union selector, got {:?}", selector)
+    };
+    assert_eq!(
+        field_names(&hero_selection("Droid")),
+        vec!["name", "id", "primaryFunction"]
+    );
+    assert_eq!(
+        field_names(&hero_selection("Human")),
+        vec!["name", "id", "homePlanet"]
+    );
+
+    match &selection[1] {
+        FieldSelector::Object(name, _, typename, selection)
+            if name == "human" && typename == "Human" =>
+        {
+            assert_eq!(field_names(selection), vec!["name", "homePlanet", "height"])
+        }
+        selector => panic!("expected an object selector, got {:?}", selector)
+    }
+}
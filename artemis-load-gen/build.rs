@@ -19,6 +19,7 @@ fn generate_code() -> Result<(), Box<dyn Error>> {
         .add_query(query("get_conference.graphql"))
         .add_query(query("add_conference.graphql"))
         .with_schema("src/api-schema.json")
+        .with_scalar("Long", "String")
         .build()?;
 
     Ok(())
@@ -21,8 +21,6 @@ use tokio::runtime::Runtime;
 
 mod queries;
 
-pub(crate) type Long = String;
-
 struct DummyFetchExchange;
 
 impl<TNext: Exchange> ExchangeFactory<TNext> for DummyFetchExchange {
@@ -111,9 +111,9 @@ fn make_read_op<Q: GraphQLQuery>(
             extra_headers: None
         },
         query: QueryBody {
-            query,
+            query: query.into(),
             variables,
-            operation_name: ""
+            operation_name: "".into()
         }
     }
 }
@@ -42,12 +42,12 @@ pub mod books {
         #[allow(unused_variables)]
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![
-                FieldSelector::Scalar("id", String::new()),
-                FieldSelector::Scalar("title", String::new()),
-                FieldSelector::Scalar("published", String::new()),
-                FieldSelector::Scalar("genre", String::new()),
-                FieldSelector::Scalar("rating", String::new()),
-                FieldSelector::Scalar("release", String::new()),
+                FieldSelector::Scalar("id".into(), String::new()),
+                FieldSelector::Scalar("title".into(), String::new()),
+                FieldSelector::Scalar("published".into(), String::new()),
+                FieldSelector::Scalar("genre".into(), String::new()),
+                FieldSelector::Scalar("rating".into(), String::new()),
+                FieldSelector::Scalar("release".into(), String::new()),
             ]
         }
     }
@@ -60,9 +60,9 @@ pub mod books {
     impl QueryInfo<Variables> for ResponseData {
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![FieldSelector::Object(
-                "books",
+                "books".into(),
                 String::new(),
-                "Book",
+                "Book".into(),
                 Book::selection(variables)
             )]
         }
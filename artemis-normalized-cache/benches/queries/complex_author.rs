@@ -51,9 +51,9 @@ pub mod complex_author {
     impl QueryInfo<Variables> for ResponseData {
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![FieldSelector::Object(
-                "authors",
+                "authors".into(),
                 String::new(),
-                "Author",
+                "Author".into(),
                 ComplexAuthor::selection(variables)
             )]
         }
@@ -70,13 +70,13 @@ pub mod complex_author {
     impl ComplexAuthor {
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![
-                FieldSelector::Scalar("id", String::new()),
-                FieldSelector::Scalar("name", String::new()),
-                FieldSelector::Scalar("recognised", String::new()),
+                FieldSelector::Scalar("id".into(), String::new()),
+                FieldSelector::Scalar("name".into(), String::new()),
+                FieldSelector::Scalar("recognised".into(), String::new()),
                 FieldSelector::Object(
-                    "book",
+                    "book".into(),
                     String::new(),
-                    "Book",
+                    "Book".into(),
                     ComplexBook::selection(variables)
                 ),
             ]
@@ -94,13 +94,13 @@ pub mod complex_author {
     impl ComplexBook {
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![
-                FieldSelector::Scalar("id", String::new()),
-                FieldSelector::Scalar("published", String::new()),
-                FieldSelector::Scalar("name", String::new()),
+                FieldSelector::Scalar("id".into(), String::new()),
+                FieldSelector::Scalar("published".into(), String::new()),
+                FieldSelector::Scalar("name".into(), String::new()),
                 FieldSelector::Object(
-                    "review",
+                    "review".into(),
                     String::new(),
-                    "Review",
+                    "Review".into(),
                     ComplexReview::selection(variables)
                 ),
             ]
@@ -118,13 +118,13 @@ pub mod complex_author {
     impl ComplexReview {
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![
-                FieldSelector::Scalar("id", String::new()),
-                FieldSelector::Scalar("score", String::new()),
-                FieldSelector::Scalar("name", String::new()),
+                FieldSelector::Scalar("id".into(), String::new()),
+                FieldSelector::Scalar("score".into(), String::new()),
+                FieldSelector::Scalar("name".into(), String::new()),
                 FieldSelector::Object(
-                    "reviewer",
+                    "reviewer".into(),
                     String::new(),
-                    "Reviewer",
+                    "Reviewer".into(),
                     ComplexReviewer::selection(variables)
                 ),
             ]
@@ -141,9 +141,9 @@ pub mod complex_author {
     impl ComplexReviewer {
         fn selection(_variables: &Variables) -> Vec<FieldSelector> {
             vec![
-                FieldSelector::Scalar("id", String::new()),
-                FieldSelector::Scalar("name", String::new()),
-                FieldSelector::Scalar("verified", String::new()),
+                FieldSelector::Scalar("id".into(), String::new()),
+                FieldSelector::Scalar("name".into(), String::new()),
+                FieldSelector::Scalar("verified".into(), String::new()),
             ]
         }
     }
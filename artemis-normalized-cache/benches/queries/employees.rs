@@ -39,10 +39,10 @@ pub mod employees {
         #[allow(unused_variables)]
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![
-                FieldSelector::Scalar("id", String::new()),
-                FieldSelector::Scalar("name", String::new()),
-                FieldSelector::Scalar("dateOfBirth", String::new()),
-                FieldSelector::Scalar("origin", String::new()),
+                FieldSelector::Scalar("id".into(), String::new()),
+                FieldSelector::Scalar("name".into(), String::new()),
+                FieldSelector::Scalar("dateOfBirth".into(), String::new()),
+                FieldSelector::Scalar("origin".into(), String::new()),
             ]
         }
     }
@@ -55,9 +55,9 @@ pub mod employees {
     impl QueryInfo<Variables> for ResponseData {
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![FieldSelector::Object(
-                "employees",
+                "employees".into(),
                 String::new(),
-                "Employee",
+                "Employee".into(),
                 Employee::selection(variables)
             )]
         }
@@ -38,10 +38,10 @@ pub mod stores {
         #[allow(unused_variables)]
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![
-                FieldSelector::Scalar("id", String::new()),
-                FieldSelector::Scalar("name", String::new()),
-                FieldSelector::Scalar("started", String::new()),
-                FieldSelector::Scalar("country", String::new()),
+                FieldSelector::Scalar("id".into(), String::new()),
+                FieldSelector::Scalar("name".into(), String::new()),
+                FieldSelector::Scalar("started".into(), String::new()),
+                FieldSelector::Scalar("country".into(), String::new()),
             ]
         }
     }
@@ -54,9 +54,9 @@ pub mod stores {
     impl QueryInfo<Variables> for ResponseData {
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![FieldSelector::Object(
-                "stores",
+                "stores".into(),
                 String::new(),
-                "Store",
+                "Store".into(),
                 Store::selection(variables)
             )]
         }
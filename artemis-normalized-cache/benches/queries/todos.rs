@@ -36,10 +36,10 @@ pub mod todos_query {
         #[allow(unused_variables)]
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![
-                FieldSelector::Scalar("id", String::new()),
-                FieldSelector::Scalar("text", String::new()),
-                FieldSelector::Scalar("complete", String::new()),
-                FieldSelector::Scalar("due", String::new()),
+                FieldSelector::Scalar("id".into(), String::new()),
+                FieldSelector::Scalar("text".into(), String::new()),
+                FieldSelector::Scalar("complete".into(), String::new()),
+                FieldSelector::Scalar("due".into(), String::new()),
             ]
         }
     }
@@ -52,9 +52,9 @@ pub mod todos_query {
     impl QueryInfo<Variables> for ResponseData {
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![FieldSelector::Object(
-                "todos",
+                "todos".into(),
                 String::new(),
-                "Todo",
+                "Todo".into(),
                 Todo::selection(variables)
             )]
         }
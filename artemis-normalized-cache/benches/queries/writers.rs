@@ -43,12 +43,12 @@ pub mod writers {
         #[allow(unused_variables)]
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![
-                FieldSelector::Scalar("id", String::new()),
-                FieldSelector::Scalar("name", String::new()),
-                FieldSelector::Scalar("amountOfBooks", String::new()),
-                FieldSelector::Scalar("interests", String::new()),
-                FieldSelector::Scalar("recognised", String::new()),
-                FieldSelector::Scalar("number", String::new()),
+                FieldSelector::Scalar("id".into(), String::new()),
+                FieldSelector::Scalar("name".into(), String::new()),
+                FieldSelector::Scalar("amountOfBooks".into(), String::new()),
+                FieldSelector::Scalar("interests".into(), String::new()),
+                FieldSelector::Scalar("recognised".into(), String::new()),
+                FieldSelector::Scalar("number".into(), String::new()),
             ]
         }
     }
@@ -61,9 +61,9 @@ pub mod writers {
     impl QueryInfo<Variables> for ResponseData {
         fn selection(variables: &Variables) -> Vec<FieldSelector> {
             vec![FieldSelector::Object(
-                "writers",
+                "writers".into(),
                 String::new(),
-                "Writer",
+                "Writer".into(),
                 Writer::selection(variables)
             )]
         }
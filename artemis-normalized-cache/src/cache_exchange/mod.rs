@@ -7,6 +7,7 @@ use crate::{
     HashSet
 };
 use artemis::{
+    devtools::DevtoolsEvent,
     exchange::{
         Client, Exchange, ExchangeFactory, ExchangeResult, Operation, OperationResult,
         OperationType
@@ -144,7 +145,11 @@ impl<TNext: Exchange> NormalizedCacheImpl<TNext> {
         } else {
             self.update_js::<Q>(extension, result.response.data.as_ref(), &mut dependencies);
         }
-        println!("Invalidated dependencies {:?}", dependencies);
+        client.emit_event(|| DevtoolsEvent::Invalidated {
+            key: query_key,
+            exchange: "NormalizedCacheExchange",
+            dependencies: dependencies.iter().cloned().collect()
+        });
         self.store.rerun_queries(dependencies, query_key, client);
     }
 
@@ -218,7 +223,11 @@ impl<TNext: Exchange> NormalizedCacheImpl<TNext> {
                     .write_query::<Q>(&result, variables, true, &mut dependencies)
                     .unwrap();
 
-                println!("Optimistic dependencies: {:?}", dependencies);
+                client.emit_event(|| DevtoolsEvent::Invalidated {
+                    key: query_key,
+                    exchange: "NormalizedCacheExchange",
+                    dependencies: dependencies.iter().cloned().collect()
+                });
                 self.store.rerun_queries(dependencies, query_key, client);
                 client.push_result(operation.key, Ok(result))
             }
@@ -297,12 +306,17 @@ impl<TNext: Exchange> Exchange for NormalizedCacheImpl<TNext> {
             self.write_updater::<Q>(&operation);
             let cached = self.store.read_query::<Q>(&operation, ptr::null_mut());
             if let Some(cached) = cached {
+                client.emit_event(|| DevtoolsEvent::CacheHit {
+                    key: operation.key,
+                    exchange: "NormalizedCacheExchange"
+                });
                 let response = OperationResult {
                     key: operation.key,
                     response: Response {
                         debug_info: Some(DebugInfo {
                             did_dedup: false,
-                            source: ResultSource::Cache
+                            source: ResultSource::Cache,
+                            status: None
                         }),
                         data: Some(cached),
                         errors: None
@@ -311,6 +325,10 @@ impl<TNext: Exchange> Exchange for NormalizedCacheImpl<TNext> {
                 };
                 Ok(response)
             } else {
+                client.emit_event(|| DevtoolsEvent::CacheMiss {
+                    key: operation.key,
+                    exchange: "NormalizedCacheExchange"
+                });
                 self.run_optimistic_query::<Q, _>(&operation, &client, extension);
                 let variables: Q::Variables = operation.query.variables.clone();
                 let res = self.next.run::<Q, _>(operation, client.clone()).await?;
@@ -335,4 +353,9 @@ impl<TNext: Exchange> Exchange for NormalizedCacheImpl<TNext> {
             Ok(res)
         }
     }
+
+    fn reset(&self) {
+        self.store.clear();
+        self.next.reset();
+    }
 }
@@ -2,6 +2,7 @@ use crate::{
     cache_exchange::NormalizedCacheExchange, HashSet, NormalizedCacheExtension, QueryStore
 };
 use artemis::{
+    dynamic::{DynamicData, DynamicDocument, DynamicQuery, DynamicVariables},
     exchange::{
         Client, Exchange, ExchangeFactory, ExchangeResult, Operation, OperationMeta,
         OperationOptions, OperationResult
@@ -21,7 +22,14 @@ use artemis_test::{
 };
 use racetrack::{track_with, Tracker};
 use serde::de::DeserializeOwned;
-use std::{any::Any, sync::Arc};
+use serde_json::json;
+use std::{
+    any::Any,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc
+    }
+};
 
 fn make_op_with_key<Q: GraphQLQuery>(
     _query: Q,
@@ -125,7 +133,8 @@ fn make_result<Q: GraphQLQuery>(
         response: Response {
             debug_info: Some(DebugInfo {
                 source: ResultSource::Network,
-                did_dedup: false
+                did_dedup: false,
+                status: None
             }),
             errors: None,
             data: Some(data)
@@ -654,3 +663,63 @@ async fn follows_optimistic_on_initial_write() {
         .was_called_once()
         .with(push_data);
 }
+
+#[tokio::test]
+async fn normalizes_dynamic_queries() {
+    struct Fetch(Arc<AtomicUsize>);
+    #[async_trait]
+    impl Exchange for Fetch {
+        async fn run<Q: GraphQLQuery, C: Client>(
+            &self,
+            operation: Operation<Q::Variables>,
+            _client: C
+        ) -> ExchangeResult<Q::ResponseData> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let data = DynamicData(json!({
+                "conference": {
+                    "__typename": "Conference",
+                    "id": "1",
+                    "name": "test",
+                    "talks": [{ "__typename": "Talk", "id": "2", "title": "talk" }]
+                }
+            }));
+            make_result::<Q>(operation, Box::new(data))
+        }
+    }
+
+    let full = "query GetConference($id: ID!) {
+        conference(id: $id) { id name talks { id title } }
+    }";
+    let partial = "query GetConferenceName($id: ID!) { conference(id: $id) { id name } }";
+    let variables = json!({ "id": "1" });
+    let make_op = |document: &str| {
+        let document = Arc::new(DynamicDocument::parse(document, None).unwrap());
+        make_op(
+            DynamicQuery,
+            DynamicVariables::new(document, variables.clone())
+        )
+    };
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let client = DummyClient {
+        tracker: Tracker::new()
+    };
+    let exchange = NormalizedCacheExchange::new().build(Fetch(calls.clone()));
+
+    exchange
+        .run::<DynamicQuery, _>(make_op(full), client.clone())
+        .await
+        .unwrap();
+    let result = exchange
+        .run::<DynamicQuery, _>(make_op(partial), client.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        result.response.data,
+        Some(DynamicData(json!({
+            "conference": { "__typename": "Conference", "id": "1", "name": "test" }
+        })))
+    );
+}
@@ -5,6 +5,7 @@ use fnv::FnvBuildHasher;
 use parking_lot::Mutex;
 use serde_json::Value;
 use std::{
+    borrow::Cow,
     fmt,
     hash::Hash,
     mem::ManuallyDrop,
@@ -16,13 +17,13 @@ use std::{
 };
 
 #[derive(Hash, Eq, PartialEq, Debug)]
-pub struct FieldKey(pub &'static str, pub String);
+pub struct FieldKey(pub Cow<'static, str>, pub String);
 #[derive(Debug)]
-pub struct RefFieldKey<'a>(pub &'static str, pub &'a String);
+pub struct RefFieldKey<'a>(pub &'a str, pub &'a String);
 
 impl<'a> From<&'a FieldKey> for RefFieldKey<'a> {
     fn from(key: &'a FieldKey) -> Self {
-        RefFieldKey(key.0, &key.1)
+        RefFieldKey(&key.0, &key.1)
     }
 }
 
@@ -30,7 +31,9 @@ fn deref_field_key(key: &RefFieldKey<'_>) -> ManuallyDrop<FieldKey> {
     // The 24-byte string headers of `a` and `b` may not be adjacent in
     // memory. Copy them (just the headers) so that they are adjacent. This
     // makes a `(String, String)` backed by the same data as `a` and `b`.
-    let k = unsafe { FieldKey(key.0, ptr::read(key.1)) };
+    // The field name is only borrowed for as long as the key is, so extending its lifetime is
+    // fine for the same reason.
+    let k = unsafe { FieldKey(Cow::Borrowed(&*(key.0 as *const str)), ptr::read(key.1)) };
 
     // Make sure not to drop the strings, even if `get` panics. The caller
     // or whoever owns `a` and `b` will drop them.
@@ -221,6 +224,18 @@ impl InMemoryData {
         }
     }
 
+    /// Remove all records, links and bookkeeping, including optimistic layers.
+    pub fn clear(&self) {
+        let guard = epoch::pin();
+        self.records.base.clear(&guard);
+        self.records.optimistic.clear(&guard);
+        self.links.base.clear(&guard);
+        self.links.optimistic.clear(&guard);
+        self.ref_counts.clear(&guard);
+        self.gc_queue.clear(&guard);
+        self.dependencies.lock().clear();
+    }
+
     pub fn clear_optimistic_layer(&self, optimistic_key: u64) {
         let guard = epoch::pin();
         self.records.optimistic.remove(&optimistic_key, &guard);
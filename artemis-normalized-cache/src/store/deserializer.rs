@@ -13,7 +13,7 @@ use serde::{
     forward_to_deserialize_any, Deserializer
 };
 use serde_json::Value;
-use std::{fmt, fmt::Display};
+use std::{borrow::Cow, fmt, fmt::Display};
 
 #[cfg(test)]
 mod tests;
@@ -86,19 +86,19 @@ impl fmt::Display for SerializerError {
     }
 }
 
-const TYPENAME: FieldKey = FieldKey("__typename", String::new());
+const TYPENAME: FieldKey = FieldKey(Cow::Borrowed("__typename"), String::new());
 
 #[inline]
-fn field_key<'a>(field_name: &'static str, args: &'a String) -> RefFieldKey<'a> {
+fn field_key<'a>(field_name: &'a str, args: &'a String) -> RefFieldKey<'a> {
     RefFieldKey(field_name, args)
 }
 
 #[inline]
 fn selector_field_name(selector: &FieldSelector) -> &str {
     match selector {
-        FieldSelector::Scalar(field_name, _) => *field_name,
-        FieldSelector::Union(field_name, _, _) => *field_name,
-        FieldSelector::Object(field_name, _, _, _) => *field_name
+        FieldSelector::Scalar(field_name, _) => field_name,
+        FieldSelector::Union(field_name, _, _) => field_name,
+        FieldSelector::Object(field_name, _, _, _) => field_name
     }
 }
 
@@ -119,14 +119,14 @@ impl<'a, 'de> Deserializer<'de> for SelectorDeserializer<'a, 'de> {
             FieldSelector::Scalar(field_name, args) => {
                 let scalar = self
                     .data
-                    .read_record(self.entity_key, field_key(*field_name, args), self.guard)
+                    .read_record(self.entity_key, field_key(field_name, args), self.guard)
                     .ok_or_else(|| SerializerError::missing())?;
                 Ok(scalar.deserialize_any(visitor)?)
             }
             FieldSelector::Object(field_name, args, _, inner_selection) => {
                 let link = self
                     .data
-                    .read_link(self.entity_key, field_key(*field_name, args), self.guard)
+                    .read_link(self.entity_key, field_key(field_name, args), self.guard)
                     .ok_or_else(|| SerializerError::missing())?;
                 match link {
                     Link::Null => visitor.visit_unit(),
@@ -151,7 +151,7 @@ impl<'a, 'de> Deserializer<'de> for SelectorDeserializer<'a, 'de> {
             FieldSelector::Union(field_name, args, inner_selection) => {
                 let link = self
                     .data
-                    .read_link(self.entity_key, field_key(*field_name, args), self.guard)
+                    .read_link(self.entity_key, field_key(field_name, args), self.guard)
                     .ok_or_else(|| SerializerError::missing())?;
                 match link {
                     Link::Null => visitor.visit_unit(),
@@ -195,7 +195,7 @@ impl<'a, 'de> Deserializer<'de> for SelectorDeserializer<'a, 'de> {
             FieldSelector::Scalar(field_name, args) => {
                 let value = self
                     .data
-                    .read_record(self.entity_key, field_key(*field_name, args), self.guard)
+                    .read_record(self.entity_key, field_key(field_name, args), self.guard)
                     .ok_or_else(|| SerializerError::missing())?;
                 match value {
                     Value::Null => visitor.visit_none(),
@@ -205,7 +205,7 @@ impl<'a, 'de> Deserializer<'de> for SelectorDeserializer<'a, 'de> {
             FieldSelector::Object(field_name, args, _, inner_selector) => {
                 let link = self
                     .data
-                    .read_link(self.entity_key, field_key(*field_name, args), self.guard)
+                    .read_link(self.entity_key, field_key(field_name, args), self.guard)
                     .ok_or_else(|| SerializerError::missing())?;
                 match link {
                     Link::Null => visitor.visit_none(),
@@ -234,7 +234,7 @@ impl<'a, 'de> Deserializer<'de> for SelectorDeserializer<'a, 'de> {
             FieldSelector::Union(field_name, args, inner_selector) => {
                 let link = self
                     .data
-                    .read_link(self.entity_key, field_key(*field_name, args), self.guard)
+                    .read_link(self.entity_key, field_key(field_name, args), self.guard)
                     .ok_or_else(|| SerializerError::missing())?;
                 match link {
                     Link::Null => visitor.visit_none(),
@@ -282,14 +282,14 @@ impl<'a, 'de> Deserializer<'de> for SelectorDeserializer<'a, 'de> {
             FieldSelector::Scalar(field_name, args) => {
                 let value = self
                     .data
-                    .read_record(self.entity_key, field_key(*field_name, args), self.guard)
+                    .read_record(self.entity_key, field_key(field_name, args), self.guard)
                     .ok_or_else(|| SerializerError::missing())?;
                 Ok(value.deserialize_enum(name, variants, visitor)?)
             }
             FieldSelector::Union(field_name, args, inner_selection) => {
                 let link = self
                     .data
-                    .read_link(self.entity_key, field_key(*field_name, args), self.guard)
+                    .read_link(self.entity_key, field_key(field_name, args), self.guard)
                     .ok_or_else(|| SerializerError::missing())?;
                 match link {
                     Link::Single(key) => {
@@ -498,12 +498,13 @@ impl<'a, 'de> MapAccess<'de> for ObjectDeserializer<'a, 'de> {
                     guard: self.guard,
                     entity_key: self.entity_key,
                     selector: value,
-                    // SAFETY: Only one child can exist at once and ObjectDeserializer only writes
+                    // Only one child can exist at once and ObjectDeserializer only writes
                     // to this when there isn't one, so multiple writers are impossible.
                     // Note: This can also be done with `Option<&mut HashSet<String>>`, however
                     // profiling shows this adds about 0.5% overhead for what is basically just
-                    // a typecheck workaround
-                    dependencies: unsafe { &mut *self.dependencies }
+                    // a typecheck workaround. The pointer is null when dependencies aren't
+                    // tracked, so it must not be turned into a reference here.
+                    dependencies: self.dependencies
                 };
                 self.value = Some(value);
                 seed.deserialize(key.into_deserializer()).map(Some)
@@ -6,13 +6,17 @@ use artemis::codegen::FieldSelector;
 use flurry::epoch::Guard;
 use serde::{
     ser::{
-        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
-        SerializeTupleStruct, SerializeTupleVariant
+        Error as _, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+        SerializeTuple, SerializeTupleStruct, SerializeTupleVariant
     },
     Serialize, Serializer
 };
 use serde_json::Value;
-use std::{fmt::Display, hint::unreachable_unchecked, sync::Arc};
+use std::{borrow::Cow, fmt::Display, hint::unreachable_unchecked, sync::Arc};
+
+const TYPENAME: &str = "__typename";
+
+type UnionSelection = Arc<dyn Fn(&str) -> Vec<FieldSelector>>;
 
 enum Field {
     Value(Value),
@@ -22,11 +26,16 @@ enum Field {
 pub struct ObjectSerializer<'a, 'g> {
     data: &'g InMemoryData,
     guard: &'g Guard,
-    selection: &'a [FieldSelector],
-    selection_iter: <&'a [FieldSelector] as IntoIterator>::IntoIter,
-    typename: &'a str,
+    selection: Cow<'a, [FieldSelector]>,
+    index: usize,
+    typename: Cow<'a, str>,
+    /// Set for union members until their `__typename` is known
+    union_selection: Option<&'a UnionSelection>,
+    write_typename: bool,
     entity_key: Option<String>,
     fields: Vec<Field>,
+    /// The key of the map entry that's being serialized
+    map_key: Option<String>,
     dependencies: *mut Dependencies,
     optimistic_key: Option<u64>
 }
@@ -45,11 +54,40 @@ impl<'a, 'g> ObjectSerializer<'a, 'g> {
         ObjectSerializer {
             data,
             guard,
-            selection,
-            selection_iter: selection.into_iter(),
-            typename,
+            selection: Cow::Borrowed(selection),
+            index: 0,
+            typename: Cow::Borrowed(typename),
+            union_selection: None,
+            write_typename: false,
             entity_key,
             fields: Vec::with_capacity(len),
+            map_key: None,
+            dependencies,
+            optimistic_key
+        }
+    }
+
+    /// Creates a serializer for a member of a union. Union members are tagged with their
+    /// `__typename`, which must be the first field, and the selection is only resolved once
+    /// it's known.
+    fn for_union(
+        data: &'g InMemoryData,
+        guard: &'g Guard,
+        selection: &'a UnionSelection,
+        dependencies: *mut Dependencies,
+        optimistic_key: Option<u64>
+    ) -> Self {
+        ObjectSerializer {
+            data,
+            guard,
+            selection: Cow::Owned(Vec::new()),
+            index: 0,
+            typename: Cow::Borrowed(""),
+            union_selection: Some(selection),
+            write_typename: false,
+            entity_key: None,
+            fields: Vec::new(),
+            map_key: None,
             dependencies,
             optimistic_key
         }
@@ -59,7 +97,7 @@ impl<'a, 'g> ObjectSerializer<'a, 'g> {
 struct UnionSerializer<'a, 'g> {
     data: &'g InMemoryData,
     guard: &'g Guard,
-    selection: &'a Arc<dyn Fn(&str) -> Vec<FieldSelector>>,
+    selection: &'a UnionSelection,
     dependencies: *mut Dependencies,
     optimistic_key: Option<u64>
 }
@@ -68,7 +106,7 @@ impl<'a, 'g> UnionSerializer<'a, 'g> {
     fn new(
         data: &'g InMemoryData,
         guard: &'g Guard,
-        selection: &'a Arc<dyn Fn(&str) -> Vec<FieldSelector>>,
+        selection: &'a UnionSelection,
         dependencies: *mut Dependencies,
         optimistic_key: Option<u64>
     ) -> Self {
@@ -197,7 +235,7 @@ impl<'a, 'g> Serializer for ObjectSerializer<'a, 'g> {
     type SerializeTuple = Unimpl;
     type SerializeTupleStruct = Unimpl;
     type SerializeTupleVariant = Unimpl;
-    type SerializeMap = Unimpl;
+    type SerializeMap = Self;
     type SerializeStruct = Self;
     type SerializeStructVariant = Unimpl;
 
@@ -345,7 +383,7 @@ impl<'a, 'g> Serializer for ObjectSerializer<'a, 'g> {
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        unimplemented!()
+        Ok(self)
     }
 
     fn serialize_struct(
@@ -381,8 +419,8 @@ impl<'a, 'g> Serializer for UnionSerializer<'a, 'g> {
     type SerializeTuple = Unimpl;
     type SerializeTupleStruct = Unimpl;
     type SerializeTupleVariant = Unimpl;
-    type SerializeMap = Unimpl;
-    type SerializeStruct = Unimpl;
+    type SerializeMap = ObjectSerializer<'a, 'g>;
+    type SerializeStruct = ObjectSerializer<'a, 'g>;
     type SerializeStructVariant = Unimpl;
 
     fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
@@ -536,8 +574,8 @@ impl<'a, 'g> Serializer for UnionSerializer<'a, 'g> {
         unimplemented!()
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        unimplemented!()
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.serialize_struct("", len.unwrap_or(0))
     }
 
     fn serialize_struct(
@@ -545,7 +583,13 @@ impl<'a, 'g> Serializer for UnionSerializer<'a, 'g> {
         _name: &'static str,
         _len: usize
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        unimplemented!()
+        Ok(ObjectSerializer::for_union(
+            self.data,
+            self.guard,
+            self.selection,
+            self.dependencies,
+            self.optimistic_key
+        ))
     }
 
     fn serialize_struct_variant(
@@ -566,25 +610,37 @@ impl<'a, 'g> Serializer for UnionSerializer<'a, 'g> {
     }
 }
 
-impl<'a, 'g> SerializeStruct for ObjectSerializer<'a, 'g> {
-    type Ok = Link;
-    type Error = serde_json::Error;
-
-    fn serialize_field<V: ?Sized + Serialize>(
+impl<'a, 'g> ObjectSerializer<'a, 'g> {
+    fn write_field<V: ?Sized + Serialize>(
         &mut self,
-        key: &'static str,
+        key: &str,
         value: &V
-    ) -> Result<(), Self::Error> {
-        let key = key as *const _ as *const str;
-        let key = unsafe { &*key };
-
-        if self.entity_key.is_none() && is_key(self.data, self.typename, key) {
-            self.selection_iter.next();
+    ) -> Result<(), serde_json::Error> {
+        if let Some(selection) = self.union_selection.take() {
+            let value = value.serialize(serde_json::value::Serializer)?;
+            let typename = value
+                .as_str()
+                .ok_or_else(|| serde_json::Error::custom("union member has no __typename"))?
+                .to_owned();
+            self.selection = Cow::Owned(selection(&typename));
+            self.typename = Cow::Owned(typename);
+            match self.selection.first() {
+                Some(FieldSelector::Scalar(field_name, _)) if field_name == TYPENAME => {
+                    self.index += 1;
+                    self.fields.push(Field::Value(value));
+                }
+                // The typename is always needed to read the union back out of the store
+                _ => self.write_typename = true
+            }
+        } else if self.entity_key.is_none() && is_key(self.data, &self.typename, key) {
+            self.index += 1;
             let value = value.serialize(serde_json::value::Serializer)?;
             self.entity_key = Some(entity_key(&self.typename, value.as_str().unwrap()));
             self.fields.push(Field::Value(value));
         } else {
-            match self.selection_iter.next().unwrap() {
+            let selector = &self.selection[self.index];
+            self.index += 1;
+            match selector {
                 FieldSelector::Scalar(_, _) => {
                     let value = value.serialize(serde_json::value::Serializer)?;
                     self.fields.push(Field::Value(value));
@@ -617,14 +673,25 @@ impl<'a, 'g> SerializeStruct for ObjectSerializer<'a, 'g> {
         Ok(())
     }
 
-    fn end(self) -> Result<Self::Ok, Self::Error> {
+    fn finish(self) -> Result<Link, serde_json::Error> {
         let entity_key = self.entity_key.expect("Entity key not found");
         if &entity_key != "Query" {
             unsafe { &mut *self.dependencies }.insert(entity_key.clone());
         }
 
+        if self.write_typename {
+            write_record(
+                &self.data,
+                self.optimistic_key,
+                &entity_key,
+                FieldKey(Cow::Borrowed(TYPENAME), String::new()),
+                Some(Value::String(self.typename.into_owned())),
+                self.guard
+            );
+        }
+
         let mut fields = self.fields.into_iter();
-        for selector in self.selection {
+        for selector in self.selection.iter() {
             let value = fields.next().unwrap();
             match selector {
                 FieldSelector::Scalar(field_name, args) => {
@@ -636,12 +703,13 @@ impl<'a, 'g> SerializeStruct for ObjectSerializer<'a, 'g> {
                         &self.data,
                         self.optimistic_key,
                         &entity_key,
-                        FieldKey(*field_name, args.to_owned()),
+                        FieldKey(field_name.clone(), args.to_owned()),
                         Some(value),
                         self.guard
                     );
                 }
-                FieldSelector::Object(field_name, args, _, _) => {
+                FieldSelector::Object(field_name, args, _, _)
+                | FieldSelector::Union(field_name, args, _) => {
                     let value = match value {
                         Field::Link(key) => key,
                         _ => unsafe { unreachable_unchecked() }
@@ -650,12 +718,11 @@ impl<'a, 'g> SerializeStruct for ObjectSerializer<'a, 'g> {
                         &self.data,
                         self.optimistic_key,
                         &entity_key,
-                        FieldKey(*field_name, args.to_owned()),
+                        FieldKey(field_name.clone(), args.to_owned()),
                         Some(value),
                         self.guard
                     );
                 }
-                FieldSelector::Union(_, _, _) => todo!("Unions")
             }
         }
 
@@ -663,6 +730,51 @@ impl<'a, 'g> SerializeStruct for ObjectSerializer<'a, 'g> {
     }
 }
 
+impl<'a, 'g> SerializeStruct for ObjectSerializer<'a, 'g> {
+    type Ok = Link;
+    type Error = serde_json::Error;
+
+    fn serialize_field<V: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &V
+    ) -> Result<(), Self::Error> {
+        self.write_field(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+/// Maps are serialized like structs, this is what objects of dynamic operations use
+impl<'a, 'g> SerializeMap for ObjectSerializer<'a, 'g> {
+    type Ok = Link;
+    type Error = serde_json::Error;
+
+    fn serialize_key<K: ?Sized + Serialize>(&mut self, key: &K) -> Result<(), Self::Error> {
+        match key.serialize(serde_json::value::Serializer)? {
+            Value::String(key) => {
+                self.map_key = Some(key);
+                Ok(())
+            }
+            _ => Err(serde_json::Error::custom("object keys must be strings"))
+        }
+    }
+
+    fn serialize_value<V: ?Sized + Serialize>(&mut self, value: &V) -> Result<(), Self::Error> {
+        let key = self
+            .map_key
+            .take()
+            .ok_or_else(|| serde_json::Error::custom("map value without a key"))?;
+        self.write_field(&key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
 #[inline]
 fn entity_key(typename: &str, key: &str) -> String {
     let mut s = String::with_capacity(typename.len() + key.len() + 1);
@@ -675,7 +787,7 @@ fn entity_key(typename: &str, key: &str) -> String {
 struct SerializeUnionVec<'a, 'g> {
     data: &'g InMemoryData,
     guard: &'g Guard,
-    selection: &'a Arc<dyn Fn(&str) -> Vec<FieldSelector>>,
+    selection: &'a UnionSelection,
     entity_keys: Vec<String>,
     dependencies: *mut Dependencies,
     optimistic_key: Option<u64>
@@ -713,8 +825,8 @@ impl<'a, 'g> SerializeSeq for SerializeUnionVec<'a, 'g> {
 pub struct SerializeVec<'a, 'g> {
     data: &'g InMemoryData,
     guard: &'g Guard,
-    selection: &'a [FieldSelector],
-    typename: &'a str,
+    selection: Cow<'a, [FieldSelector]>,
+    typename: Cow<'a, str>,
     entity_keys: Vec<String>,
     dependencies: *mut Dependencies,
     optimistic_key: Option<u64>
@@ -731,8 +843,8 @@ impl<'a, 'g> SerializeSeq for SerializeVec<'a, 'g> {
         let serializer = ObjectSerializer::new(
             self.data,
             self.guard,
-            self.selection,
-            self.typename,
+            &self.selection,
+            &self.typename,
             None,
             self.dependencies,
             self.optimistic_key
@@ -18,7 +18,7 @@ use fnv::FnvBuildHasher;
 use serde::de::DeserializeOwned;
 use serde::{de::Deserialize, Serialize};
 use serde_json::Value;
-use std::{collections::HashMap, error::Error, fmt, sync::Arc};
+use std::{borrow::Cow, collections::HashMap, error::Error, fmt, sync::Arc};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::JsValue;
 
@@ -41,7 +41,7 @@ impl fmt::Display for StoreError {
     }
 }
 
-const TYPENAME: FieldKey = FieldKey("__typename", String::new());
+const TYPENAME: FieldKey = FieldKey(Cow::Borrowed("__typename"), String::new());
 
 pub fn is_root(typename: &str) -> bool {
     typename == "Query" || typename == "Mutation" || typename == "Subscription"
@@ -219,6 +219,10 @@ impl Store {
         Ok(())
     }
 
+    pub fn clear(&self) {
+        self.data.clear();
+    }
+
     pub fn clear_optimistic_layer(&self, query_key: u64) {
         self.data.clear_optimistic_layer(query_key);
     }
@@ -245,7 +249,7 @@ impl Store {
     }
 
     #[inline]
-    fn field_key<'a>(field_name: &'static str, args: &'a String) -> RefFieldKey<'a> {
+    fn field_key<'a>(field_name: &'a str, args: &'a String) -> RefFieldKey<'a> {
         RefFieldKey(field_name, args)
     }
 
@@ -347,12 +351,12 @@ impl Store {
                     self.write_record(
                         optimistic_key,
                         entity_key,
-                        FieldKey(*field_name, args.to_owned()),
+                        FieldKey(field_name.clone(), args.to_owned()),
                         None
                     );
                 }
                 FieldSelector::Object(field_name, args, _, subselection) => {
-                    let field_key = Self::field_key(*field_name, args);
+                    let field_key = Self::field_key(field_name, args);
                     if let Some(link) = self.data.read_link(entity_key, field_key, &guard) {
                         match link {
                             Link::Single(entity_key) => self.invalidate_selection(
@@ -378,7 +382,7 @@ impl Store {
                     }
                 }
                 FieldSelector::Union(field_name, args, subselection) => {
-                    let field_key = Self::field_key(*field_name, args);
+                    let field_key = Self::field_key(field_name, args);
                     if let Some(link) = self.data.read_link(entity_key, field_key, &guard) {
                         match link {
                             Link::Single(ref entity_key) => self.invalidate_union(
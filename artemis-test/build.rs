@@ -30,10 +30,10 @@ fn generate_code() -> Result<(), Box<dyn Error>> {
         .with_out_dir("src/queries")
         .with_derives_on_variables("Debug,PartialEq")
         .with_derives_on_response("Debug,PartialEq,Serialize")
-        .add_query(query("get_conference.graphql"))
-        .add_query(query("get_conferences.graphql"))
-        .add_query(query("add_conference.graphql"))
+        .add_queries_from_dir("src/queries", "*.graphql")
+        .add_fragments(query("fragments.graphql"))
         .with_schema("api-schema.json")
+        .with_scalar("Long", "String")
         .build()?;
 
     Ok(())
@@ -4,8 +4,6 @@ pub use queries::*;
 #[cfg(target_arch = "wasm32")]
 pub mod client;
 
-pub(crate) type Long = String;
-
 #[cfg(target_arch = "wasm32")]
 mod wasm {
     use wasm_bindgen::{prelude::*, JsValue};
@@ -0,0 +1,355 @@
+//! In-flight activity and network status of a client, for things like global loading bars and
+//! offline banners.
+//!
+//! [`Client::activity`](../struct.Client.html#method.activity) returns a stream of the number of
+//! operations currently running through the exchanges, grouped by their `OperationType`.
+//! [`Client::network_status`](../struct.Client.html#method.network_status) returns a stream of
+//! whether the server is reachable. The status is reported by exchanges that send operations over
+//! the network, like the `FetchExchange`, through
+//! [`Client::report_network_status`](../exchange/trait.Client.html#method.report_network_status),
+//! and optionally by a probe set with
+//! [`ClientBuilder::with_network_probe`](../struct.ClientBuilder.html#method.with_network_probe).
+//! Exchanges can wait for the server to become reachable again with
+//! [`Client::wait_until_online`](../exchange/trait.Client.html#method.wait_until_online).
+//!
+//! Both streams start with the current state and then yield every change. Intermediate states are
+//! skipped if the stream isn't consumed fast enough, so the latest item is always up to date.
+//! They end when the client is dropped.
+//!
+//! # Example
+//!
+//! ```
+//! # tokio_test::block_on(async {
+//! use artemis::{activity::NetworkStatus, Client};
+//! use futures::StreamExt;
+//!
+//! let client = Client::builder("http://localhost:8080/graphql")
+//!     .with_default_exchanges()
+//!     .build();
+//!
+//! let mut activity = client.activity();
+//! tokio::spawn(async move {
+//!     while let Some(activity) = activity.next().await {
+//!         println!("loading: {}", !activity.is_idle());
+//!     }
+//! });
+//!
+//! let mut network_status = client.network_status();
+//! tokio::spawn(async move {
+//!     while let Some(status) = network_status.next().await {
+//!         println!("offline: {}", status == NetworkStatus::Offline);
+//!     }
+//! });
+//! # });
+//! ```
+
+#[cfg(feature = "network-probe")]
+use crate::spawner::Spawner;
+use crate::OperationType;
+use futures::{
+    future::BoxFuture,
+    task::{Context, Poll, Waker},
+    Stream, StreamExt
+};
+use parking_lot::Mutex;
+use std::{
+    pin::Pin,
+    sync::{Arc, Weak}
+};
+#[cfg(feature = "network-probe")]
+use {futures_timer::Delay, std::time::Duration};
+
+/// The number of operations currently running through the exchanges, by operation type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Activity {
+    /// The number of queries in flight
+    pub queries: usize,
+    /// The number of mutations in flight
+    pub mutations: usize,
+    /// The number of subscriptions in flight
+    pub subscriptions: usize
+}
+
+impl Activity {
+    /// The number of operations in flight, regardless of type.
+    pub fn total(&self) -> usize {
+        self.queries + self.mutations + self.subscriptions
+    }
+
+    /// Whether there are no operations in flight.
+    pub fn is_idle(&self) -> bool {
+        self.total() == 0
+    }
+
+    fn count_mut(&mut self, operation_type: &OperationType) -> &mut usize {
+        match operation_type {
+            OperationType::Query => &mut self.queries,
+            OperationType::Mutation => &mut self.mutations,
+            OperationType::Subscription => &mut self.subscriptions
+        }
+    }
+}
+
+/// Whether the server can currently be reached.
+/// Clients start out `Online` until an exchange or the probe reports otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum NetworkStatus {
+    Online,
+    Offline
+}
+
+struct WatchState<T> {
+    value: T,
+    version: u64,
+    wakers: Vec<Waker>
+}
+
+/// A value that can be watched for changes by any number of streams.
+struct Watch<T> {
+    state: Mutex<WatchState<T>>
+}
+
+impl<T: Clone + PartialEq> Watch<T> {
+    fn new(value: T) -> Self {
+        Self {
+            state: Mutex::new(WatchState {
+                value,
+                version: 0,
+                wakers: Vec::new()
+            })
+        }
+    }
+
+    fn get(&self) -> T {
+        self.state.lock().value.clone()
+    }
+
+    /// Updates the value, waking all streams if it changed.
+    fn update<F: FnOnce(&mut T)>(&self, f: F) {
+        let mut state = self.state.lock();
+        let previous = state.value.clone();
+        f(&mut state.value);
+        if state.value != previous {
+            state.version += 1;
+            for waker in state.wakers.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<T> Drop for Watch<T> {
+    fn drop(&mut self) {
+        for waker in self.state.get_mut().wakers.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+/// A stream of the latest state of a client, see the [module docs](./index.html).
+pub struct StateStream<T> {
+    watch: Weak<Watch<T>>,
+    version: Option<u64>
+}
+
+/// A stream of the in-flight activity of a client.
+pub type ActivityStream = StateStream<Activity>;
+/// A stream of the network status of a client.
+pub type NetworkStatusStream = StateStream<NetworkStatus>;
+
+impl<T> StateStream<T> {
+    fn new(watch: &Arc<Watch<T>>) -> Self {
+        Self {
+            watch: Arc::downgrade(watch),
+            version: None
+        }
+    }
+}
+
+impl<T: Clone> Stream for StateStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let watch = match self.watch.upgrade() {
+            Some(watch) => watch,
+            None => return Poll::Ready(None)
+        };
+        let mut state = watch.state.lock();
+        if self.version != Some(state.version) {
+            self.version = Some(state.version);
+            Poll::Ready(Some(state.value.clone()))
+        } else {
+            if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                state.wakers.push(cx.waker().clone());
+            }
+            Poll::Pending
+        }
+    }
+}
+
+/// Counts an operation as in flight until it's dropped.
+pub(crate) struct InFlight {
+    activity: Arc<Watch<Activity>>,
+    operation_type: OperationType
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let operation_type = &self.operation_type;
+        self.activity
+            .update(|activity| *activity.count_mut(operation_type) -= 1);
+    }
+}
+
+/// A probe that resolves to whether the server is reachable.
+#[cfg(feature = "network-probe")]
+pub(crate) type ProbeFn = Arc<dyn Fn() -> BoxFuture<'static, bool> + Send + Sync>;
+
+/// Tracks the activity and network status of a client.
+pub(crate) struct ActivityTracker {
+    activity: Arc<Watch<Activity>>,
+    network_status: Arc<Watch<NetworkStatus>>
+}
+
+impl ActivityTracker {
+    pub(crate) fn new() -> Self {
+        Self {
+            activity: Arc::new(Watch::new(Activity::default())),
+            network_status: Arc::new(Watch::new(NetworkStatus::Online))
+        }
+    }
+
+    pub(crate) fn start(&self, operation_type: &OperationType) -> InFlight {
+        self.activity
+            .update(|activity| *activity.count_mut(operation_type) += 1);
+        InFlight {
+            activity: self.activity.clone(),
+            operation_type: operation_type.clone()
+        }
+    }
+
+    pub(crate) fn activity(&self) -> ActivityStream {
+        StateStream::new(&self.activity)
+    }
+
+    pub(crate) fn network_status(&self) -> NetworkStatusStream {
+        StateStream::new(&self.network_status)
+    }
+
+    pub(crate) fn current_network_status(&self) -> NetworkStatus {
+        self.network_status.get()
+    }
+
+    pub(crate) fn set_network_status(&self, status: NetworkStatus) {
+        self.network_status.update(|current| *current = status);
+    }
+
+    pub(crate) fn wait_until_online(&self) -> BoxFuture<'static, ()> {
+        let mut statuses = self.network_status();
+        Box::pin(async move {
+            while let Some(status) = statuses.next().await {
+                if status == NetworkStatus::Online {
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Runs the probe every `interval` until the client is dropped.
+    #[cfg(feature = "network-probe")]
+    pub(crate) fn spawn_probe(&self, spawner: &dyn Spawner, interval: Duration, probe: ProbeFn) {
+        let network_status = Arc::downgrade(&self.network_status);
+        spawner.spawn(Box::pin(async move {
+            loop {
+                Delay::new(interval).await;
+                let status = if probe().await {
+                    NetworkStatus::Online
+                } else {
+                    NetworkStatus::Offline
+                };
+                match network_status.upgrade() {
+                    Some(network_status) => network_status.update(|current| *current = status),
+                    None => break
+                }
+            }
+        }));
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test {
+    use super::{Activity, NetworkStatus};
+    use crate::{
+        exchange::Client,
+        test_utils::{data_response, TestExchange, TestQuery, TEST_URL},
+        ClientBuilder
+    };
+    use futures::{channel::oneshot, future, FutureExt, StreamExt};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_activity() {
+        let (sender, release) = oneshot::channel();
+        let release = release.shared();
+        let client = ClientBuilder::new(TEST_URL)
+            .with_exchange(TestExchange::new(move |_| {
+                let release = release.clone();
+                async move {
+                    release.await.unwrap();
+                    Ok(data_response(json!(1)))
+                }
+            }))
+            .build();
+        let mut activity = client.activity();
+        assert_eq!(activity.next().await, Some(Activity::default()));
+
+        let (result, _) = future::join(client.query(TestQuery, ()), async {
+            let in_flight = activity.next().await.unwrap();
+            assert_eq!(in_flight.queries, 1);
+            assert_eq!(in_flight.total(), 1);
+            sender.send(()).unwrap();
+            assert!(activity.next().await.unwrap().is_idle());
+        })
+        .await;
+        result.unwrap();
+
+        drop(client);
+        assert_eq!(activity.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_network_status() {
+        let client = ClientBuilder::new(TEST_URL)
+            .with_exchange(TestExchange::new(|request| {
+                (request.report_network_status)(NetworkStatus::Offline);
+                async { Ok(data_response(json!(1))) }
+            }))
+            .build();
+        let mut network_status = client.network_status();
+        assert_eq!(network_status.next().await, Some(NetworkStatus::Online));
+
+        client.query(TestQuery, ()).await.unwrap();
+        assert!(!client.is_online());
+        assert_eq!(network_status.next().await, Some(NetworkStatus::Offline));
+
+        let mut online = client.0.wait_until_online();
+        assert!((&mut online).now_or_never().is_none());
+        client.0.report_network_status(NetworkStatus::Online);
+        assert!(online.now_or_never().is_some());
+        assert_eq!(network_status.next().await, Some(NetworkStatus::Online));
+    }
+
+    #[cfg(feature = "network-probe")]
+    #[tokio::test]
+    async fn test_network_probe() {
+        use std::time::Duration;
+
+        let client = ClientBuilder::new(TEST_URL)
+            .with_exchange(crate::test_utils::FakeExchange)
+            .with_network_probe(Duration::from_millis(10), || async { false })
+            .build();
+        let mut network_status = client.network_status();
+        assert_eq!(network_status.next().await, Some(NetworkStatus::Online));
+        assert_eq!(network_status.next().await, Some(NetworkStatus::Offline));
+    }
+}
@@ -0,0 +1,203 @@
+//! A blocking client, for use in synchronous code such as CLI tools or build scripts.
+//!
+//! The blocking client wraps a regular [`Client`](../struct.Client.html) together with a private
+//! single-threaded Tokio runtime and blocks the calling thread until each result is available.
+//! It's built from the same [`ClientBuilder`](../struct.ClientBuilder.html), so exchanges and all
+//! other options are configured exactly like the async client.
+//!
+//! Like `reqwest::blocking`, this must **not** be used from within an async runtime, since
+//! blocking the executor would stall it. Doing so will panic.
+//!
+//! Requires feature: `blocking`
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use artemis_test::get_conference::{GetConference, get_conference::Variables};
+//! use artemis::ClientBuilder;
+//!
+//! let client = ClientBuilder::new("http://localhost:8080/graphql")
+//!     .with_default_exchanges()
+//!     .build_blocking()
+//!     .unwrap();
+//!
+//! let result = client.query(GetConference, Variables { id: "1".to_string() }).unwrap();
+//! assert!(result.data.is_some());
+//! ```
+
+#[cfg(feature = "observable")]
+use crate::Observable;
+use crate::{
+    default_exchanges::TerminatorExchange, ClientBuilder, Exchange, GraphQLQuery, QueryError,
+    QueryOptions, ResetOptions, Response
+};
+#[cfg(feature = "observable")]
+use futures::StreamExt;
+use std::{future::Future, sync::Arc};
+use tokio::runtime::Runtime;
+
+/// A blocking artemis client. Cloning is cheap, clones share the same runtime and exchanges.
+#[derive(Clone)]
+pub struct Client<M: Exchange = TerminatorExchange> {
+    inner: crate::Client<M>,
+    runtime: Arc<Runtime>
+}
+
+impl Client {
+    /// Returns a `ClientBuilder` with the given endpoint URL.
+    /// Finish it with `build_blocking` to get a blocking client.
+    pub fn builder<U: Into<String>>(url: U) -> ClientBuilder {
+        ClientBuilder::new(url)
+    }
+}
+
+impl<M: Exchange> Client<M> {
+    pub(crate) fn new(inner: crate::Client<M>, runtime: Arc<Runtime>) -> Self {
+        Self { inner, runtime }
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Executes a query with the given variables, blocking until the result is available.
+    /// Returns the result of the query, or a `QueryError` if one of the exchanges encountered a fatal error.
+    pub fn query<Q: GraphQLQuery>(
+        &self,
+        query: Q,
+        variables: Q::Variables
+    ) -> Result<Response<Q::ResponseData>, QueryError> {
+        self.block_on(self.inner.query(query, variables))
+    }
+
+    /// Executes a query with the given variables and options, blocking until the result is available.
+    /// Returns the result of the query, or a `QueryError` if one of the exchanges encountered a fatal error.
+    pub fn query_with_options<Q: GraphQLQuery>(
+        &self,
+        query: Q,
+        variables: Q::Variables,
+        options: QueryOptions
+    ) -> Result<Response<Q::ResponseData>, QueryError> {
+        self.block_on(self.inner.query_with_options(query, variables, options))
+    }
+
+    /// Executes an operation that's only known at runtime with the given JSON variables,
+    /// blocking until the result is available.
+    /// See [`Client::query_dynamic`](../client/struct.Client.html#method.query_dynamic) for details.
+    ///
+    /// Requires feature: `dynamic`
+    #[cfg(feature = "dynamic")]
+    pub fn query_dynamic(
+        &self,
+        document: &str,
+        variables: serde_json::Value
+    ) -> Result<Response<serde_json::Value>, QueryError> {
+        self.block_on(self.inner.query_dynamic(document, variables))
+    }
+
+    /// Subscribes to a query, returning an iterator over the initial result and any future updates.
+    /// Each call to `next` blocks until the next result is available.
+    /// Dropping the `Subscription` will cancel it.
+    ///
+    /// Requires feature: `observable`
+    #[cfg(feature = "observable")]
+    pub fn subscribe<Q: GraphQLQuery + 'static>(
+        &self,
+        query: Q,
+        variables: Q::Variables
+    ) -> Subscription<Q::ResponseData, M> {
+        self.subscribe_with_options(query, variables, QueryOptions::default())
+    }
+
+    /// Subscribes to a query with options, returning an iterator over the initial result and any
+    /// future updates. Each call to `next` blocks until the next result is available.
+    /// Dropping the `Subscription` will cancel it.
+    ///
+    /// Requires feature: `observable`
+    #[cfg(feature = "observable")]
+    pub fn subscribe_with_options<Q: GraphQLQuery + 'static>(
+        &self,
+        query: Q,
+        variables: Q::Variables,
+        options: QueryOptions
+    ) -> Subscription<Q::ResponseData, M> {
+        Subscription {
+            observable: self.inner.subscribe_with_options(query, variables, options),
+            runtime: self.runtime.clone()
+        }
+    }
+
+    /// Clears all state held by the exchanges and reruns every active subscription.
+    /// See [`Client::reset_store`](../struct.Client.html#method.reset_store).
+    pub fn reset_store(&self) {
+        self.inner.reset_store()
+    }
+
+    /// Clears all state held by the exchanges with options.
+    /// See [`Client::reset_store_with_options`](../struct.Client.html#method.reset_store_with_options).
+    pub fn reset_store_with_options(&self, options: ResetOptions) {
+        self.inner.reset_store_with_options(options)
+    }
+
+    /// Gets the underlying async client. Its futures must be run on an async runtime,
+    /// not through this client.
+    pub fn as_async(&self) -> &crate::Client<M> {
+        &self.inner
+    }
+}
+
+/// A blocking subscription to a query. This is an iterator over the query results
+/// and unsubscribes on drop.
+///
+/// Requires feature: `observable`
+#[cfg(feature = "observable")]
+pub struct Subscription<R: Clone, M: Exchange> {
+    observable: Observable<Result<Response<R>, QueryError>, M>,
+    runtime: Arc<Runtime>
+}
+
+#[cfg(feature = "observable")]
+impl<R: Clone, M: Exchange> Subscription<R, M> {
+    /// Manually cause the client to rerun this query.
+    /// Note this doesn't invalidate any caching, so if the query is in the cache it will simply be re-read
+    pub fn rerun(&self) {
+        self.observable.rerun();
+    }
+}
+
+#[cfg(feature = "observable")]
+impl<R: Clone, M: Exchange> Iterator for Subscription<R, M> {
+    type Item = Result<Response<R>, QueryError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let observable = &mut self.observable;
+        self.runtime.block_on(observable.next())
+    }
+}
+
+#[cfg(all(test, feature = "observable"))]
+mod test {
+    use crate::{
+        test_utils::{Data, FakeExchange, TestQuery},
+        ClientBuilder
+    };
+
+    #[test]
+    fn test_blocking_client() {
+        let client = ClientBuilder::new("http://localhost:4000/graphql")
+            .with_exchange(FakeExchange)
+            .build_blocking()
+            .unwrap();
+
+        let result = client.query(TestQuery, ()).unwrap();
+        assert_eq!(result.data, Some(Data(1)));
+
+        let mut subscription = client.subscribe(TestQuery, ());
+        let result = subscription.next().unwrap().unwrap();
+        assert_eq!(result.data, Some(Data(1)));
+
+        subscription.rerun();
+        let result = subscription.next().unwrap().unwrap();
+        assert_eq!(result.data, Some(Data(1)));
+    }
+}
@@ -1,11 +1,28 @@
+#[cfg(feature = "network-probe")]
+use crate::activity::ProbeFn;
 #[cfg(feature = "default-exchanges")]
 use crate::default_exchanges::{CacheExchange, DedupExchange, FetchExchange};
+#[cfg(feature = "dynamic")]
+use crate::dynamic::{DocumentCache, DynamicSchema};
+#[cfg(all(not(target_arch = "wasm32"), feature = "blocking"))]
+use crate::spawner::TokioSpawner;
+#[cfg(feature = "observable")]
+use crate::OverflowStrategy;
 use crate::{
-    client::ClientImpl, default_exchanges::TerminatorExchange, Client, Exchange, ExchangeFactory,
-    HeaderPair, RequestPolicy
+    activity::ActivityTracker,
+    client::{prefetch::Prefetches, ClientImpl},
+    default_exchanges::TerminatorExchange,
+    devtools::Devtools,
+    spawner::{default_spawner, Spawner},
+    Client, Exchange, ExchangeFactory, HeaderPair, RequestPolicy
 };
+#[cfg(feature = "observable")]
 use parking_lot::Mutex;
-use std::{collections::HashMap, sync::Arc};
+#[cfg(feature = "observable")]
+use std::collections::HashMap;
+use std::sync::Arc;
+#[cfg(feature = "network-probe")]
+use std::{future::Future, time::Duration};
 
 /// A builder for the artemis client.
 pub struct ClientBuilder<M: Exchange = TerminatorExchange> {
@@ -13,6 +30,14 @@ pub struct ClientBuilder<M: Exchange = TerminatorExchange> {
     url: String,
     extra_headers: Option<Arc<dyn Fn() -> Vec<HeaderPair> + Send + Sync>>,
     request_policy: RequestPolicy,
+    #[cfg(feature = "observable")]
+    overflow_strategy: OverflowStrategy,
+    spawner: Option<Arc<dyn Spawner>>,
+    devtools: bool,
+    #[cfg(feature = "dynamic")]
+    schema: Option<Arc<DynamicSchema>>,
+    #[cfg(feature = "network-probe")]
+    network_probe: Option<(Duration, ProbeFn)>,
     #[cfg(target_arch = "wasm32")]
     fetch: Option<js_sys::Function>
 }
@@ -25,6 +50,14 @@ impl ClientBuilder<TerminatorExchange> {
             url: url.into(),
             extra_headers: None,
             request_policy: RequestPolicy::CacheFirst,
+            #[cfg(feature = "observable")]
+            overflow_strategy: OverflowStrategy::default(),
+            spawner: None,
+            devtools: false,
+            #[cfg(feature = "dynamic")]
+            schema: None,
+            #[cfg(feature = "network-probe")]
+            network_probe: None,
             #[cfg(target_arch = "wasm32")]
             fetch: None
         }
@@ -58,6 +91,14 @@ impl<M: Exchange> ClientBuilder<M> {
             url: self.url,
             extra_headers: self.extra_headers,
             request_policy: self.request_policy,
+            #[cfg(feature = "observable")]
+            overflow_strategy: self.overflow_strategy,
+            spawner: self.spawner,
+            devtools: self.devtools,
+            #[cfg(feature = "dynamic")]
+            schema: self.schema,
+            #[cfg(feature = "network-probe")]
+            network_probe: self.network_probe,
             #[cfg(target_arch = "wasm32")]
             fetch: self.fetch
         }
@@ -94,18 +135,131 @@ impl<M: Exchange> ClientBuilder<M> {
         self
     }
 
+    /// Sets how subscriptions handle results that arrive faster than they're consumed.
+    /// The default is `OverflowStrategy::Bounded(8)`.
+    ///
+    /// Requires feature: `observable`
+    #[cfg(feature = "observable")]
+    pub fn with_overflow_strategy(mut self, overflow_strategy: OverflowStrategy) -> Self {
+        self.overflow_strategy = overflow_strategy;
+        self
+    }
+
+    /// Sets the executor used for background work such as rerunning subscriptions.
+    /// The default depends on the enabled features, see the [spawner](../spawner/index.html)
+    /// module for details.
+    pub fn with_spawner(mut self, spawner: impl Spawner) -> Self {
+        self.spawner = Some(Arc::new(spawner));
+        self
+    }
+
+    /// Enables the client's [devtools](../devtools/index.html).
+    /// This makes the client keep the latest result of each subscription and lets exchanges
+    /// emit events, so it should usually only be enabled in development.
+    pub fn with_devtools(mut self) -> Self {
+        self.devtools = true;
+        self
+    }
+
+    /// Sets the schema used to determine the types of fields in dynamic operations.
+    /// See the [dynamic](../dynamic/index.html) module for details.
+    ///
+    /// Requires feature: `dynamic`
+    #[cfg(feature = "dynamic")]
+    pub fn with_schema(mut self, schema: DynamicSchema) -> Self {
+        self.schema = Some(Arc::new(schema));
+        self
+    }
+
+    /// Sets a probe that's run every `interval` to check whether the server is reachable,
+    /// in addition to the outcomes reported by exchanges. The probe resolves to `true` if it is.
+    /// This keeps the [network status](../activity/index.html) up to date while no operations
+    /// are being sent. The probe runs on the client's spawner until the client is dropped.
+    ///
+    /// Requires feature: `network-probe`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use artemis::ClientBuilder;
+    /// # use std::time::Duration;
+    /// let client = ClientBuilder::new("http://localhost:8080/graphql")
+    ///     .with_default_exchanges()
+    ///     .with_network_probe(Duration::from_secs(30), || async {
+    ///         reqwest::get("http://localhost:8080/health").await.is_ok()
+    ///     })
+    ///     .build();
+    /// ```
+    #[cfg(feature = "network-probe")]
+    pub fn with_network_probe<F, Fut>(mut self, interval: Duration, probe: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static
+    {
+        self.network_probe = Some((interval, Arc::new(move || Box::pin(probe()))));
+        self
+    }
+
     /// Builds the client with the options from the builder
     pub fn build(self) -> Client<M> {
+        #[cfg(feature = "observable")]
+        let active_subscriptions = Arc::new(Mutex::new(HashMap::new()));
+        let devtools = if self.devtools {
+            #[cfg(feature = "observable")]
+            let devtools = Devtools::new(active_subscriptions.clone());
+            #[cfg(not(feature = "observable"))]
+            let devtools = Devtools::new();
+            Some(Arc::new(devtools))
+        } else {
+            None
+        };
+
         let client = ClientImpl {
             url: self.url,
             exchange: self.exchange,
             extra_headers: self.extra_headers,
             request_policy: self.request_policy,
-            active_subscriptions: Arc::new(Mutex::new(HashMap::new())),
+            #[cfg(feature = "observable")]
+            active_subscriptions,
+            #[cfg(feature = "observable")]
+            overflow_strategy: self.overflow_strategy,
+            spawner: self.spawner.unwrap_or_else(default_spawner),
+            devtools,
+            prefetches: Prefetches::default(),
+            activity: ActivityTracker::new(),
+            #[cfg(feature = "dynamic")]
+            documents: DocumentCache::new(self.schema),
             #[cfg(target_arch = "wasm32")]
             fetch: self.fetch
         };
+        #[cfg(feature = "network-probe")]
+        {
+            if let Some((interval, probe)) = self.network_probe {
+                client
+                    .activity
+                    .spawn_probe(&*client.spawner, interval, probe);
+            }
+        }
 
         Client(Arc::new(client))
     }
+
+    /// Builds a blocking client with the options from the builder.
+    /// The client owns a private runtime, which is also used as the default spawner.
+    /// Returns an error if the runtime couldn't be created.
+    ///
+    /// Requires feature: `blocking`
+    #[cfg(all(not(target_arch = "wasm32"), feature = "blocking"))]
+    pub fn build_blocking(mut self) -> std::io::Result<crate::blocking::Client<M>> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let runtime = Arc::new(runtime);
+        if self.spawner.is_none() {
+            self.spawner = Some(Arc::new(TokioSpawner::with_handle(
+                runtime.handle().clone()
+            )));
+        }
+        Ok(crate::blocking::Client::new(self.build(), runtime))
+    }
 }
@@ -1,14 +1,28 @@
 use crate::{
-    utils::progressive_hash, Exchange, ExchangeResult, GraphQLQuery, HeaderPair, Operation,
-    OperationMeta, QueryBody, QueryError, QueryOptions, RequestPolicy, Response
+    activity::{ActivityTracker, NetworkStatus},
+    client::prefetch::Prefetches,
+    devtools::{Devtools, DevtoolsEvent},
+    exchange::{Client, OperationType},
+    spawner::Spawner,
+    types::OperationOptions,
+    utils::progressive_hash,
+    Exchange, ExchangeResult, GraphQLQuery, HeaderPair, Operation, OperationMeta, QueryBody,
+    QueryError, QueryOptions, RefetchQuery, RequestPolicy, ResetOptions, Response
 };
-use parking_lot::Mutex;
-use std::{collections::HashMap, sync::Arc};
+use futures::future::{self, BoxFuture};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
 
+#[cfg(feature = "scheduling")]
+use crate::default_exchanges::Priority;
+#[cfg(feature = "dynamic")]
+use crate::dynamic::{DocumentCache, DynamicQuery, DynamicVariables};
 #[cfg(feature = "observable")]
-use crate::client::observable::Subscription;
-use crate::{exchange::Client, types::OperationOptions};
-use serde::de::DeserializeOwned;
+use crate::{client::observable::Subscription, types::OverflowStrategy};
+#[cfg(feature = "observable")]
+use parking_lot::Mutex;
+#[cfg(feature = "observable")]
+use std::collections::HashMap;
 
 // SAFETY: JavaScript doesn't have multi-threading
 // The only non-send value is the pointer in JsValue
@@ -26,36 +40,71 @@ pub struct ClientImpl<M: Exchange> {
     pub(crate) request_policy: RequestPolicy,
     #[cfg(feature = "observable")]
     pub(crate) active_subscriptions: Arc<Mutex<HashMap<u64, Subscription>>>,
+    #[cfg(feature = "observable")]
+    pub(crate) overflow_strategy: OverflowStrategy,
+    pub(crate) spawner: Arc<dyn Spawner>,
+    pub(crate) devtools: Option<Arc<Devtools>>,
+    pub(crate) prefetches: Prefetches,
+    pub(crate) activity: ActivityTracker,
+    #[cfg(feature = "dynamic")]
+    pub(crate) documents: DocumentCache,
     #[cfg(target_arch = "wasm32")]
     pub(crate) fetch: Option<js_sys::Function>
 }
 
 impl<M: Exchange> Client for Arc<ClientImpl<M>> {
     fn rerun_query(&self, query_key: u64) {
-        if cfg!(feature = "observable") {
-            super::observable::rerun_query(self, query_key);
-        }
+        #[cfg(feature = "observable")]
+        super::observable::rerun_query(self, query_key);
+        #[cfg(not(feature = "observable"))]
+        let _ = query_key;
     }
 
     fn push_result<R>(&self, key: u64, result: ExchangeResult<R>)
     where
         R: DeserializeOwned + Send + Sync + Clone + 'static
     {
-        if cfg!(feature = "observable") {
-            super::observable::push_result(self, key, result);
+        #[cfg(feature = "observable")]
+        super::observable::push_result(self, key, result);
+        #[cfg(not(feature = "observable"))]
+        let _ = (key, result);
+    }
+
+    fn emit_event<F: FnOnce() -> DevtoolsEvent>(&self, event: F) {
+        if let Some(ref devtools) = self.devtools {
+            devtools.emit(event);
         }
     }
+
+    fn report_network_status(&self, status: NetworkStatus) {
+        self.activity.set_network_status(status);
+    }
+
+    fn wait_until_online(&self) -> BoxFuture<'static, ()> {
+        self.activity.wait_until_online()
+    }
 }
 
 impl<M: Exchange> ClientImpl<M> {
+    /// The spawner used to run background tasks, such as subscription reruns.
+    pub fn spawner(&self) -> &dyn Spawner {
+        &*self.spawner
+    }
+
+    /// The devtools of this client, if they're enabled.
+    pub fn devtools(&self) -> Option<&Arc<Devtools>> {
+        self.devtools.as_ref()
+    }
+
     #[cfg(feature = "observable")]
     pub(crate) fn clear_observable(&self, key: u64, index: usize) {
         let mut subscriptions = self.active_subscriptions.lock();
-        if let Some(subscription) = subscriptions.get_mut(&key) {
-            subscription.listeners.remove(index);
-            if subscription.listeners.is_empty() {
-                subscriptions.remove(&key);
-            }
+        let is_empty = subscriptions
+            .get(&key)
+            .map(|subscription| subscription.listeners.remove(index))
+            .unwrap_or(false);
+        if is_empty {
+            subscriptions.remove(&key);
         }
     }
 
@@ -63,6 +112,14 @@ impl<M: Exchange> ClientImpl<M> {
         self: &Arc<Self>,
         operation: Operation<Q::Variables>
     ) -> Result<Response<Q::ResponseData>, QueryError> {
+        self.emit_event(|| DevtoolsEvent::OperationStarted {
+            key: operation.key,
+            operation_name: operation.query.operation_name.clone(),
+            operation_type: operation.meta.operation_type.clone(),
+            url: operation.options.url.clone(),
+            request_policy: operation.options.request_policy.clone()
+        });
+        let _in_flight = self.activity.start(&operation.meta.operation_type);
         self.exchange
             .run::<Q, _>(operation, self.clone())
             .await
@@ -85,10 +142,110 @@ impl<M: Exchange> ClientImpl<M> {
         options: QueryOptions
     ) -> Result<Response<Q::ResponseData>, QueryError> {
         let (query, meta) = Q::build_query(variables);
+        let refetch_queries = if meta.operation_type == OperationType::Mutation {
+            options.refetch_queries.clone()
+        } else {
+            Vec::new()
+        };
+        let await_refetch_queries = options.await_refetch_queries;
+
         let operation = self.create_request_operation::<Q>(query, meta, options);
-        self.execute_request_operation::<Q>(operation).await
+        let result = self.execute_request_operation::<Q>(operation).await;
+
+        match result {
+            Ok(ref response) if response.errors.is_none() && !refetch_queries.is_empty() => {
+                self.refetch_queries(&refetch_queries, await_refetch_queries)
+                    .await
+            }
+            _ => {}
+        }
+        result
+    }
+
+    pub async fn prefetch_with_options<Q: GraphQLQuery>(
+        self: &Arc<Self>,
+        _query: Q,
+        variables: Q::Variables,
+        options: QueryOptions
+    ) -> Result<(), QueryError> {
+        self.prefetch_operation::<Q>(variables, options).await
+    }
+
+    pub async fn prefetch_many<Q: GraphQLQuery, I>(
+        self: &Arc<Self>,
+        _query: Q,
+        variables: I
+    ) -> Result<(), QueryError>
+    where
+        I: IntoIterator<Item = Q::Variables>
+    {
+        let prefetches = variables
+            .into_iter()
+            .map(|variables| self.prefetch_operation::<Q>(variables, QueryOptions::default()));
+        future::join_all(prefetches).await.into_iter().collect()
+    }
+
+    pub fn cancel_prefetches(&self) {
+        self.prefetches.cancel_all()
+    }
+
+    async fn prefetch_operation<Q: GraphQLQuery>(
+        self: &Arc<Self>,
+        variables: Q::Variables,
+        mut options: QueryOptions
+    ) -> Result<(), QueryError> {
+        if options.request_policy.is_none() {
+            options.request_policy = Some(RequestPolicy::CacheFirst);
+        }
+        #[cfg(feature = "scheduling")]
+        {
+            let extensions = options.extensions.get_or_insert_with(Default::default);
+            if extensions.get::<Priority, _>("Priority").is_none() {
+                // Copies the map if the caller still holds on to it
+                Arc::make_mut(extensions).insert(Priority::LOW);
+            }
+        }
+
+        let (query, meta) = Q::build_query(variables);
+        let operation = self.create_request_operation::<Q>(query, meta, options);
+        self.prefetches
+            .run(self.execute_request_operation::<Q>(operation))
+            .await??;
+        Ok(())
+    }
+
+    #[cfg(feature = "dynamic")]
+    pub async fn query_dynamic_with_options(
+        self: &Arc<Self>,
+        document: &str,
+        variables: serde_json::Value,
+        options: QueryOptions
+    ) -> Result<Response<serde_json::Value>, QueryError> {
+        let document = self.documents.get(document)?;
+        let variables = DynamicVariables::new(document.clone(), variables);
+        let response = self
+            .query_with_options(DynamicQuery, variables.clone(), options)
+            .await?;
+        let data = response.data.map(|data| {
+            let mut data = data.0;
+            document.restore_aliases(&mut data, variables.values());
+            data
+        });
+        Ok(Response {
+            debug_info: response.debug_info,
+            data,
+            errors: response.errors
+        })
     }
 
+    #[cfg(feature = "observable")]
+    async fn refetch_queries(self: &Arc<Self>, queries: &[RefetchQuery], wait: bool) {
+        super::observable::refetch_queries(self, queries, wait).await
+    }
+
+    #[cfg(not(feature = "observable"))]
+    async fn refetch_queries(self: &Arc<Self>, _queries: &[RefetchQuery], _wait: bool) {}
+
     #[cfg(feature = "observable")]
     pub fn subscribe<Q: GraphQLQuery + 'static>(
         self: &Arc<Self>,
@@ -108,6 +265,18 @@ impl<M: Exchange> ClientImpl<M> {
         super::observable::subscribe_with_options(self, _query, variables, options)
     }
 
+    pub fn reset_store(self: &Arc<Self>) {
+        self.reset_store_with_options(ResetOptions::default())
+    }
+
+    pub fn reset_store_with_options(self: &Arc<Self>, options: ResetOptions) {
+        self.exchange.reset();
+        #[cfg(feature = "observable")]
+        super::observable::reset_subscriptions(self, options.clear_only);
+        #[cfg(not(feature = "observable"))]
+        let _ = options;
+    }
+
     pub(crate) fn create_request_operation<Q: GraphQLQuery>(
         &self,
         query: QueryBody<Q::Variables>,
@@ -5,13 +5,16 @@ use std::sync::Arc;
 mod builder;
 mod r#impl;
 #[cfg(feature = "observable")]
-mod observable;
+pub(crate) mod observable;
+pub(crate) mod prefetch;
 
 use crate::{
-    default_exchanges::TerminatorExchange, Exchange, GraphQLQuery, QueryError, QueryOptions,
-    Response
+    activity::{ActivityStream, NetworkStatus, NetworkStatusStream},
+    default_exchanges::TerminatorExchange, devtools::Devtools, Exchange, GraphQLQuery, QueryError, QueryOptions,
+    ResetOptions, Response
 };
 pub use builder::ClientBuilder;
+pub use prefetch::PrefetchCancelledError;
 pub use r#impl::ClientImpl;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
@@ -85,6 +88,123 @@ impl<M: Exchange> Client<M> {
         self.0.query_with_options(_query, variables, options).await
     }
 
+    /// Executes an operation that's only known at runtime with the given JSON variables.
+    /// The document must contain a single named operation and may contain fragments.
+    /// It's parsed the first time it's used and runs through the exchanges like any other query.
+    /// Returns the result of the operation, or a `QueryError` if the document is invalid or one of
+    /// the exchanges encountered a fatal error.
+    ///
+    /// See the [dynamic](../dynamic/index.html) module for details.
+    ///
+    /// Requires feature: `dynamic`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use artemis::ClientBuilder;
+    /// # use serde_json::json;
+    /// # tokio_test::block_on(async {
+    /// let client = ClientBuilder::new("http://localhost:8080/graphql")
+    ///     .with_default_exchanges()
+    ///     .build();
+    ///
+    /// let query = "query GetConference($id: ID!) { conference(id: $id) { id name } }";
+    /// let result = client.query_dynamic(query, json!({ "id": "1" })).await.unwrap();
+    ///
+    /// assert!(result.data.is_some())
+    /// # });
+    /// ```
+    #[cfg(feature = "dynamic")]
+    pub async fn query_dynamic(
+        &self,
+        document: &str,
+        variables: serde_json::Value
+    ) -> Result<Response<serde_json::Value>, QueryError> {
+        self.0
+            .query_dynamic_with_options(document, variables, QueryOptions::default())
+            .await
+    }
+
+    /// Executes an operation that's only known at runtime with the given JSON variables and options.
+    /// See [`query_dynamic`](#method.query_dynamic) for details.
+    ///
+    /// Requires feature: `dynamic`
+    #[cfg(feature = "dynamic")]
+    pub async fn query_dynamic_with_options(
+        &self,
+        document: &str,
+        variables: serde_json::Value,
+        options: QueryOptions
+    ) -> Result<Response<serde_json::Value>, QueryError> {
+        self.0
+            .query_dynamic_with_options(document, variables, options)
+            .await
+    }
+
+    /// Runs a query in the background so its result is cached by the time it's needed,
+    /// for example to preload the data of the next screen.
+    /// The query uses the `CacheFirst` policy, so it isn't sent if it's already cached, and the
+    /// `Priority::LOW` hint if the `scheduling` feature is enabled.
+    /// The result is only stored by the exchanges and not returned, so the future resolves to
+    /// `()`, or a `QueryError` if one of the exchanges encountered a fatal error or the prefetch
+    /// was cancelled with [`cancel_prefetches`](#method.cancel_prefetches).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use artemis_test::get_conference::{GetConference, get_conference::Variables};
+    /// # use artemis::ClientBuilder;
+    /// # tokio_test::block_on(async {
+    /// let client = ClientBuilder::new("http://localhost:8080/graphql")
+    ///     .with_default_exchanges()
+    ///     .build();
+    ///
+    /// client.prefetch(GetConference, Variables { id: "1".to_string() }).await.unwrap();
+    /// # });
+    /// ```
+    pub async fn prefetch<Q: GraphQLQuery>(
+        &self,
+        query: Q,
+        variables: Q::Variables
+    ) -> Result<(), QueryError> {
+        self.0
+            .prefetch_with_options(query, variables, QueryOptions::default())
+            .await
+    }
+
+    /// Prefetches a query with options. The policy is only set to the prefetch default if it's
+    /// `None`, and the low priority is added to the extensions unless they already have a
+    /// priority. See [`prefetch`](#method.prefetch) for details.
+    pub async fn prefetch_with_options<Q: GraphQLQuery>(
+        &self,
+        query: Q,
+        variables: Q::Variables,
+        options: QueryOptions
+    ) -> Result<(), QueryError> {
+        self.0.prefetch_with_options(query, variables, options).await
+    }
+
+    /// Prefetches a query once for each set of variables, running them concurrently.
+    /// Resolves once all of them are done, returning the first error if any failed.
+    /// See [`prefetch`](#method.prefetch) for details.
+    pub async fn prefetch_many<Q: GraphQLQuery, I>(
+        &self,
+        query: Q,
+        variables: I
+    ) -> Result<(), QueryError>
+    where
+        I: IntoIterator<Item = Q::Variables>
+    {
+        self.0.prefetch_many(query, variables).await
+    }
+
+    /// Cancels all outstanding prefetches, for example when the user navigates somewhere else.
+    /// Their futures resolve to a [`PrefetchCancelledError`](./struct.PrefetchCancelledError.html).
+    /// Prefetches started after this aren't affected.
+    pub fn cancel_prefetches(&self) {
+        self.0.cancel_prefetches()
+    }
+
     /// Subscribes to a query, returning any potential early results, the initial result and any future updates
     /// The function returns an `Observable` which can be subscribed to like a regular stream.
     /// Dropping the `Observable` will cancel the subscription.
@@ -147,4 +267,56 @@ impl<M: Exchange> Client<M> {
     ) -> observable::OperationObservable<Q, M> {
         self.0.subscribe_with_options(_query, variables, options)
     }
+
+    /// Clears all state held by the exchanges, such as cached results and in-flight queries,
+    /// then reruns every active subscription so it receives fresh data.
+    /// Use this when the user logs out or switches accounts.
+    ///
+    /// Exchanges are cleared through [`Exchange::reset`](../exchange/trait.Exchange.html#method.reset).
+    pub fn reset_store(&self) {
+        self.0.reset_store()
+    }
+
+    /// Clears all state held by the exchanges with options.
+    /// If `clear_only` is set, active subscriptions receive an empty response instead of being rerun.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use artemis::{ClientBuilder, ResetOptions};
+    /// let client = ClientBuilder::new("http://localhost:8080/graphql")
+    ///     .with_default_exchanges()
+    ///     .build();
+    ///
+    /// client.reset_store_with_options(ResetOptions { clear_only: true });
+    /// ```
+    pub fn reset_store_with_options(&self, options: ResetOptions) {
+        self.0.reset_store_with_options(options)
+    }
+
+    /// Returns a stream of the number of operations in flight, grouped by operation type.
+    /// It starts with the current activity, then yields every change.
+    /// See the [activity](../activity/index.html) module for details.
+    pub fn activity(&self) -> ActivityStream {
+        self.0.activity.activity()
+    }
+
+    /// Returns a stream of whether the server is reachable.
+    /// It starts with the current status, then yields every change.
+    /// See the [activity](../activity/index.html) module for details.
+    pub fn network_status(&self) -> NetworkStatusStream {
+        self.0.activity.network_status()
+    }
+
+    /// Whether the server was reachable the last time it was contacted or probed.
+    pub fn is_online(&self) -> bool {
+        self.0.activity.current_network_status() == NetworkStatus::Online
+    }
+
+    /// Returns the client's devtools, or `None` if they weren't enabled with
+    /// [`ClientBuilder::with_devtools`](./struct.ClientBuilder.html#method.with_devtools).
+    /// See the [devtools](../devtools/index.html) module for details.
+    pub fn devtools(&self) -> Option<&Arc<Devtools>> {
+        self.0.devtools()
+    }
 }
@@ -1,22 +1,206 @@
 use crate::{
-    client::ClientImpl, types::Observable, utils::progressive_hash, Exchange, ExchangeResult,
-    GraphQLQuery, QueryError, QueryOptions, Response
+    client::ClientImpl,
+    devtools::DevtoolsEvent,
+    exchange::Client,
+    types::{Observable, OverflowStrategy},
+    utils::progressive_hash,
+    Exchange, ExchangeResult, GraphQLQuery, QueryError, QueryOptions, RefetchQuery, RequestPolicy,
+    Response
 };
-use futures::{channel::mpsc::Sender, SinkExt};
-use serde::de::DeserializeOwned;
+use futures::{
+    future::BoxFuture,
+    task::{Context, Poll, Waker}
+};
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Serialize};
 use stable_vec::StableVec;
-use std::{any::Any, future::Future, pin::Pin, sync::Arc};
+use std::{any::Any, borrow::Cow, collections::VecDeque, sync::Arc};
 
 pub type OperationObservable<Q, M> =
     Observable<Result<Response<<Q as GraphQLQuery>::ResponseData>, QueryError>, M>;
 
-type RerunFn =
-    Arc<dyn Fn() -> Pin<Box<dyn Future<Output = Arc<dyn Any + Send + Sync>> + Send>> + Send + Sync>;
+// Takes an optional request policy that overrides the one of the subscription
+type RerunFn = Arc<dyn Fn(Option<RequestPolicy>) -> BoxFuture<'static, ()> + Send + Sync>;
+type ClearFn = Arc<dyn Fn() + Send + Sync>;
+type LatestResultFn = Arc<dyn Fn() -> Option<serde_json::Value> + Send + Sync>;
 
 pub(crate) struct Subscription {
-    pub(crate) listeners: StableVec<Sender<Arc<dyn Any + Send + Sync>>>,
+    pub(crate) listeners: Arc<dyn ListenerSet>,
     // This captures the type and variables of the query without requiring generics, so we can store it in a hashmap
-    pub(crate) rerun: RerunFn
+    pub(crate) rerun: RerunFn,
+    pub(crate) operation_name: Cow<'static, str>,
+    // Discards pending results and pushes an empty response
+    pub(crate) clear: ClearFn,
+    // Serializes the latest result for devtools. Only returns a value if devtools are enabled
+    pub(crate) latest_result: LatestResultFn
+}
+
+/// The type-erased side of a `Broadcaster`, used for bookkeeping that doesn't need to know
+/// the result type.
+pub(crate) trait ListenerSet: Send + Sync {
+    /// Removes the listener at `index`, returning `true` if there are no listeners left.
+    fn remove(&self, index: usize) -> bool;
+    fn listener_count(&self) -> usize;
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
+}
+
+/// A typed set of listeners for a single subscription.
+/// Broadcasting never waits on a listener, it only pushes into that listener's queue
+/// according to the client's `OverflowStrategy`.
+pub(crate) struct Broadcaster<T> {
+    listeners: Mutex<StableVec<Arc<Listener<T>>>>,
+    overflow: OverflowStrategy,
+    // Only kept when devtools are enabled, since holding on to the value forces listeners to clone it
+    latest: Option<Mutex<Option<Arc<T>>>>
+}
+
+impl<T: Send + Sync + 'static> Broadcaster<T> {
+    fn new(overflow: OverflowStrategy) -> Self {
+        Self {
+            listeners: Mutex::new(StableVec::new()),
+            overflow,
+            latest: None
+        }
+    }
+
+    fn keep_latest(mut self) -> Self {
+        self.latest = Some(Mutex::new(None));
+        self
+    }
+
+    fn latest(&self) -> Option<Arc<T>> {
+        self.latest
+            .as_ref()
+            .and_then(|latest| latest.lock().clone())
+    }
+
+    fn set_latest(&self, value: &Arc<T>) {
+        if let Some(ref latest) = self.latest {
+            *latest.lock() = Some(value.clone());
+        }
+    }
+
+    fn listen(&self) -> (usize, Arc<Listener<T>>) {
+        let listener = Arc::new(Listener::new());
+        let index = self.listeners.lock().push(listener.clone());
+        (index, listener)
+    }
+
+    fn broadcast(&self, value: T) {
+        let value = Arc::new(value);
+        self.set_latest(&value);
+        for listener in self.listeners.lock().values() {
+            listener.push(value.clone(), self.overflow);
+        }
+    }
+
+    /// Like `broadcast`, but discards any values that haven't been received yet.
+    fn replace(&self, value: T) {
+        let value = Arc::new(value);
+        self.set_latest(&value);
+        for listener in self.listeners.lock().values() {
+            listener.clear();
+            listener.push(value.clone(), self.overflow);
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> ListenerSet for Broadcaster<T> {
+    fn remove(&self, index: usize) -> bool {
+        let mut listeners = self.listeners.lock();
+        listeners.remove(index);
+        listeners.is_empty()
+    }
+
+    fn listener_count(&self) -> usize {
+        self.listeners.lock().num_elements()
+    }
+
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+        self
+    }
+}
+
+/// An item received by a listener.
+pub(crate) enum Received<T> {
+    Value(Arc<T>),
+    /// The listener fell behind and this many values were dropped.
+    Lagged(u64)
+}
+
+struct ListenerState<T> {
+    queue: VecDeque<Arc<T>>,
+    lagged: u64,
+    waker: Option<Waker>
+}
+
+/// The receiving end of a subscription. Values are shared between listeners and only cloned
+/// if more than one listener is still holding on to them when they're received.
+pub(crate) struct Listener<T> {
+    state: Mutex<ListenerState<T>>
+}
+
+impl<T> Listener<T> {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(ListenerState {
+                queue: VecDeque::new(),
+                lagged: 0,
+                waker: None
+            })
+        }
+    }
+
+    fn clear(&self) {
+        let mut state = self.state.lock();
+        state.queue.clear();
+        state.lagged = 0;
+    }
+
+    fn push(&self, value: Arc<T>, overflow: OverflowStrategy) {
+        let waker = {
+            let mut state = self.state.lock();
+            match overflow {
+                OverflowStrategy::KeepLatest => state.queue.clear(),
+                OverflowStrategy::Bounded(capacity) => {
+                    while state.queue.len() >= capacity.max(1) {
+                        state.queue.pop_front();
+                        state.lagged += 1;
+                    }
+                }
+            }
+            state.queue.push_back(value);
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Received<T>> {
+        let mut state = self.state.lock();
+        if state.lagged > 0 {
+            let lagged = state.lagged;
+            state.lagged = 0;
+            Poll::Ready(Received::Lagged(lagged))
+        } else if let Some(value) = state.queue.pop_front() {
+            Poll::Ready(Received::Value(value))
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+type ResultBroadcaster<R> = Broadcaster<Result<Response<R>, QueryError>>;
+
+fn result_to_json<R: Serialize + Clone>(
+    result: &Result<Response<R>, QueryError>
+) -> serde_json::Value {
+    match result {
+        Ok(response) => serde_json::to_value(response).unwrap_or(serde_json::Value::Null),
+        Err(e) => serde_json::json!({ "error": e.to_string() })
+    }
 }
 
 pub fn subscribe_with_options<Q: GraphQLQuery + 'static, M: Exchange>(
@@ -26,90 +210,282 @@ pub fn subscribe_with_options<Q: GraphQLQuery + 'static, M: Exchange>(
     options: QueryOptions
 ) -> super::observable::OperationObservable<Q, M> {
     let (query, meta) = Q::build_query(variables.clone());
-    let (sender, receiver) = futures::channel::mpsc::channel(8);
     let key = progressive_hash(meta.query_key, &variables);
+    let operation_name = query.operation_name.clone();
 
     let operation = client.create_request_operation::<Q>(query, meta, options);
 
-    let observable = {
+    let (index, listener) = {
         let mut subscriptions = client.active_subscriptions.lock();
-        let index = if let Some(subscription) = subscriptions.get_mut(&key) {
-            subscription.listeners.push(sender)
+        if let Some(subscription) = subscriptions.get(&key) {
+            let broadcaster = subscription
+                .listeners
+                .clone()
+                .into_any()
+                .downcast::<ResultBroadcaster<Q::ResponseData>>()
+                .expect("Subscription key collision between queries with different types");
+            broadcaster.listen()
         } else {
+            let mut broadcaster =
+                ResultBroadcaster::<Q::ResponseData>::new(client.overflow_strategy);
+            if client.devtools.is_some() {
+                broadcaster = broadcaster.keep_latest();
+            }
+            let broadcaster = Arc::new(broadcaster);
+            let listener = broadcaster.listen();
             let client = client.clone();
+            let clear = {
+                let broadcaster = broadcaster.clone();
+                Arc::new(move || {
+                    broadcaster.replace(Ok(Response {
+                        debug_info: None,
+                        data: None,
+                        errors: None
+                    }))
+                })
+            };
+            let latest_result = {
+                let broadcaster = broadcaster.clone();
+                Arc::new(move || broadcaster.latest().map(|latest| result_to_json(&latest)))
+            };
             let subscription = Subscription {
-                listeners: vec![sender].into(),
-                rerun: Arc::new(move || {
+                listeners: broadcaster.clone(),
+                rerun: Arc::new(move |request_policy| {
                     let client = client.clone();
-                    let operation = operation.clone();
+                    let mut operation = operation.clone();
+                    if let Some(request_policy) = request_policy {
+                        operation.options.request_policy = request_policy;
+                    }
+                    let broadcaster = broadcaster.clone();
 
                     Box::pin(async move {
+                        client.emit_event(|| DevtoolsEvent::Rerun { key });
                         let res = client.execute_request_operation::<Q>(operation).await;
-                        let res_boxed: Arc<dyn std::any::Any + Send + Sync> = Arc::new(res);
-                        res_boxed
+                        broadcaster.broadcast(res);
                     })
-                })
+                }),
+                operation_name,
+                clear,
+                latest_result
             };
-            subscriptions.insert(key.clone(), subscription);
-            0
-        };
-        super::observable::Observable::new(key, receiver, client.clone(), index)
+            subscriptions.insert(key, subscription);
+            listener
+        }
     };
 
     rerun_query(client, key);
-    observable
+    Observable::new(key, listener, client.clone(), index)
 }
 
 pub fn rerun_query<M: Exchange>(client: &Arc<ClientImpl<M>>, id: u64) {
-    let client = client.clone();
-    let fut = async move {
-        let rerun = {
-            let subscriptions = client.active_subscriptions.clone();
-            let subscriptions = subscriptions.lock();
-            subscriptions.get(&id).map(|sub| sub.rerun.clone())
-        };
-        let value = if let Some(rerun) = rerun {
-            Some(rerun().await)
-        } else {
-            None
-        };
+    let rerun = {
+        let subscriptions = client.active_subscriptions.lock();
+        subscriptions.get(&id).map(|sub| sub.rerun.clone())
+    };
+    if let Some(rerun) = rerun {
+        client.spawner.spawn(rerun(None));
+    }
+}
 
-        let subscriptions = client.active_subscriptions.clone();
-        let mut subscriptions = subscriptions.lock();
-        let subscription = subscriptions.get_mut(&id);
+pub async fn refetch_queries<M: Exchange>(
+    client: &Arc<ClientImpl<M>>,
+    queries: &[RefetchQuery],
+    wait: bool
+) {
+    let rerun_fns: Vec<RerunFn> = {
+        let subscriptions = client.active_subscriptions.lock();
+        subscriptions
+            .iter()
+            .filter(|(key, sub)| {
+                queries
+                    .iter()
+                    .any(|query| query.matches(**key, &sub.operation_name))
+            })
+            .map(|(_, sub)| sub.rerun.clone())
+            .collect()
+    };
 
-        if let Some(Subscription { listeners, .. }) = subscription {
-            let value = value.unwrap();
-            for listener in listeners.values_mut() {
-                futures::executor::block_on(listener.send(value.clone())).unwrap();
-            }
+    let request_policy = Some(RequestPolicy::NetworkOnly);
+    if wait {
+        futures::future::join_all(rerun_fns.iter().map(|rerun| rerun(request_policy.clone())))
+            .await;
+    } else {
+        for rerun in rerun_fns {
+            client.spawner.spawn(rerun(request_policy.clone()));
         }
-    };
-    spawn(fut);
+    }
+}
+
+pub fn reset_subscriptions<M: Exchange>(client: &Arc<ClientImpl<M>>, clear_only: bool) {
+    if clear_only {
+        let clear_fns: Vec<ClearFn> = {
+            let subscriptions = client.active_subscriptions.lock();
+            subscriptions
+                .values()
+                .map(|sub| sub.clear.clone())
+                .collect()
+        };
+        for clear in clear_fns {
+            clear();
+        }
+    } else {
+        let rerun_fns: Vec<RerunFn> = {
+            let subscriptions = client.active_subscriptions.lock();
+            subscriptions
+                .values()
+                .map(|sub| sub.rerun.clone())
+                .collect()
+        };
+        for rerun in rerun_fns {
+            client.spawner.spawn(rerun(None));
+        }
+    }
 }
 
 pub fn push_result<R, M: Exchange>(client: &ClientImpl<M>, id: u64, result: ExchangeResult<R>)
 where
     R: DeserializeOwned + Send + Sync + Clone + 'static
 {
-    let mut subscriptions = client.active_subscriptions.lock();
-    let subscription = subscriptions.get_mut(&id);
+    let listeners = {
+        let subscriptions = client.active_subscriptions.lock();
+        subscriptions.get(&id).map(|sub| sub.listeners.clone())
+    };
 
-    let result = Arc::new(result);
+    let broadcaster = listeners
+        .and_then(|listeners| listeners.into_any().downcast::<ResultBroadcaster<R>>().ok());
+    if let Some(broadcaster) = broadcaster {
+        broadcaster.broadcast(result.map(|result| result.response));
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test {
+    use super::{Broadcaster, Received};
+    use crate::{
+        spawner::LocalPoolSpawner,
+        test_utils::{Data, FakeExchange, TestMutation, TestQuery},
+        ClientBuilder, OverflowStrategy, QueryOptions, RefetchQuery, ResetOptions
+    };
+    use futures::{
+        executor::LocalPool,
+        task::{noop_waker, Context, Poll},
+        FutureExt, StreamExt
+    };
 
-    if let Some(Subscription { listeners, .. }) = subscription {
-        for listener in listeners.values_mut() {
-            futures::executor::block_on(listener.send(result.clone())).unwrap()
+    fn recv(listener: &super::Listener<u32>) -> Option<Received<u32>> {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        match listener.poll_recv(&mut cx) {
+            Poll::Ready(received) => Some(received),
+            Poll::Pending => None
         }
     }
-}
 
-#[cfg(target_arch = "wasm32")]
-fn spawn(fut: impl Future<Output = ()> + Send + 'static) {
-    wasm_bindgen_futures::spawn_local(fut);
-}
+    #[test]
+    fn test_keep_latest() {
+        let broadcaster = Broadcaster::new(OverflowStrategy::KeepLatest);
+        let (_, listener) = broadcaster.listen();
+
+        broadcaster.broadcast(1);
+        broadcaster.broadcast(2);
+
+        assert!(matches!(recv(&listener), Some(Received::Value(v)) if *v == 2));
+        assert!(recv(&listener).is_none());
+    }
+
+    #[test]
+    fn test_bounded_lag() {
+        let broadcaster = Broadcaster::new(OverflowStrategy::Bounded(2));
+        let (_, slow) = broadcaster.listen();
+        let (_, fast) = broadcaster.listen();
+
+        broadcaster.broadcast(1);
+        assert!(matches!(recv(&fast), Some(Received::Value(v)) if *v == 1));
+        broadcaster.broadcast(2);
+        broadcaster.broadcast(3);
+
+        assert!(matches!(recv(&slow), Some(Received::Lagged(1))));
+        assert!(matches!(recv(&slow), Some(Received::Value(v)) if *v == 2));
+        assert!(matches!(recv(&slow), Some(Received::Value(v)) if *v == 3));
+        assert!(recv(&slow).is_none());
+    }
+
+    #[test]
+    fn test_removed_listener() {
+        let broadcaster = Broadcaster::new(OverflowStrategy::default());
+        let (index, listener) = broadcaster.listen();
+
+        assert!(super::ListenerSet::remove(&broadcaster, index));
+        broadcaster.broadcast(1);
+        assert!(recv(&listener).is_none());
+    }
+
+    #[test]
+    fn test_local_pool_spawner() {
+        let mut pool = LocalPool::new();
+        let client = ClientBuilder::new("http://localhost:4000/graphql")
+            .with_exchange(FakeExchange)
+            .with_spawner(LocalPoolSpawner::new(&pool.spawner()).unwrap())
+            .build();
+
+        let mut observable = client.subscribe(TestQuery, ());
+        let result = pool.run_until(observable.next()).unwrap().unwrap();
 
-#[cfg(not(target_arch = "wasm32"))]
-fn spawn(fut: impl Future<Output = ()> + Send + 'static) {
-    tokio::spawn(fut);
+        assert_eq!(result.data, Some(Data(1)));
+    }
+
+    #[test]
+    fn test_reset_store() {
+        let mut pool = LocalPool::new();
+        let client = ClientBuilder::new("http://localhost:4000/graphql")
+            .with_exchange(FakeExchange)
+            .with_spawner(LocalPoolSpawner::new(&pool.spawner()).unwrap())
+            .build();
+
+        let mut observable = client.subscribe(TestQuery, ());
+        let result = pool.run_until(observable.next()).unwrap().unwrap();
+        assert_eq!(result.data, Some(Data(1)));
+
+        client.reset_store_with_options(ResetOptions { clear_only: true });
+        let result = pool.run_until(observable.next()).unwrap().unwrap();
+        assert_eq!(result.data, None);
+
+        client.reset_store();
+        let result = pool.run_until(observable.next()).unwrap().unwrap();
+        assert_eq!(result.data, Some(Data(1)));
+    }
+
+    #[test]
+    fn test_refetch_queries() {
+        let mut pool = LocalPool::new();
+        let client = ClientBuilder::new("http://localhost:4000/graphql")
+            .with_exchange(FakeExchange)
+            .with_spawner(LocalPoolSpawner::new(&pool.spawner()).unwrap())
+            .build();
+
+        let mut observable = client.subscribe(TestQuery, ());
+        pool.run_until(observable.next()).unwrap().unwrap();
+
+        pool.run_until(client.query(TestMutation, ())).unwrap();
+        assert!(observable.next().now_or_never().is_none());
+
+        let options = QueryOptions {
+            refetch_queries: vec![RefetchQuery::query(TestQuery, ())],
+            await_refetch_queries: true,
+            ..QueryOptions::default()
+        };
+        pool.run_until(client.query_with_options(TestMutation, (), options))
+            .unwrap();
+        let result = observable.next().now_or_never().unwrap().unwrap().unwrap();
+        assert_eq!(result.data, Some(Data(1)));
+
+        let options = QueryOptions {
+            refetch_queries: vec!["Test".into()],
+            await_refetch_queries: true,
+            ..QueryOptions::default()
+        };
+        pool.run_until(client.query_with_options(TestMutation, (), options))
+            .unwrap();
+        assert!(observable.next().now_or_never().is_some());
+    }
 }
@@ -0,0 +1,167 @@
+use futures::future::{self, AbortHandle};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering}
+};
+
+/// The error returned by prefetches that were cancelled with
+/// [`Client::cancel_prefetches`](./struct.Client.html#method.cancel_prefetches).
+#[derive(Debug, Clone)]
+pub struct PrefetchCancelledError;
+
+impl Error for PrefetchCancelledError {}
+
+impl fmt::Display for PrefetchCancelledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "prefetch was cancelled")
+    }
+}
+
+/// The outstanding prefetches of a client, so they can be cancelled together.
+#[derive(Default)]
+pub(crate) struct Prefetches {
+    next_id: AtomicU64,
+    handles: Mutex<HashMap<u64, AbortHandle>>
+}
+
+/// Removes a prefetch from the outstanding ones when it finishes or is dropped.
+struct Registration<'a> {
+    prefetches: &'a Prefetches,
+    id: u64
+}
+
+impl<'a> Drop for Registration<'a> {
+    fn drop(&mut self) {
+        self.prefetches.handles.lock().remove(&self.id);
+    }
+}
+
+impl Prefetches {
+    /// Runs the future until it completes or all prefetches are cancelled.
+    pub(crate) async fn run<F: Future>(
+        &self,
+        future: F
+    ) -> Result<F::Output, PrefetchCancelledError> {
+        let (future, handle) = future::abortable(future);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.handles.lock().insert(id, handle);
+        let _registration = Registration {
+            prefetches: self,
+            id
+        };
+
+        future.await.map_err(|_| PrefetchCancelledError)
+    }
+
+    pub(crate) fn cancel_all(&self) {
+        for (_, handle) in self.handles.lock().drain() {
+            handle.abort();
+        }
+    }
+
+    #[cfg(all(test, not(target_arch = "wasm32")))]
+    pub(crate) fn len(&self) -> usize {
+        self.handles.lock().len()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test {
+    use super::PrefetchCancelledError;
+    use crate::{
+        test_utils::{data_response, TestExchange, TestQuery, TEST_URL},
+        ClientBuilder, RequestPolicy
+    };
+    use futures::future;
+    use parking_lot::Mutex;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_prefetch() {
+        let options = Arc::new(Mutex::new(Vec::new()));
+        let recorded = options.clone();
+        let client = ClientBuilder::new(TEST_URL)
+            .with_exchange(TestExchange::new(move |request| {
+                recorded.lock().push(request.operation.options);
+                async { Ok(data_response(json!(1))) }
+            }))
+            .build();
+
+        client.prefetch(TestQuery, ()).await.unwrap();
+        client.prefetch_many(TestQuery, vec![(), ()]).await.unwrap();
+
+        let options = options.lock();
+        assert_eq!(options.len(), 3);
+        for options in options.iter() {
+            assert_eq!(options.request_policy, RequestPolicy::CacheFirst);
+            #[cfg(feature = "scheduling")]
+            {
+                let priority = options
+                    .extensions
+                    .as_ref()
+                    .and_then(|extensions| extensions.get("Priority"));
+                assert_eq!(priority, Some(crate::default_exchanges::Priority::LOW));
+            }
+        }
+        assert_eq!(client.0.prefetches.len(), 0);
+    }
+
+    #[cfg(feature = "scheduling")]
+    #[tokio::test]
+    async fn test_prefetch_with_extensions() {
+        use crate::{default_exchanges::Priority, exchange::Extension, ExtensionMap, QueryOptions};
+
+        #[derive(Clone, Debug, PartialEq)]
+        struct Marker(u32);
+
+        impl Extension for Marker {}
+
+        let options = Arc::new(Mutex::new(Vec::new()));
+        let recorded = options.clone();
+        let client = ClientBuilder::new(TEST_URL)
+            .with_exchange(TestExchange::new(move |request| {
+                recorded.lock().push(request.operation.options);
+                async { Ok(data_response(json!(1))) }
+            }))
+            .build();
+
+        let mut extensions = ExtensionMap::new();
+        extensions.insert(Marker(1));
+        let options_with_extensions = QueryOptions {
+            extensions: Some(Arc::new(extensions)),
+            ..QueryOptions::default()
+        };
+        client
+            .prefetch_with_options(TestQuery, (), options_with_extensions)
+            .await
+            .unwrap();
+
+        let options = options.lock();
+        let extensions = options[0].extensions.as_ref().unwrap();
+        assert_eq!(extensions.get("Priority"), Some(Priority::LOW));
+        assert_eq!(extensions.get("Marker"), Some(Marker(1)));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_prefetches() {
+        let client = ClientBuilder::new(TEST_URL)
+            .with_exchange(TestExchange::new(|_| future::pending()))
+            .build();
+
+        let (result, _) = future::join(client.prefetch_many(TestQuery, vec![(), ()]), async {
+            tokio::task::yield_now().await;
+            assert_eq!(client.0.prefetches.len(), 2);
+            client.cancel_prefetches();
+        })
+        .await;
+
+        let error = result.unwrap_err();
+        assert!(error.downcast_ref::<PrefetchCancelledError>().is_some());
+        assert_eq!(client.0.prefetches.len(), 0);
+    }
+}
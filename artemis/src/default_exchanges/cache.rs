@@ -1,4 +1,5 @@
 use crate::{
+    devtools::DevtoolsEvent,
     exchange::Client,
     types::{ExchangeResult, Operation, OperationResult},
     DebugInfo, Exchange, ExchangeFactory, GraphQLQuery, OperationMeta, OperationType, QueryError,
@@ -6,12 +7,13 @@ use crate::{
 };
 use std::{
     any::Any,
+    borrow::Cow,
     collections::{HashMap, HashSet},
     sync::{Arc, Mutex}
 };
 
 type ResultCache = Arc<Mutex<HashMap<u64, Box<dyn Any + Send>>>>;
-type OperationCache = Arc<Mutex<HashMap<&'static str, HashSet<u64>>>>;
+type OperationCache = Arc<Mutex<HashMap<Cow<'static, str>, HashSet<u64>>>>;
 
 /// The default caching exchange.
 ///
@@ -77,7 +79,7 @@ impl<TNext: Exchange> CacheExchangeImpl<TNext> {
             let mut operation_cache = self.operation_cache.lock().unwrap();
             for involved_type in involved_types {
                 operation_cache
-                    .entry(*involved_type)
+                    .entry(involved_type.clone())
                     .and_modify(|entry| {
                         entry.insert(key);
                     })
@@ -116,6 +118,11 @@ impl<TNext: Exchange> CacheExchangeImpl<TNext> {
             ops.insert(key);
             ops
         };
+        client.emit_event(|| DevtoolsEvent::Invalidated {
+            key,
+            exchange: "CacheExchange",
+            dependencies: involved_types.iter().map(ToString::to_string).collect()
+        });
         {
             let mut cache = self.result_cache.lock().unwrap();
             for op in ops_to_remove.iter() {
@@ -141,6 +148,12 @@ impl<TNext: Exchange> Exchange for CacheExchangeImpl<TNext> {
         }
 
         if !self.is_operation_cached::<Q>(&operation) {
+            if operation.meta.operation_type == OperationType::Query {
+                client.emit_event(|| DevtoolsEvent::CacheMiss {
+                    key: operation.key,
+                    exchange: "CacheExchange"
+                });
+            }
             let res = self.next.run::<Q, _>(operation, client.clone()).await?;
 
             match res.meta.operation_type {
@@ -160,13 +173,18 @@ impl<TNext: Exchange> Exchange for CacheExchangeImpl<TNext> {
             };
 
             if let Some(cached) = cached_result {
+                client.emit_event(|| DevtoolsEvent::CacheHit {
+                    key: operation.key,
+                    exchange: "CacheExchange"
+                });
                 let result = OperationResult {
                     key: operation.key,
                     meta: operation.meta,
                     response: Response {
                         debug_info: Some(DebugInfo {
                             source: ResultSource::Cache,
-                            did_dedup: false
+                            did_dedup: false,
+                            status: None
                         }),
                         data: Some(cached),
                         errors: None
@@ -174,8 +192,18 @@ impl<TNext: Exchange> Exchange for CacheExchangeImpl<TNext> {
                 };
                 Ok(result)
             } else {
+                client.emit_event(|| DevtoolsEvent::CacheMiss {
+                    key: operation.key,
+                    exchange: "CacheExchange"
+                });
                 self.next.run::<Q, _>(operation, client).await
             }
         }
     }
+
+    fn reset(&self) {
+        self.result_cache.lock().unwrap().clear();
+        self.operation_cache.lock().unwrap().clear();
+        self.next.reset();
+    }
 }
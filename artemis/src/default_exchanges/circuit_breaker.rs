@@ -0,0 +1,681 @@
+use super::fetch::FetchError;
+use crate::{
+    exchange::Client,
+    types::{ExchangeResult, Operation, OperationResult},
+    DebugInfo, Exchange, ExchangeFactory, GraphQLQuery, OperationType, QueryError, RequestPolicy,
+    Response, ResultSource
+};
+use futures::{
+    channel::mpsc::{self, Receiver, Sender},
+    task::{Context, Poll},
+    Stream, StreamExt
+};
+use instant::Instant;
+use std::{
+    any::Any,
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration
+};
+
+/// The number of state changes buffered for each stream returned by
+/// [`CircuitMonitor::changes`](./struct.CircuitMonitor.html#method.changes).
+const CHANGE_BUFFER: usize = 64;
+
+/// The state of the circuit for a URL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum CircuitState {
+    /// Requests are sent as usual.
+    Closed,
+    /// Too many requests failed recently, requests fail fast without being sent.
+    Open,
+    /// The circuit was open long enough, a single probe request is sent to test the URL.
+    /// If it succeeds the circuit closes, otherwise it opens again.
+    HalfOpen
+}
+
+/// A change of the circuit state for a URL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitStateChange {
+    /// The URL of the circuit
+    pub url: String,
+    /// The previous state
+    pub from: CircuitState,
+    /// The new state
+    pub to: CircuitState
+}
+
+/// The error returned for operations that aren't sent because the circuit for their URL is open.
+#[derive(Debug)]
+pub struct CircuitOpenError {
+    /// The URL the operation would have been sent to
+    pub url: String
+}
+
+impl Error for CircuitOpenError {}
+
+impl fmt::Display for CircuitOpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "circuit breaker is open for {}", self.url)
+    }
+}
+
+/// Whether an error means the server is unhealthy.
+/// Only network errors and server error responses count, everything else is the client's fault.
+fn is_failure(error: &QueryError) -> bool {
+    match error.downcast_ref::<FetchError>() {
+        #[cfg(not(target_arch = "wasm32"))]
+        Some(FetchError::NetworkError(_)) => true,
+        Some(FetchError::NotOk(status, _, _)) => is_server_error(*status),
+        _ => false
+    }
+}
+
+/// Whether a response came with a server error status. Servers may still send a GraphQL
+/// response along with it, which is passed on as is.
+fn is_failed_response<R: Clone>(response: &Response<R>) -> bool {
+    let status = response
+        .debug_info
+        .as_ref()
+        .and_then(|debug_info| debug_info.status);
+    matches!(status, Some(status) if is_server_error(status))
+}
+
+fn is_server_error(status: u16) -> bool {
+    status >= 500
+}
+
+#[derive(Clone)]
+struct Options {
+    failure_threshold: f64,
+    minimum_requests: usize,
+    window_size: usize,
+    open_duration: Duration,
+    stale_fallback: bool
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 0.5,
+            minimum_requests: 5,
+            window_size: 20,
+            open_duration: Duration::from_secs(30),
+            stale_fallback: false
+        }
+    }
+}
+
+enum Circuit {
+    /// The outcomes of the most recent requests, `true` meaning failed.
+    Closed(VecDeque<bool>),
+    Open(Instant),
+    HalfOpen {
+        probing: bool
+    }
+}
+
+impl Circuit {
+    fn state(&self) -> CircuitState {
+        match self {
+            Circuit::Closed(_) => CircuitState::Closed,
+            Circuit::Open(_) => CircuitState::Open,
+            Circuit::HalfOpen { .. } => CircuitState::HalfOpen
+        }
+    }
+}
+
+enum Admission {
+    Allowed,
+    Probe,
+    Rejected
+}
+
+struct Breaker {
+    options: Mutex<Options>,
+    circuits: Mutex<HashMap<String, Circuit>>,
+    listeners: Mutex<Vec<Sender<CircuitStateChange>>>,
+    /// The last successful result of each query, used as a fallback while the circuit is open.
+    stale: Mutex<HashMap<u64, Box<dyn Any + Send>>>
+}
+
+impl Default for Breaker {
+    fn default() -> Self {
+        Self {
+            options: Mutex::new(Options::default()),
+            circuits: Mutex::new(HashMap::new()),
+            listeners: Mutex::new(Vec::new()),
+            stale: Mutex::new(HashMap::new())
+        }
+    }
+}
+
+impl Breaker {
+    fn options(&self) -> Options {
+        self.options.lock().unwrap().clone()
+    }
+
+    /// Replaces the circuit and notifies listeners if the state changed.
+    fn transition(&self, url: &str, circuit: &mut Circuit, next: Circuit) {
+        let from = circuit.state();
+        *circuit = next;
+        let to = circuit.state();
+        if from == to {
+            return;
+        }
+
+        let change = CircuitStateChange {
+            url: url.to_string(),
+            from,
+            to
+        };
+        self.listeners.lock().unwrap().retain(|listener| {
+            match listener.clone().try_send(change.clone()) {
+                Ok(()) => true,
+                Err(e) => e.is_full()
+            }
+        });
+    }
+
+    fn admit(&self, url: &str) -> Admission {
+        let options = self.options();
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits
+            .entry(url.to_string())
+            .or_insert_with(|| Circuit::Closed(VecDeque::new()));
+        match circuit {
+            Circuit::Closed(_) => Admission::Allowed,
+            Circuit::Open(since) if since.elapsed() < options.open_duration => Admission::Rejected,
+            Circuit::Open(_) | Circuit::HalfOpen { probing: false } => {
+                self.transition(url, circuit, Circuit::HalfOpen { probing: true });
+                Admission::Probe
+            }
+            Circuit::HalfOpen { probing: true } => Admission::Rejected
+        }
+    }
+
+    fn record(&self, url: &str, failed: bool, probe: bool) {
+        let options = self.options();
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = match circuits.get_mut(url) {
+            Some(circuit) => circuit,
+            None => return
+        };
+        match circuit {
+            Circuit::HalfOpen { .. } if probe => {
+                let next = if failed {
+                    Circuit::Open(Instant::now())
+                } else {
+                    Circuit::Closed(VecDeque::new())
+                };
+                self.transition(url, circuit, next);
+            }
+            Circuit::Closed(outcomes) => {
+                outcomes.push_back(failed);
+                while outcomes.len() > options.window_size {
+                    outcomes.pop_front();
+                }
+                let failures = outcomes.iter().filter(|failed| **failed).count();
+                if outcomes.len() >= options.minimum_requests
+                    && failures as f64 / outcomes.len() as f64 >= options.failure_threshold
+                {
+                    self.transition(url, circuit, Circuit::Open(Instant::now()));
+                }
+            }
+            // Requests that were sent before the circuit opened don't affect it
+            _ => {}
+        }
+    }
+
+    /// Allows another probe if one was cancelled before it finished.
+    fn cancel_probe(&self, url: &str) {
+        let mut circuits = self.circuits.lock().unwrap();
+        if let Some(Circuit::HalfOpen { probing }) = circuits.get_mut(url) {
+            *probing = false;
+        }
+    }
+
+    fn state(&self, url: &str) -> CircuitState {
+        self.circuits
+            .lock()
+            .unwrap()
+            .get(url)
+            .map(Circuit::state)
+            .unwrap_or(CircuitState::Closed)
+    }
+}
+
+/// Tracks a sent request, so a cancelled probe doesn't leave the circuit half open forever.
+struct Attempt<'a> {
+    breaker: &'a Breaker,
+    url: &'a str,
+    probe: bool,
+    done: bool
+}
+
+impl<'a> Attempt<'a> {
+    fn finish(mut self, failed: Option<bool>) {
+        self.done = true;
+        match failed {
+            Some(failed) => self.breaker.record(self.url, failed, self.probe),
+            None if self.probe => self.breaker.cancel_probe(self.url),
+            None => {}
+        }
+    }
+}
+
+impl<'a> Drop for Attempt<'a> {
+    fn drop(&mut self) {
+        if !self.done && self.probe {
+            self.breaker.cancel_probe(self.url);
+        }
+    }
+}
+
+/// A handle to the circuit states of a `CircuitBreakerExchange`. Get it from
+/// [`CircuitBreakerExchange::monitor`](./struct.CircuitBreakerExchange.html#method.monitor)
+/// before adding the exchange to the client.
+#[derive(Clone)]
+pub struct CircuitMonitor {
+    breaker: Arc<Breaker>
+}
+
+impl CircuitMonitor {
+    /// The current state of the circuit for a URL.
+    /// URLs that haven't been requested yet are `Closed`.
+    pub fn state(&self, url: &str) -> CircuitState {
+        self.breaker.state(url)
+    }
+
+    /// Returns a stream of all state changes from now on.
+    /// Changes are dropped for this stream if it isn't consumed fast enough.
+    pub fn changes(&self) -> CircuitStateChanges {
+        let (sender, receiver) = mpsc::channel(CHANGE_BUFFER);
+        self.breaker.listeners.lock().unwrap().push(sender);
+        CircuitStateChanges { receiver }
+    }
+}
+
+/// A stream of circuit state changes, returned from
+/// [`CircuitMonitor::changes`](./struct.CircuitMonitor.html#method.changes).
+pub struct CircuitStateChanges {
+    receiver: Receiver<CircuitStateChange>
+}
+
+impl Stream for CircuitStateChanges {
+    type Item = CircuitStateChange;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+/// An exchange that stops sending requests to a URL while it's failing.
+///
+/// Each URL has its own circuit, which tracks the outcome of recent requests.
+/// Network errors and server error (5xx) responses count as failures, GraphQL errors don't.
+/// Once the share of failures reaches the threshold, the circuit opens and operations fail fast
+/// with a [`CircuitOpenError`](./struct.CircuitOpenError.html) instead of being sent.
+/// After the open duration has passed, a single probe request is let through to decide whether
+/// to close the circuit again.
+///
+/// With [`with_stale_fallback`](#method.with_stale_fallback), queries that don't use
+/// `RequestPolicy::NetworkOnly` are answered with their last successful result instead of an error
+/// while the circuit is open.
+///
+/// Subscriptions are passed through unchanged.
+/// This should usually be added right before the `FetchExchange`.
+///
+/// Requires feature: `circuit-breaker`
+///
+/// # Example
+///
+/// ```
+/// use artemis::{
+///     default_exchanges::{
+///         CacheExchange, CircuitBreakerExchange, CircuitState, DedupExchange, FetchExchange
+///     },
+///     ClientBuilder
+/// };
+/// use std::time::Duration;
+///
+/// let circuit_breaker = CircuitBreakerExchange::new()
+///     .with_failure_threshold(0.5)
+///     .with_open_duration(Duration::from_secs(10))
+///     .with_stale_fallback();
+/// let monitor = circuit_breaker.monitor();
+///
+/// let client = ClientBuilder::new("http://localhost:8080/graphql")
+///     .with_exchange(FetchExchange)
+///     .with_exchange(circuit_breaker)
+///     .with_exchange(CacheExchange)
+///     .with_exchange(DedupExchange)
+///     .build();
+///
+/// assert_eq!(monitor.state("http://localhost:8080/graphql"), CircuitState::Closed);
+/// ```
+#[derive(Default)]
+pub struct CircuitBreakerExchange {
+    breaker: Arc<Breaker>
+}
+
+impl CircuitBreakerExchange {
+    /// Creates a new circuit breaker that opens once half of the last 20 requests to a URL failed,
+    /// with at least 5 requests, and stays open for 30 seconds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The share of failed requests, between `0` and `1`, at which the circuit opens.
+    pub fn with_failure_threshold(self, failure_threshold: f64) -> Self {
+        self.breaker.options.lock().unwrap().failure_threshold = failure_threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The number of requests that need to be recorded before the circuit can open.
+    pub fn with_minimum_requests(self, minimum_requests: usize) -> Self {
+        self.breaker.options.lock().unwrap().minimum_requests = minimum_requests.max(1);
+        self
+    }
+
+    /// The number of recent requests the failure rate is calculated from.
+    pub fn with_window_size(self, window_size: usize) -> Self {
+        self.breaker.options.lock().unwrap().window_size = window_size.max(1);
+        self
+    }
+
+    /// How long the circuit stays open before a probe request is sent.
+    pub fn with_open_duration(self, open_duration: Duration) -> Self {
+        self.breaker.options.lock().unwrap().open_duration = open_duration;
+        self
+    }
+
+    /// Keeps the last successful result of each query and returns it while the circuit is open,
+    /// unless the query uses `RequestPolicy::NetworkOnly`.
+    pub fn with_stale_fallback(self) -> Self {
+        self.breaker.options.lock().unwrap().stale_fallback = true;
+        self
+    }
+
+    /// Returns a handle to the circuit states of this exchange.
+    pub fn monitor(&self) -> CircuitMonitor {
+        CircuitMonitor {
+            breaker: self.breaker.clone()
+        }
+    }
+}
+
+impl<TNext: Exchange> ExchangeFactory<TNext> for CircuitBreakerExchange {
+    type Output = CircuitBreakerExchangeImpl<TNext>;
+
+    fn build(self, next: TNext) -> Self::Output {
+        CircuitBreakerExchangeImpl {
+            breaker: self.breaker,
+            next
+        }
+    }
+}
+
+pub struct CircuitBreakerExchangeImpl<TNext: Exchange> {
+    breaker: Arc<Breaker>,
+    next: TNext
+}
+
+impl<TNext: Exchange> CircuitBreakerExchangeImpl<TNext> {
+    fn stale_result<Q: GraphQLQuery>(
+        &self,
+        operation: &Operation<Q::Variables>
+    ) -> Option<OperationResult<Q::ResponseData>> {
+        if !self.breaker.options().stale_fallback
+            || operation.meta.operation_type != OperationType::Query
+            || operation.options.request_policy == RequestPolicy::NetworkOnly
+        {
+            return None;
+        }
+
+        let stale = self.breaker.stale.lock().unwrap();
+        let mut response = stale
+            .get(&operation.key)?
+            .downcast_ref::<Response<Q::ResponseData>>()?
+            .clone();
+        response.debug_info = Some(DebugInfo {
+            source: ResultSource::Cache,
+            did_dedup: false,
+            status: None
+        });
+        Some(OperationResult {
+            key: operation.key,
+            meta: operation.meta.clone(),
+            response
+        })
+    }
+}
+
+#[async_trait]
+impl<TNext: Exchange> Exchange for CircuitBreakerExchangeImpl<TNext> {
+    async fn run<Q: GraphQLQuery, C: Client>(
+        &self,
+        operation: Operation<Q::Variables>,
+        client: C
+    ) -> ExchangeResult<Q::ResponseData> {
+        if operation.meta.operation_type == OperationType::Subscription {
+            return self.next.run::<Q, _>(operation, client).await;
+        }
+
+        let url = operation.options.url.clone();
+        let probe = match self.breaker.admit(&url) {
+            Admission::Allowed => false,
+            Admission::Probe => true,
+            Admission::Rejected => {
+                return match self.stale_result::<Q>(&operation) {
+                    Some(result) => Ok(result),
+                    None => Err(CircuitOpenError { url }.into())
+                };
+            }
+        };
+
+        let attempt = Attempt {
+            breaker: &self.breaker,
+            url: &url,
+            probe,
+            done: false
+        };
+        let key = operation.key;
+        let is_query = operation.meta.operation_type == OperationType::Query;
+        let result = self.next.run::<Q, _>(operation, client).await;
+        match &result {
+            Ok(result) if is_failed_response(&result.response) => attempt.finish(Some(true)),
+            Ok(result) => {
+                attempt.finish(Some(false));
+                if is_query && self.breaker.options().stale_fallback {
+                    self.breaker
+                        .stale
+                        .lock()
+                        .unwrap()
+                        .insert(key, Box::new(result.response.clone()));
+                }
+            }
+            Err(e) if is_failure(e) => attempt.finish(Some(true)),
+            Err(_) => attempt.finish(None)
+        }
+        result
+    }
+
+    fn reset(&self) {
+        self.breaker.stale.lock().unwrap().clear();
+        self.next.reset();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test {
+    use super::{CircuitBreakerExchange, CircuitOpenError, CircuitState, CircuitStateChange};
+    use crate::{
+        default_exchanges::FetchError,
+        exchange::Operation,
+        test_utils::{data_response, make_operation, Data, TestExchange, TestQuery, TEST_URL},
+        ClientBuilder, DebugInfo, Exchange, ExchangeFactory, QueryError, RequestPolicy,
+        ResultSource
+    };
+    use futures::{FutureExt, StreamExt};
+    use serde_json::json;
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc
+        },
+        time::Duration
+    };
+    use tokio::time::sleep;
+
+    /// Returns a server error while `failing` is set and `Data(1)` otherwise. With `respond`, the
+    /// server error comes with a GraphQL response.
+    fn flaky_exchange(failing: &Arc<AtomicBool>, respond: bool) -> impl Exchange {
+        let failing = failing.clone();
+        TestExchange::new(move |_| {
+            let failing = failing.load(Ordering::SeqCst);
+            async move {
+                if failing && !respond {
+                    return Err(QueryError::from(FetchError::NotOk(
+                        503,
+                        "Service Unavailable".to_string(),
+                        String::new()
+                    )));
+                }
+                let mut response = data_response(json!(1));
+                response.debug_info = Some(DebugInfo {
+                    source: ResultSource::Network,
+                    did_dedup: false,
+                    status: Some(if failing { 503 } else { 200 })
+                });
+                Ok(response)
+            }
+        })
+    }
+
+    fn operation(request_policy: RequestPolicy) -> Operation<()> {
+        let mut operation = make_operation::<TestQuery>(1, ());
+        operation.options.request_policy = request_policy;
+        operation
+    }
+
+    #[tokio::test]
+    async fn test_opens_and_closes() {
+        let failing = Arc::new(AtomicBool::new(false));
+        let circuit_breaker = CircuitBreakerExchange::new()
+            .with_minimum_requests(2)
+            .with_open_duration(Duration::from_millis(20));
+        let monitor = circuit_breaker.monitor();
+        let mut changes = monitor.changes();
+        let exchange = circuit_breaker.build(flaky_exchange(&failing, false));
+        let client = ClientBuilder::new(TEST_URL).build();
+        let run = || {
+            exchange.run::<TestQuery, _>(operation(RequestPolicy::NetworkOnly), client.0.clone())
+        };
+
+        failing.store(true, Ordering::SeqCst);
+        run().await.unwrap_err();
+        assert_eq!(monitor.state(TEST_URL), CircuitState::Closed);
+        run().await.unwrap_err();
+        assert_eq!(monitor.state(TEST_URL), CircuitState::Open);
+
+        failing.store(false, Ordering::SeqCst);
+        let error = run().await.unwrap_err();
+        assert!(error.downcast_ref::<CircuitOpenError>().is_some());
+
+        sleep(Duration::from_millis(30)).await;
+        let result = run().await.unwrap();
+        assert_eq!(result.response.data, Some(Data(1)));
+        assert_eq!(monitor.state(TEST_URL), CircuitState::Closed);
+
+        let transitions: Vec<_> = (0..3)
+            .map(|_| changes.next().now_or_never().flatten().unwrap())
+            .map(|CircuitStateChange { from, to, .. }| (from, to))
+            .collect();
+        assert_eq!(
+            transitions,
+            vec![
+                (CircuitState::Closed, CircuitState::Open),
+                (CircuitState::Open, CircuitState::HalfOpen),
+                (CircuitState::HalfOpen, CircuitState::Closed)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_probe() {
+        let failing = Arc::new(AtomicBool::new(false));
+        let circuit_breaker = CircuitBreakerExchange::new()
+            .with_minimum_requests(1)
+            .with_open_duration(Duration::from_millis(20));
+        let monitor = circuit_breaker.monitor();
+        let exchange = circuit_breaker.build(flaky_exchange(&failing, false));
+        let client = ClientBuilder::new(TEST_URL).build();
+        let run = || {
+            exchange.run::<TestQuery, _>(operation(RequestPolicy::NetworkOnly), client.0.clone())
+        };
+
+        failing.store(true, Ordering::SeqCst);
+        run().await.unwrap_err();
+        sleep(Duration::from_millis(30)).await;
+        let error = run().await.unwrap_err();
+        assert!(error.downcast_ref::<FetchError>().is_some());
+        assert_eq!(monitor.state(TEST_URL), CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn test_server_error_responses_are_failures() {
+        let failing = Arc::new(AtomicBool::new(false));
+        let circuit_breaker = CircuitBreakerExchange::new().with_minimum_requests(1);
+        let monitor = circuit_breaker.monitor();
+        let exchange = circuit_breaker.build(flaky_exchange(&failing, true));
+        let client = ClientBuilder::new(TEST_URL).build();
+
+        failing.store(true, Ordering::SeqCst);
+        let result = exchange
+            .run::<TestQuery, _>(operation(RequestPolicy::NetworkOnly), client.0.clone())
+            .await
+            .unwrap();
+        assert_eq!(result.response.data, Some(Data(1)));
+        assert_eq!(monitor.state(TEST_URL), CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn test_stale_fallback() {
+        let failing = Arc::new(AtomicBool::new(false));
+        let circuit_breaker = CircuitBreakerExchange::new()
+            .with_minimum_requests(1)
+            .with_stale_fallback();
+        let exchange = circuit_breaker.build(flaky_exchange(&failing, false));
+        let client = ClientBuilder::new(TEST_URL).build();
+
+        exchange
+            .run::<TestQuery, _>(operation(RequestPolicy::CacheFirst), client.0.clone())
+            .await
+            .unwrap();
+        failing.store(true, Ordering::SeqCst);
+        exchange
+            .run::<TestQuery, _>(operation(RequestPolicy::CacheFirst), client.0.clone())
+            .await
+            .unwrap_err();
+
+        let result = exchange
+            .run::<TestQuery, _>(operation(RequestPolicy::CacheFirst), client.0.clone())
+            .await
+            .unwrap();
+        assert_eq!(result.response.data, Some(Data(1)));
+        assert_eq!(
+            result.response.debug_info.unwrap().source,
+            ResultSource::Cache
+        );
+
+        let error = exchange
+            .run::<TestQuery, _>(operation(RequestPolicy::NetworkOnly), client.0.clone())
+            .await
+            .unwrap_err();
+        assert!(error.downcast_ref::<CircuitOpenError>().is_some());
+    }
+}
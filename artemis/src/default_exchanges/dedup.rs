@@ -1,7 +1,8 @@
 use crate::{
-    exchange::Client,
+    devtools::DevtoolsEvent,
+    exchange::{Client, Extension},
     types::{ExchangeResult, Operation, OperationResult},
-    Exchange, ExchangeFactory, GraphQLQuery, OperationType, QueryError
+    Exchange, ExchangeFactory, GraphQLQuery, OperationType, QueryError, RequestPolicy
 };
 use futures::channel::{oneshot, oneshot::Sender};
 use std::{
@@ -9,19 +10,43 @@ use std::{
     collections::HashMap,
     error::Error,
     fmt,
-    sync::{Arc, Mutex}
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex
+    }
 };
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::JsValue;
+
+type DedupResult = Result<Box<dyn Any + Send>, QueryError>;
+type InFlightCache = Mutex<HashMap<DedupKey, InFlight>>;
+
+/// Operations are only deduplicated if they're sent to the same URL with the same request policy.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct DedupKey {
+    key: u64,
+    url: String,
+    request_policy: RequestPolicy
+}
 
-type InFlightCache = Arc<Mutex<HashMap<u64, Vec<Sender<Result<Box<dyn Any + Send>, QueryError>>>>>>;
+struct InFlight {
+    // Identifies the leading operation, so a stale leader can't resolve or remove a newer entry
+    id: u64,
+    listeners: Vec<Sender<DedupResult>>
+}
 
 /// The default deduplication exchange.
 ///
 /// This will keep track of in-flight queries and catch any identical queries before they execute,
-/// instead waiting for the result from the in-flight query
+/// instead waiting for the result from the in-flight query.
+/// If the in-flight query is cancelled, one of the waiting queries takes over.
+///
+/// Mutations are only deduplicated if they opt in with a [`DedupExtension`](./struct.DedupExtension.html).
 pub struct DedupExchange;
 pub struct DedupExchangeImpl<TNext: Exchange> {
     next: TNext,
-    in_flight_operations: InFlightCache
+    in_flight_operations: InFlightCache,
+    next_id: AtomicU64
 }
 
 impl<TNext: Exchange> ExchangeFactory<TNext> for DedupExchange {
@@ -30,11 +55,33 @@ impl<TNext: Exchange> ExchangeFactory<TNext> for DedupExchange {
     fn build(self, next: TNext) -> Self::Output {
         DedupExchangeImpl {
             next,
-            in_flight_operations: InFlightCache::default()
+            in_flight_operations: InFlightCache::default(),
+            next_id: AtomicU64::new(0)
         }
     }
 }
 
+/// Per-operation options for the `DedupExchange`, passed in the `extensions` of `QueryOptions`.
+/// The JavaScript key is `Dedup`.
+#[derive(Clone, Debug, Default)]
+pub struct DedupExtension {
+    /// Deduplicate this mutation with identical in-flight mutations.
+    /// Mutations aren't deduplicated by default, since running one twice is usually intentional.
+    pub dedup_mutation: bool
+}
+
+impl Extension for DedupExtension {
+    #[cfg(target_arch = "wasm32")]
+    fn from_js(value: JsValue) -> Option<Self> {
+        let dedup_mutation: JsValue = "dedupMutation".into();
+        let dedup_mutation = js_sys::Reflect::get(&value, &dedup_mutation)
+            .ok()
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+        Some(Self { dedup_mutation })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DedupError;
 impl Error for DedupError {}
@@ -45,13 +92,20 @@ impl fmt::Display for DedupError {
 }
 
 fn should_skip<Q: GraphQLQuery>(operation: &Operation<Q::Variables>) -> bool {
-    let op_type = &operation.meta.operation_type;
-    op_type != &OperationType::Query && op_type != &OperationType::Mutation
+    match operation.meta.operation_type {
+        OperationType::Query => false,
+        OperationType::Mutation => !operation
+            .options
+            .extensions
+            .as_ref()
+            .and_then(|ext| ext.get::<DedupExtension, _>("Dedup"))
+            .map(|ext| ext.dedup_mutation)
+            .unwrap_or(false),
+        _ => true
+    }
 }
 
-fn make_deduped_result<Q: GraphQLQuery>(
-    res: &ExchangeResult<Q::ResponseData>
-) -> Result<Box<dyn Any + Send>, QueryError> {
+fn make_deduped_result<Q: GraphQLQuery>(res: &ExchangeResult<Q::ResponseData>) -> DedupResult {
     match res {
         Ok(ref res) => {
             let mut res = res.clone();
@@ -64,13 +118,40 @@ fn make_deduped_result<Q: GraphQLQuery>(
     }
 }
 
-impl<TNext: Exchange> DedupExchangeImpl<TNext> {
-    fn notify_listeners<Q: GraphQLQuery>(&self, key: u64, res: &ExchangeResult<Q::ResponseData>) {
+/// Held by the leading operation while it's in flight.
+/// If the leader is dropped before it completes, the in-flight entry is removed. This drops the
+/// senders of all waiting operations, which then retry, and the first to do so takes over.
+struct Leader<'a> {
+    in_flight_operations: &'a InFlightCache,
+    key: DedupKey,
+    id: u64,
+    done: bool
+}
+
+impl<'a> Leader<'a> {
+    fn complete<Q: GraphQLQuery>(mut self, res: &ExchangeResult<Q::ResponseData>) {
+        self.done = true;
+        if let Some(in_flight) = self.take_entry() {
+            for sender in in_flight.listeners {
+                // The receiver may have been dropped by a cancelled query, which is fine
+                let _ = sender.send(make_deduped_result::<Q>(res));
+            }
+        }
+    }
+
+    fn take_entry(&self) -> Option<InFlight> {
         let mut cache = self.in_flight_operations.lock().unwrap();
-        let to_be_notified = cache.remove(&key).unwrap();
-        for sender in to_be_notified {
-            let res = make_deduped_result::<Q>(res);
-            sender.send(res).unwrap();
+        match cache.get(&self.key) {
+            Some(in_flight) if in_flight.id == self.id => cache.remove(&self.key),
+            _ => None
+        }
+    }
+}
+
+impl<'a> Drop for Leader<'a> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.take_entry();
         }
     }
 }
@@ -80,55 +161,86 @@ impl<TNext: Exchange> Exchange for DedupExchangeImpl<TNext> {
     async fn run<Q: GraphQLQuery, C: Client>(
         &self,
         operation: Operation<Q::Variables>,
-        _client: C
+        client: C
     ) -> ExchangeResult<Q::ResponseData> {
         if should_skip::<Q>(&operation) {
-            return self.next.run::<Q, _>(operation, _client).await;
+            return self.next.run::<Q, _>(operation, client).await;
         }
 
-        let key = operation.key;
-        let rcv = {
-            let mut cache = self.in_flight_operations.lock().unwrap();
-            if let Some(listeners) = cache.get_mut(&key) {
-                let (sender, receiver) = oneshot::channel();
-                listeners.push(sender);
-                Some(receiver)
-            } else {
-                cache.insert(key, Vec::new());
-                None
-            }
+        let key = DedupKey {
+            key: operation.key,
+            url: operation.options.url.clone(),
+            request_policy: operation.options.request_policy.clone()
         };
+        loop {
+            let rcv = {
+                let mut cache = self.in_flight_operations.lock().unwrap();
+                if let Some(in_flight) = cache.get_mut(&key) {
+                    let (sender, receiver) = oneshot::channel();
+                    in_flight.listeners.push(sender);
+                    Ok(receiver)
+                } else {
+                    let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+                    let in_flight = InFlight {
+                        id,
+                        listeners: Vec::new()
+                    };
+                    cache.insert(key.clone(), in_flight);
+                    Err(id)
+                }
+            };
 
-        if let Some(rcv) = rcv {
-            let res: Box<dyn Any> = rcv.await.unwrap()?;
-            let res: OperationResult<Q::ResponseData> = *res.downcast().unwrap();
-            Ok(res)
-        } else {
-            let res = self.next.run::<Q, _>(operation, _client).await;
-            self.notify_listeners::<Q>(key, &res);
-            res
+            match rcv {
+                Ok(rcv) => {
+                    client.emit_event(|| DevtoolsEvent::DedupJoined { key: key.key });
+                    // If the leader was cancelled or reset, this retries and may take over
+                    if let Ok(res) = rcv.await {
+                        let res: Box<dyn Any> = res?;
+                        let res: OperationResult<Q::ResponseData> = *res.downcast().unwrap();
+                        return Ok(res);
+                    }
+                }
+                Err(id) => {
+                    let leader = Leader {
+                        in_flight_operations: &self.in_flight_operations,
+                        key,
+                        id,
+                        done: false
+                    };
+                    let res = self.next.run::<Q, _>(operation, client).await;
+                    leader.complete::<Q>(&res);
+                    return res;
+                }
+            }
         }
     }
+
+    fn reset(&self) {
+        // Waiting operations are woken up by their dropped senders and start over
+        self.in_flight_operations.lock().unwrap().clear();
+        self.next.reset();
+    }
 }
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod test {
     use super::DedupExchangeImpl;
     use crate::{
-        default_exchanges::DedupExchange,
+        default_exchanges::{DedupExchange, DedupExtension},
         exchange::Client,
         types::{Operation, OperationOptions, OperationResult},
-        ClientBuilder, DebugInfo, Exchange, ExchangeFactory, ExchangeResult, FieldSelector,
-        GraphQLQuery, OperationMeta, OperationType, QueryBody, QueryInfo, RequestPolicy, Response,
-        ResultSource
+        ClientBuilder, DebugInfo, Exchange, ExchangeFactory, ExchangeResult, ExtensionMap,
+        FieldSelector, GraphQLQuery, OperationMeta, OperationType, QueryBody, QueryInfo,
+        RequestPolicy, Response, ResultSource
     };
     use artemis_test::get_conference::{
         get_conference::{ResponseData, Variables, OPERATION_NAME, QUERY},
         GetConference
     };
+    use futures::poll;
     use lazy_static::lazy_static;
-    use std::time::Duration;
-    use tokio::time::sleep;
+    use std::{sync::Arc, time::Duration};
+    use tokio::time::{sleep, timeout};
 
     lazy_static! {
         static ref VARIABLES: Variables = Variables {
@@ -166,7 +278,8 @@ mod test {
                 response: Response {
                     debug_info: Some(DebugInfo {
                         source: ResultSource::Network,
-                        did_dedup: false
+                        did_dedup: false,
+                        status: None
                     }),
                     data: None,
                     errors: None
@@ -194,12 +307,12 @@ mod test {
         let meta = OperationMeta {
             query_key: 13543040u32,
             operation_type: OperationType::Query,
-            involved_types: vec!["Conference", "Person", "Talk"]
+            involved_types: vec!["Conference".into(), "Person".into(), "Talk".into()]
         };
         let body = QueryBody {
             variables,
-            query: QUERY,
-            operation_name: OPERATION_NAME
+            query: QUERY.into(),
+            operation_name: OPERATION_NAME.into()
         };
         (body, meta)
     }
@@ -244,4 +357,92 @@ mod test {
 
         assert_eq!(did_one_dedup, true);
     }
+
+    #[tokio::test]
+    async fn test_leader_cancelled() {
+        let (query, meta) = build_query(VARIABLES.clone());
+        let exchange = DedupExchange.build(FakeFetchExchange);
+        let client = ClientBuilder::new("http://localhost:4000/graphql").build();
+
+        let mut leader = Box::pin(exchange.run::<GetConference, _>(
+            make_operation(query.clone(), meta.clone()),
+            client.0.clone()
+        ));
+        assert!(poll!(&mut leader).is_pending());
+        let mut follower = Box::pin(exchange.run::<GetConference, _>(
+            make_operation(query.clone(), meta.clone()),
+            client.0.clone()
+        ));
+        assert!(poll!(&mut follower).is_pending());
+        drop(leader);
+
+        let res = timeout(Duration::from_secs(1), follower)
+            .await
+            .expect("follower didn't take over from the cancelled leader")
+            .unwrap();
+        assert_eq!(res.response.debug_info.unwrap().did_dedup, false);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_key() {
+        let (query, meta) = build_query(VARIABLES.clone());
+        let exchange = DedupExchange.build(FakeFetchExchange);
+        let client = ClientBuilder::new("http://localhost:4000/graphql").build();
+
+        let mut other_url = make_operation(query.clone(), meta.clone());
+        other_url.options.url = "http://localhost:8081/graphql".to_string();
+        let mut other_policy = make_operation(query.clone(), meta.clone());
+        other_policy.options.request_policy = RequestPolicy::CacheAndNetwork;
+
+        let (res1, res2, res3) = futures::join!(
+            exchange.run::<GetConference, _>(
+                make_operation(query.clone(), meta.clone()),
+                client.0.clone()
+            ),
+            exchange.run::<GetConference, _>(other_url, client.0.clone()),
+            exchange.run::<GetConference, _>(other_policy, client.0.clone())
+        );
+
+        for res in [res1, res2, res3] {
+            assert_eq!(res.unwrap().response.debug_info.unwrap().did_dedup, false);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mutation_opt_in() {
+        let (query, mut meta) = build_query(VARIABLES.clone());
+        meta.operation_type = OperationType::Mutation;
+        let exchange = DedupExchange.build(FakeFetchExchange);
+        let client = ClientBuilder::new("http://localhost:4000/graphql").build();
+
+        let (res1, res2) = futures::join!(
+            exchange.run::<GetConference, _>(
+                make_operation(query.clone(), meta.clone()),
+                client.0.clone()
+            ),
+            exchange.run::<GetConference, _>(
+                make_operation(query.clone(), meta.clone()),
+                client.0.clone()
+            )
+        );
+        assert_eq!(res1.unwrap().response.debug_info.unwrap().did_dedup, false);
+        assert_eq!(res2.unwrap().response.debug_info.unwrap().did_dedup, false);
+
+        let opt_in = || {
+            let mut extensions = ExtensionMap::new();
+            extensions.insert(DedupExtension {
+                dedup_mutation: true
+            });
+            let mut operation = make_operation(query.clone(), meta.clone());
+            operation.options.extensions = Some(Arc::new(extensions));
+            operation
+        };
+        let (res1, res2) = futures::join!(
+            exchange.run::<GetConference, _>(opt_in(), client.0.clone()),
+            exchange.run::<GetConference, _>(opt_in(), client.0.clone())
+        );
+        let did_1_dedup = res1.unwrap().response.debug_info.unwrap().did_dedup;
+        let did_2_dedup = res2.unwrap().response.debug_info.unwrap().did_dedup;
+        assert_eq!(did_1_dedup ^ did_2_dedup, true);
+    }
 }
@@ -1,4 +1,6 @@
 use crate::{
+    activity::NetworkStatus,
+    devtools::DevtoolsEvent,
     exchange::{Client, ExchangeResult, Operation, OperationResult},
     DebugInfo, Exchange, ExchangeFactory, GraphQLQuery, HeaderPair, OperationOptions, QueryBody,
     Response, ResultSource
@@ -19,12 +21,15 @@ use wasm_bindgen::prelude::*;
 pub enum FetchError {
     #[cfg(not(target_arch = "wasm32"))]
     NetworkError(Box<dyn Error + Send + Sync>),
-    #[cfg(target_arch = "wasm32")]
+    /// The server returned an error status code. On native targets this is only returned if the
+    /// body isn't a GraphQL response, otherwise the status is in the response's `DebugInfo`.
     NotOk(u16, String, String),
     #[cfg(target_arch = "wasm32")]
     DecodeError(std::io::Error),
     #[cfg(not(target_arch = "wasm32"))]
     DecodeError(reqwest::Error),
+    /// The response body was valid JSON, but didn't match the shape of the query.
+    DataError(serde_json::Error),
     #[cfg(target_arch = "wasm32")]
     EncodeError(serde_json::Error)
 }
@@ -36,9 +41,9 @@ impl fmt::Display for FetchError {
             #[cfg(not(target_arch = "wasm32"))]
             FetchError::NetworkError(e) => write!(f, "fetch error: {}", e),
             FetchError::DecodeError(e) => write!(f, "decoding error: {}", e),
+            FetchError::DataError(e) => write!(f, "invalid response data: {}", e),
             #[cfg(target_arch = "wasm32")]
             FetchError::EncodeError(e) => write!(f, "encoding error: {}", e),
-            #[cfg(target_arch = "wasm32")]
             FetchError::NotOk(status_code, status_text, body) => write!(
                 f,
                 "server returned error code: {} {}\n{}",
@@ -96,13 +101,28 @@ impl FetchExchange {
             request = request.header(&key, &value);
         }
 
-        Ok(request
+        let response = request
             .send()
             .await
-            .map_err(|e| FetchError::NetworkError(Box::new(e)))?
-            .json()
-            .await
-            .map_err(FetchError::DecodeError)?)
+            .map_err(|e| FetchError::NetworkError(Box::new(e)))?;
+
+        let status = response.status();
+        let body = if status.is_success() {
+            response.json().await.map_err(FetchError::DecodeError)?
+        } else {
+            // Error statuses may still come with a GraphQL response
+            let body = response.text().await.map_err(FetchError::DecodeError)?;
+            match serde_json::from_str(&body) {
+                Ok(body) => body,
+                Err(_) => {
+                    let status_text = status.canonical_reason().unwrap_or_default().to_string();
+                    return Err(FetchError::NotOk(status.as_u16(), status_text, body));
+                }
+            }
+        };
+        let mut response = Response::from_json(body).map_err(FetchError::DataError)?;
+        response.debug_info = network_debug_info(status.as_u16());
+        Ok(response)
     }
 
     #[cfg(target_arch = "wasm32")]
@@ -158,9 +178,13 @@ impl FetchExchange {
                 return Err(FetchError::NotOk(res.status(), res.status_text(), body));
             }
 
-            serde_json::from_slice(&body)
+            let body = serde_json::from_slice(&body)
                 .map_err(std::io::Error::from)
-                .map_err(FetchError::DecodeError)
+                .map_err(FetchError::DecodeError)?;
+            let mut response: Response<Q::ResponseData> =
+                Response::from_json(body).map_err(FetchError::DataError)?;
+            response.debug_info = network_debug_info(res.status());
+            Ok(response)
         };
 
         Box::pin(InnerFuture::<Q> { fut: Box::pin(fut) })
@@ -188,12 +212,30 @@ impl<Q: GraphQLQuery> Future for InnerFuture<Q> {
     }
 }
 
+fn network_debug_info(status: u16) -> Option<DebugInfo> {
+    Some(DebugInfo {
+        // TODO: Make this conditional
+        source: ResultSource::Network,
+        did_dedup: false,
+        status: Some(status)
+    })
+}
+
+/// Whether the server could be reached, regardless of whether the request succeeded.
+fn network_status<R>(response: &Result<R, FetchError>) -> NetworkStatus {
+    match response {
+        #[cfg(not(target_arch = "wasm32"))]
+        Err(FetchError::NetworkError(_)) => NetworkStatus::Offline,
+        _ => NetworkStatus::Online
+    }
+}
+
 #[async_trait]
 impl Exchange for FetchExchange {
     async fn run<Q: GraphQLQuery, C: Client>(
         &self,
         operation: Operation<Q::Variables>,
-        _client: C
+        client: C
     ) -> ExchangeResult<Q::ResponseData> {
         let extra_headers = if let Some(ref extra_headers) = operation.options.extra_headers {
             extra_headers()
@@ -201,16 +243,26 @@ impl Exchange for FetchExchange {
             Vec::new()
         };
 
-        let mut response =
-            FetchExchange::fetch::<Q>(extra_headers, operation.options, operation.query).await?;
-
-        let debug_info = Some(DebugInfo {
-            // TODO: Make this conditional
-            source: ResultSource::Network,
-            did_dedup: false
+        let key = operation.key;
+        let url = operation.options.url.clone();
+        client.emit_event(|| DevtoolsEvent::FetchSent {
+            key,
+            url: url.clone()
         });
 
-        response.debug_info = debug_info;
+        let response =
+            FetchExchange::fetch::<Q>(extra_headers, operation.options, operation.query).await;
+        client.emit_event(|| DevtoolsEvent::FetchReceived {
+            key,
+            url,
+            has_errors: response
+                .as_ref()
+                .map(|response| response.errors.is_some())
+                .unwrap_or(false),
+            error: response.as_ref().err().map(ToString::to_string)
+        });
+        client.report_network_status(network_status(&response));
+        let response = response?;
 
         Ok(OperationResult {
             key: operation.key,
@@ -0,0 +1,891 @@
+use crate::{
+    codegen::{FieldSelector, QueryInfo},
+    exchange::Client,
+    types::{ExchangeResult, Operation, OperationMeta, OperationResult},
+    utils::operation_selection_set_mut,
+    DebugInfo, Exchange, ExchangeFactory, GraphQLQuery, OperationType, QueryBody, QueryError,
+    Response, ResultSource
+};
+use graphql_parser::query::{
+    Definition, Directive, Document, FragmentDefinition, OperationDefinition, Selection,
+    SelectionSet, TypeCondition, Value as GraphQLValue
+};
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt,
+    marker::PhantomData,
+    sync::Arc
+};
+
+/// The directive that marks a field as local.
+const CLIENT_DIRECTIVE: &str = "client";
+
+/// An error that occurred while resolving local fields.
+#[derive(Debug)]
+pub enum LocalStateError {
+    /// The document of the operation couldn't be parsed.
+    ParseError(String),
+    /// No resolver was registered for a local field. Contains the type name and field name.
+    MissingResolver(String, String),
+    /// The type of the object a local field is selected on is unknown.
+    /// Select `__typename` on it to fix this.
+    MissingTypename(String)
+}
+
+impl Error for LocalStateError {}
+
+impl fmt::Display for LocalStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LocalStateError::ParseError(e) => write!(f, "failed to parse document: {}", e),
+            LocalStateError::MissingResolver(type_name, field_name) => write!(
+                f,
+                "no resolver registered for local field {}.{}",
+                type_name, field_name
+            ),
+            LocalStateError::MissingTypename(field_name) => write!(
+                f,
+                "can't resolve local field {} without selecting __typename on its parent",
+                field_name
+            )
+        }
+    }
+}
+
+/// The information passed to a resolver of a local field.
+pub struct ResolverContext<'a> {
+    /// The object the field is selected on, as returned by the server.
+    /// For root fields this is the data of the operation.
+    pub parent: &'a Value,
+    /// The arguments of the field, with variables filled in.
+    pub arguments: &'a Map<String, Value>,
+    /// The variables of the operation.
+    pub variables: &'a Value
+}
+
+type Resolver = Box<dyn Fn(&ResolverContext<'_>) -> Result<Value, QueryError> + Send + Sync>;
+
+fn is_local(directives: &[Directive]) -> bool {
+    directives
+        .iter()
+        .any(|directive| directive.name == CLIENT_DIRECTIVE)
+}
+
+fn root_type_name(operation_type: &OperationType) -> &'static str {
+    match operation_type {
+        OperationType::Query => "Query",
+        OperationType::Mutation => "Mutation",
+        OperationType::Subscription => "Subscription"
+    }
+}
+
+fn argument_value(value: &GraphQLValue, variables: &Value) -> Value {
+    match value {
+        GraphQLValue::Variable(name) => variables.get(name).cloned().unwrap_or(Value::Null),
+        GraphQLValue::Int(number) => number.as_i64().into(),
+        GraphQLValue::Float(number) => (*number).into(),
+        GraphQLValue::String(string) | GraphQLValue::Enum(string) => string.clone().into(),
+        GraphQLValue::Boolean(boolean) => (*boolean).into(),
+        GraphQLValue::Null => Value::Null,
+        GraphQLValue::List(values) => values
+            .iter()
+            .map(|value| argument_value(value, variables))
+            .collect(),
+        GraphQLValue::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), argument_value(value, variables)))
+                .collect()
+        )
+    }
+}
+
+/// Removes local fields from the selection set, along with fields and fragments that only
+/// selected local fields. Returns whether there's anything left in it.
+fn strip_selection_set(
+    selection_set: &mut SelectionSet,
+    empty_fragments: &HashSet<String>
+) -> bool {
+    let items = std::mem::take(&mut selection_set.items);
+    selection_set.items = items
+        .into_iter()
+        .filter_map(|mut item| {
+            let keep = match &mut item {
+                Selection::Field(field) => {
+                    !is_local(&field.directives)
+                        && (field.selection_set.items.is_empty()
+                            || strip_selection_set(&mut field.selection_set, empty_fragments))
+                }
+                Selection::FragmentSpread(spread) => {
+                    !empty_fragments.contains(&spread.fragment_name)
+                }
+                Selection::InlineFragment(fragment) => {
+                    strip_selection_set(&mut fragment.selection_set, empty_fragments)
+                }
+            };
+            if keep {
+                Some(item)
+            } else {
+                None
+            }
+        })
+        .collect();
+    !selection_set.items.is_empty()
+}
+
+fn spread_fragments<'a>(selection_set: &'a SelectionSet, spreads: &mut Vec<&'a str>) {
+    for item in &selection_set.items {
+        match item {
+            Selection::Field(field) => spread_fragments(&field.selection_set, spreads),
+            Selection::FragmentSpread(spread) => spreads.push(&spread.fragment_name),
+            Selection::InlineFragment(fragment) => {
+                spread_fragments(&fragment.selection_set, spreads)
+            }
+        }
+    }
+}
+
+fn operation_name(operation: &OperationDefinition) -> Option<&str> {
+    match operation {
+        OperationDefinition::SelectionSet(_) => None,
+        OperationDefinition::Query(query) => query.name.as_deref(),
+        OperationDefinition::Mutation(mutation) => mutation.name.as_deref(),
+        OperationDefinition::Subscription(subscription) => subscription.name.as_deref()
+    }
+}
+
+fn operation_selection_set(operation: &OperationDefinition) -> &SelectionSet {
+    match operation {
+        OperationDefinition::SelectionSet(selection_set) => selection_set,
+        OperationDefinition::Query(query) => &query.selection_set,
+        OperationDefinition::Mutation(mutation) => &mutation.selection_set,
+        OperationDefinition::Subscription(subscription) => &subscription.selection_set
+    }
+}
+
+/// Finds the operation with the given name, or the first one if there's none with that name.
+fn find_operation<'a>(document: &'a Document, name: &str) -> Option<&'a OperationDefinition> {
+    let mut operations = document
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Operation(operation) => Some(operation),
+            Definition::Fragment(_) => None
+        });
+    operations
+        .clone()
+        .find(|operation| operation_name(operation) == Some(name))
+        .or_else(|| operations.next())
+}
+
+/// Returns a copy of the document without any local fields, as well as operations and fragments
+/// that are left empty or unused.
+fn strip_local_fields(document: &Document) -> Document {
+    let mut stripped = document.clone();
+
+    // Stripping a fragment can empty out other fragments spreading it, so repeat until stable
+    let mut empty_fragments = HashSet::new();
+    loop {
+        let mut changed = false;
+        for definition in &mut stripped.definitions {
+            if let Definition::Fragment(fragment) = definition {
+                if !empty_fragments.contains(&fragment.name)
+                    && !strip_selection_set(&mut fragment.selection_set, &empty_fragments)
+                {
+                    empty_fragments.insert(fragment.name.clone());
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    stripped.definitions.retain(|definition| match definition {
+        Definition::Operation(_) => true,
+        Definition::Fragment(fragment) => !empty_fragments.contains(&fragment.name)
+    });
+    for definition in &mut stripped.definitions {
+        if let Definition::Operation(operation) = definition {
+            strip_selection_set(operation_selection_set_mut(operation), &empty_fragments);
+        }
+    }
+    stripped.definitions.retain(|definition| match definition {
+        Definition::Operation(operation) => !operation_selection_set(operation).items.is_empty(),
+        Definition::Fragment(_) => true
+    });
+
+    // Servers reject documents with unused fragments
+    let fragments: HashMap<&str, &FragmentDefinition> = stripped
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Fragment(fragment) => Some((fragment.name.as_str(), fragment)),
+            Definition::Operation(_) => None
+        })
+        .collect();
+    let mut spreads = Vec::new();
+    for definition in &stripped.definitions {
+        if let Definition::Operation(operation) = definition {
+            spread_fragments(operation_selection_set(operation), &mut spreads);
+        }
+    }
+    let mut reachable = HashSet::new();
+    while let Some(name) = spreads.pop() {
+        if reachable.insert(name.to_string()) {
+            if let Some(fragment) = fragments.get(name) {
+                spread_fragments(&fragment.selection_set, &mut spreads);
+            }
+        }
+    }
+    stripped.definitions.retain(|definition| match definition {
+        Definition::Operation(_) => true,
+        Definition::Fragment(fragment) => reachable.contains(&fragment.name)
+    });
+
+    stripped
+}
+
+/// A parsed document containing local fields.
+struct LocalDocument {
+    document: Document,
+    /// The document without local fields
+    stripped: Document,
+    /// `stripped` as a string, `None` if none of the operations select server fields.
+    server_query: Option<Arc<str>>
+}
+
+impl LocalDocument {
+    fn parse(query: &str) -> Result<Self, LocalStateError> {
+        let document = graphql_parser::parse_query(query)
+            .map_err(|e| LocalStateError::ParseError(e.to_string()))?;
+        let stripped = strip_local_fields(&document);
+        let server_query = if stripped.definitions.is_empty() {
+            None
+        } else {
+            Some(stripped.to_string().into())
+        };
+        Ok(Self {
+            document,
+            stripped,
+            server_query
+        })
+    }
+
+    fn fragment(&self, name: &str) -> Option<&FragmentDefinition> {
+        self.document
+            .definitions
+            .iter()
+            .find_map(|definition| match definition {
+                Definition::Fragment(fragment) if fragment.name == name => Some(fragment),
+                _ => None
+            })
+    }
+
+    /// Whether the operation selects any server fields.
+    fn has_server_fields(&self, name: &str) -> bool {
+        let name = match find_operation(&self.document, name) {
+            Some(operation) => operation_name(operation),
+            None => return false
+        };
+        self.stripped
+            .definitions
+            .iter()
+            .any(|definition| match definition {
+                Definition::Operation(operation) => operation_name(operation) == name,
+                Definition::Fragment(_) => false
+            })
+    }
+}
+
+/// The server part of an operation with local fields. The response is kept as JSON, since the
+/// local fields required by `Q::ResponseData` are only added afterwards.
+struct ServerQuery<Q>(PhantomData<Q>);
+
+#[derive(Serialize, Deserialize)]
+#[serde(transparent, bound = "")]
+struct ServerData<Q> {
+    data: Value,
+    #[serde(skip)]
+    _query: PhantomData<fn() -> Q>
+}
+
+impl<Q> Clone for ServerData<Q> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            _query: PhantomData
+        }
+    }
+}
+
+impl<Q: GraphQLQuery> QueryInfo<Q::Variables> for ServerData<Q> {
+    fn selection(variables: &Q::Variables) -> Vec<FieldSelector> {
+        // Codegen leaves local fields out of the selection
+        Q::selection(variables)
+    }
+}
+
+impl<Q: GraphQLQuery> GraphQLQuery for ServerQuery<Q> {
+    type Variables = Q::Variables;
+    type ResponseData = ServerData<Q>;
+
+    fn build_query(variables: Self::Variables) -> (QueryBody<Self::Variables>, OperationMeta) {
+        Q::build_query(variables)
+    }
+}
+
+/// Merges local fields into server data.
+struct LocalResolver<'a> {
+    resolvers: &'a HashMap<(String, String), Resolver>,
+    document: &'a LocalDocument,
+    variables: &'a Value
+}
+
+impl<'a> LocalResolver<'a> {
+    /// Resolves the local fields in the selection set on `value`.
+    /// If `strict` is false, the selection came from a fragment that may not apply to `value`,
+    /// so local fields without a resolver are skipped instead of failing.
+    fn resolve(
+        &self,
+        selection_set: &SelectionSet,
+        value: &mut Value,
+        type_name: Option<&str>,
+        strict: bool
+    ) -> Result<(), QueryError> {
+        if let Value::Array(items) = value {
+            for item in items {
+                self.resolve(selection_set, item, type_name, strict)?;
+            }
+            return Ok(());
+        }
+        if !value.is_object() {
+            return Ok(());
+        }
+
+        let type_name = value
+            .get("__typename")
+            .and_then(Value::as_str)
+            .map(ToString::to_string)
+            .or_else(|| type_name.map(ToString::to_string));
+
+        for item in &selection_set.items {
+            match item {
+                Selection::Field(field) if is_local(&field.directives) => {
+                    let type_name = match &type_name {
+                        Some(type_name) => type_name,
+                        None if strict => {
+                            return Err(LocalStateError::MissingTypename(field.name.clone()).into())
+                        }
+                        None => continue
+                    };
+                    let resolver = match self
+                        .resolvers
+                        .get(&(type_name.to_string(), field.name.clone()))
+                    {
+                        Some(resolver) => resolver,
+                        None if strict => {
+                            return Err(LocalStateError::MissingResolver(
+                                type_name.to_string(),
+                                field.name.clone()
+                            )
+                            .into())
+                        }
+                        None => continue
+                    };
+                    let arguments = field
+                        .arguments
+                        .iter()
+                        .map(|(name, value)| (name.clone(), argument_value(value, self.variables)))
+                        .collect();
+                    let resolved = resolver(&ResolverContext {
+                        parent: value,
+                        arguments: &arguments,
+                        variables: self.variables
+                    })?;
+                    let key = field.alias.as_ref().unwrap_or(&field.name).clone();
+                    value.as_object_mut().unwrap().insert(key, resolved);
+                }
+                Selection::Field(field) => {
+                    let key = field.alias.as_ref().unwrap_or(&field.name);
+                    if let Some(child) = value.get_mut(key) {
+                        self.resolve(&field.selection_set, child, None, strict)?;
+                    }
+                }
+                Selection::FragmentSpread(spread) => {
+                    if let Some(fragment) = self.document.fragment(&spread.fragment_name) {
+                        let TypeCondition::On(on) = &fragment.type_condition;
+                        let applies = type_name.as_deref() == Some(on.as_str());
+                        self.resolve(
+                            &fragment.selection_set,
+                            value,
+                            type_name.as_deref(),
+                            strict && applies
+                        )?;
+                    }
+                }
+                Selection::InlineFragment(fragment) => {
+                    let applies = match &fragment.type_condition {
+                        Some(TypeCondition::On(on)) => type_name.as_deref() == Some(on.as_str()),
+                        None => true
+                    };
+                    self.resolve(
+                        &fragment.selection_set,
+                        value,
+                        type_name.as_deref(),
+                        strict && applies
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An exchange that resolves fields marked with the `@client` directive locally.
+///
+/// Local fields are removed from the document before it's passed on, and resolved with the
+/// registered resolvers once the server data arrives. Operations that only select local fields
+/// aren't passed on at all. Resolvers are registered for a type name and field name, where root
+/// fields use `Query` and `Mutation`. Nested local fields use the `__typename` of their parent,
+/// so it must be selected.
+///
+/// The schema of local fields is declared in client schema extension files, which are added to
+/// the code generator with `CodegenBuilder::add_client_schema` in `artemis-build`.
+/// Since local fields aren't part of the cached selection, they're resolved again every time,
+/// even for cached results. Subscriptions are passed through unchanged.
+///
+/// This must be the first exchange in the chain (the last one added to the builder).
+///
+/// Requires feature: `local-state`
+///
+/// # Example
+///
+/// ```
+/// use artemis::{
+///     default_exchanges::{CacheExchange, DedupExchange, FetchExchange, LocalStateExchange},
+///     ClientBuilder
+/// };
+/// use std::sync::{Arc, Mutex};
+///
+/// let selected_tab = Arc::new(Mutex::new("home".to_string()));
+///
+/// let local_state = LocalStateExchange::new()
+///     .with_resolver("Query", "selectedTab", move |_| {
+///         Ok(selected_tab.lock().unwrap().clone().into())
+///     });
+///
+/// let client = ClientBuilder::new("http://localhost:8080/graphql")
+///     .with_exchange(FetchExchange)
+///     .with_exchange(CacheExchange)
+///     .with_exchange(DedupExchange)
+///     .with_exchange(local_state)
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct LocalStateExchange {
+    resolvers: HashMap<(String, String), Resolver>
+}
+
+impl LocalStateExchange {
+    /// Creates a new local state exchange without any resolvers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the resolver for a local field. Its result is inserted into the response data
+    /// as is, so it must match the shape of the selection.
+    pub fn with_resolver<F>(mut self, type_name: &str, field_name: &str, resolver: F) -> Self
+    where
+        F: Fn(&ResolverContext<'_>) -> Result<Value, QueryError> + Send + Sync + 'static
+    {
+        self.resolvers.insert(
+            (type_name.to_string(), field_name.to_string()),
+            Box::new(resolver)
+        );
+        self
+    }
+}
+
+impl<TNext: Exchange> ExchangeFactory<TNext> for LocalStateExchange {
+    type Output = LocalStateExchangeImpl<TNext>;
+
+    fn build(self, next: TNext) -> Self::Output {
+        LocalStateExchangeImpl {
+            resolvers: self.resolvers,
+            documents: Mutex::new(CachedDocuments::default()),
+            next
+        }
+    }
+}
+
+/// How many split documents the exchange keeps around. Dynamic queries can bring any number of
+/// documents, so past this the one that went unused the longest is dropped.
+const DOCUMENT_CACHE_SIZE: usize = 128;
+
+#[derive(Default)]
+struct CachedDocuments {
+    /// Split documents by query key, with the tick they were last used at
+    documents: HashMap<u32, (Arc<LocalDocument>, u64)>,
+    tick: u64
+}
+
+pub struct LocalStateExchangeImpl<TNext: Exchange> {
+    resolvers: HashMap<(String, String), Resolver>,
+    documents: Mutex<CachedDocuments>,
+    next: TNext
+}
+
+impl<TNext: Exchange> LocalStateExchangeImpl<TNext> {
+    fn document(
+        &self,
+        meta: &OperationMeta,
+        query: &str
+    ) -> Result<Arc<LocalDocument>, QueryError> {
+        {
+            let mut cache = self.documents.lock();
+            cache.tick += 1;
+            let tick = cache.tick;
+            if let Some((document, last_used)) = cache.documents.get_mut(&meta.query_key) {
+                *last_used = tick;
+                return Ok(document.clone());
+            }
+        }
+
+        let document = Arc::new(LocalDocument::parse(query)?);
+        let mut cache = self.documents.lock();
+        if cache.documents.len() >= DOCUMENT_CACHE_SIZE {
+            let least_recently_used = cache
+                .documents
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key);
+            if let Some(key) = least_recently_used {
+                cache.documents.remove(&key);
+            }
+        }
+        let tick = cache.tick;
+        cache
+            .documents
+            .insert(meta.query_key, (document.clone(), tick));
+        Ok(document)
+    }
+}
+
+fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, QueryError> {
+    Ok(serde_json::from_value(value)?)
+}
+
+#[async_trait]
+impl<TNext: Exchange> Exchange for LocalStateExchangeImpl<TNext> {
+    async fn run<Q: GraphQLQuery, C: Client>(
+        &self,
+        mut operation: Operation<Q::Variables>,
+        client: C
+    ) -> ExchangeResult<Q::ResponseData> {
+        if operation.meta.operation_type == OperationType::Subscription
+            || !operation.query.query.contains("@client")
+        {
+            return self.next.run::<Q, _>(operation, client).await;
+        }
+
+        let document = self.document(&operation.meta, &operation.query.query)?;
+        let variables = serde_json::to_value(&operation.query.variables)?;
+        let operation_name = operation.query.operation_name.clone();
+        let key = operation.key;
+        let meta = operation.meta.clone();
+
+        let (data, errors, debug_info) = match &document.server_query {
+            Some(server_query) if document.has_server_fields(&operation_name) => {
+                operation.query.query = Cow::Owned(server_query.to_string());
+                let result = self
+                    .next
+                    .run::<ServerQuery<Q>, _>(operation, client)
+                    .await?;
+                let response = result.response;
+                let data = response.data.map(|data| data.data);
+                (data, response.errors, response.debug_info)
+            }
+            _ => {
+                let debug_info = Some(DebugInfo {
+                    source: ResultSource::Cache,
+                    did_dedup: false,
+                    status: None
+                });
+                (Some(Value::Object(Map::new())), None, debug_info)
+            }
+        };
+
+        let data = match data {
+            Some(mut data) => {
+                if let Some(definition) = find_operation(&document.document, &operation_name) {
+                    let resolver = LocalResolver {
+                        resolvers: &self.resolvers,
+                        document: &document,
+                        variables: &variables
+                    };
+                    resolver.resolve(
+                        operation_selection_set(definition),
+                        &mut data,
+                        Some(root_type_name(&meta.operation_type)),
+                        true
+                    )?;
+                }
+                Some(from_value(data)?)
+            }
+            None => None
+        };
+
+        Ok(OperationResult {
+            key,
+            meta,
+            response: Response {
+                debug_info,
+                data,
+                errors
+            }
+        })
+    }
+
+    fn reset(&self) {
+        self.next.reset();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test {
+    use super::{strip_local_fields, LocalStateError, LocalStateExchange, DOCUMENT_CACHE_SIZE};
+    use crate::{
+        codegen::{FieldSelector, QueryInfo},
+        exchange::{Operation, OperationMeta},
+        test_utils::{data_response, make_operation, TestExchange},
+        ClientBuilder, Exchange, ExchangeFactory, GraphQLQuery, OperationType, QueryBody
+    };
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    const QUERY: &str = r#"
+        query Local {
+          selectedTab @client
+          human(id: "1000") {
+            __typename
+            name
+            isFavorite @client
+          }
+        }
+
+        query Tab {
+          selectedTab @client
+        }
+    "#;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Human {
+        name: String,
+        is_favorite: bool
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct LocalData {
+        selected_tab: String,
+        human: Option<Human>
+    }
+
+    impl QueryInfo<()> for LocalData {
+        fn selection(_variables: &()) -> Vec<FieldSelector> {
+            Vec::new()
+        }
+    }
+
+    struct LocalQuery;
+
+    impl GraphQLQuery for LocalQuery {
+        type Variables = ();
+        type ResponseData = LocalData;
+
+        fn build_query(variables: ()) -> (QueryBody<()>, OperationMeta) {
+            let body = QueryBody {
+                variables,
+                query: QUERY.into(),
+                operation_name: "Local".into()
+            };
+            let meta = OperationMeta {
+                query_key: 3,
+                operation_type: OperationType::Query,
+                involved_types: Vec::new()
+            };
+            (body, meta)
+        }
+    }
+
+    fn operation(operation_name: &'static str) -> Operation<()> {
+        let mut operation = make_operation::<LocalQuery>(3, ());
+        operation.query.operation_name = operation_name.into();
+        operation
+    }
+
+    /// Records the documents it receives and returns the server part of `LocalData`.
+    fn server_exchange(queries: &Arc<Mutex<Vec<String>>>) -> impl Exchange {
+        let queries = queries.clone();
+        TestExchange::new(move |request| {
+            queries
+                .lock()
+                .unwrap()
+                .push(request.operation.query.query.to_string());
+            async move {
+                Ok(data_response(json!({
+                    "human": { "__typename": "Human", "name": "Luke" }
+                })))
+            }
+        })
+    }
+
+    fn local_state() -> LocalStateExchange {
+        LocalStateExchange::new()
+            .with_resolver("Query", "selectedTab", |_| Ok(json!("home")))
+            .with_resolver("Human", "isFavorite", |context| {
+                Ok((context.parent["name"] == "Luke").into())
+            })
+    }
+
+    #[test]
+    fn test_strip_local_fields() {
+        let document = graphql_parser::parse_query(
+            r#"
+            query Test {
+              selectedTab @client
+              human(id: "1000") {
+                name
+                ...HumanLocal
+                ...HumanServer
+              }
+            }
+
+            query LocalOnly {
+              selectedTab @client
+            }
+
+            fragment HumanLocal on Human {
+              isFavorite @client
+            }
+
+            fragment HumanServer on Human {
+              height
+            }
+            "#
+        )
+        .unwrap();
+        let stripped = strip_local_fields(&document).to_string();
+
+        assert!(!stripped.contains("@client"));
+        assert!(!stripped.contains("selectedTab"));
+        assert!(!stripped.contains("LocalOnly"));
+        assert!(!stripped.contains("HumanLocal"));
+        assert!(stripped.contains("...HumanServer"));
+        assert!(stripped.contains("fragment HumanServer on Human"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_local_fields() {
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let exchange = local_state().build(server_exchange(&queries));
+        let client = ClientBuilder::new("http://localhost:4000/graphql").build();
+
+        let result = exchange
+            .run::<LocalQuery, _>(operation("Local"), client.0.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            result.response.data,
+            Some(LocalData {
+                selected_tab: "home".to_string(),
+                human: Some(Human {
+                    name: "Luke".to_string(),
+                    is_favorite: true
+                })
+            })
+        );
+
+        let queries = queries.lock().unwrap();
+        assert_eq!(queries.len(), 1);
+        assert!(!queries[0].contains("@client"));
+        assert!(!queries[0].contains("query Tab"));
+    }
+
+    #[tokio::test]
+    async fn test_local_only_operation() {
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let exchange = local_state().build(server_exchange(&queries));
+        let client = ClientBuilder::new("http://localhost:4000/graphql").build();
+
+        let result = exchange
+            .run::<LocalQuery, _>(operation("Tab"), client.0.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            result.response.data,
+            Some(LocalData {
+                selected_tab: "home".to_string(),
+                human: None
+            })
+        );
+        assert!(queries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_missing_resolver() {
+        let exchange = LocalStateExchange::new()
+            .with_resolver("Query", "selectedTab", |_| Ok(json!("home")))
+            .build(server_exchange(&Arc::default()));
+        let client = ClientBuilder::new("http://localhost:4000/graphql").build();
+
+        let error = exchange
+            .run::<LocalQuery, _>(operation("Local"), client.0.clone())
+            .await
+            .unwrap_err();
+        match error.downcast_ref::<LocalStateError>() {
+            Some(LocalStateError::MissingResolver(type_name, field_name)) => {
+                assert_eq!(type_name, "Human");
+                assert_eq!(field_name, "isFavorite");
+            }
+            _ => panic!("expected missing resolver error, got {}", error)
+        }
+    }
+
+    #[test]
+    fn test_document_cache_is_bounded() {
+        let exchange = local_state().build(server_exchange(&Arc::default()));
+        let meta = |query_key: usize| OperationMeta {
+            query_key: query_key as u32,
+            operation_type: OperationType::Query,
+            involved_types: Vec::new()
+        };
+
+        let first = exchange.document(&meta(0), QUERY).unwrap();
+        for key in 1..DOCUMENT_CACHE_SIZE {
+            exchange.document(&meta(key), QUERY).unwrap();
+        }
+        // Using the first document again makes the second one the least recently used
+        assert!(Arc::ptr_eq(
+            &first,
+            &exchange.document(&meta(0), QUERY).unwrap()
+        ));
+        exchange
+            .document(&meta(DOCUMENT_CACHE_SIZE), QUERY)
+            .unwrap();
+
+        let documents = &exchange.documents.lock().documents;
+        assert_eq!(documents.len(), DOCUMENT_CACHE_SIZE);
+        assert!(documents.contains_key(&0));
+        assert!(!documents.contains_key(&1));
+    }
+}
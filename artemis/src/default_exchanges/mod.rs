@@ -1,23 +1,41 @@
 //! This module contains the default exchanges.
-//! Note that these require the `default-exchanges` feature.
+//! Note that these require the `default-exchanges` feature,
+//! except for the `SchedulingExchange`, which requires the `scheduling` feature,
+//! the `CircuitBreakerExchange`, which requires the `circuit-breaker` feature,
+//! and the `LocalStateExchange`, which requires the `local-state` feature.
 
 use crate::types::{Exchange, Operation};
 use std::{error::Error, fmt};
 
 #[cfg(feature = "default-exchanges")]
 mod cache;
+#[cfg(feature = "circuit-breaker")]
+mod circuit_breaker;
 #[cfg(feature = "default-exchanges")]
 mod dedup;
 #[cfg(feature = "default-exchanges")]
 mod fetch;
+#[cfg(feature = "local-state")]
+mod local_state;
+#[cfg(feature = "scheduling")]
+mod scheduling;
 
 use crate::{exchange::Client, ExchangeResult, GraphQLQuery};
 #[cfg(feature = "default-exchanges")]
 pub use cache::CacheExchange;
+#[cfg(feature = "circuit-breaker")]
+pub use circuit_breaker::{
+    CircuitBreakerExchange, CircuitMonitor, CircuitOpenError, CircuitState, CircuitStateChange,
+    CircuitStateChanges
+};
 #[cfg(feature = "default-exchanges")]
-pub use dedup::DedupExchange;
+pub use dedup::{DedupExchange, DedupExtension};
 #[cfg(feature = "default-exchanges")]
-pub use fetch::FetchExchange;
+pub use fetch::{FetchError, FetchExchange};
+#[cfg(feature = "local-state")]
+pub use local_state::{LocalStateError, LocalStateExchange, ResolverContext};
+#[cfg(feature = "scheduling")]
+pub use scheduling::{Priority, SchedulingExchange, SchedulingMetrics};
 
 #[derive(Debug)]
 enum MiddlewareError {
@@ -0,0 +1,540 @@
+use crate::{
+    exchange::{Client, Extension},
+    types::{ExchangeResult, Operation},
+    Exchange, ExchangeFactory, GraphQLQuery, OperationType
+};
+use futures::{
+    channel::oneshot,
+    future::{self, Either}
+};
+use futures_timer::Delay;
+use instant::Instant;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration
+};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::JsValue;
+
+/// The priority of an operation in the `SchedulingExchange`, passed in the `extensions` of
+/// `QueryOptions`. Higher priorities are started first, operations with the same priority are
+/// started in the order they arrived.
+/// The JavaScript key is `Priority`, with a number as the value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Priority(pub i32);
+
+impl Priority {
+    /// For operations the user is actively waiting on.
+    pub const HIGH: Priority = Priority(100);
+    /// The priority of operations without a `Priority` extension.
+    pub const NORMAL: Priority = Priority(0);
+    /// For background work, such as prefetching.
+    pub const LOW: Priority = Priority(-100);
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority::NORMAL
+    }
+}
+
+impl Extension for Priority {
+    #[cfg(target_arch = "wasm32")]
+    fn from_js(value: JsValue) -> Option<Self> {
+        value.as_f64().map(|priority| Priority(priority as i32))
+    }
+}
+
+/// A token bucket that holds up to `burst` tokens and gains one every `refill_interval`.
+struct TokenBucket {
+    burst: f64,
+    refill_interval: Duration,
+    tokens: f64,
+    last_refill: Instant
+}
+
+impl TokenBucket {
+    fn new(burst: u32, refill_interval: Duration) -> Self {
+        Self {
+            burst: burst as f64,
+            refill_interval,
+            tokens: burst as f64,
+            last_refill: Instant::now()
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now - self.last_refill;
+        let refilled = elapsed.as_secs_f64() / self.refill_interval.as_secs_f64();
+        self.tokens = (self.tokens + refilled).min(self.burst);
+        self.last_refill = now;
+    }
+
+    /// How long to wait before checking for a token again.
+    fn retry_after(&self) -> Duration {
+        if self.tokens < 1.0 {
+            self.refill_interval.mul_f64(1.0 - self.tokens)
+        } else {
+            self.refill_interval
+        }
+    }
+}
+
+struct Waiting {
+    priority: Priority,
+    url: String,
+    sender: oneshot::Sender<Permit>
+}
+
+#[derive(Default)]
+struct State {
+    max_concurrency: Option<usize>,
+    max_concurrency_per_url: Option<usize>,
+    rate_limit: Option<TokenBucket>,
+    in_flight: usize,
+    in_flight_per_url: HashMap<String, usize>,
+    // Sorted by priority, then arrival
+    queue: Vec<Waiting>
+}
+
+impl State {
+    fn enqueue(&mut self, waiting: Waiting) {
+        let index = self
+            .queue
+            .iter()
+            .position(|other| other.priority < waiting.priority)
+            .unwrap_or(self.queue.len());
+        self.queue.insert(index, waiting);
+    }
+}
+
+#[derive(Default)]
+struct Scheduler {
+    state: Mutex<State>
+}
+
+type Ready = Vec<(oneshot::Sender<Permit>, Permit)>;
+
+impl Scheduler {
+    /// Starts as many waiting operations as the limits allow.
+    /// The returned permits must be sent after the lock is released, since a permit that can't be
+    /// delivered is dropped, which dispatches again.
+    fn dispatch(self: &Arc<Self>, state: &mut State) -> Ready {
+        let mut ready = Vec::new();
+        if let Some(ref mut bucket) = state.rate_limit {
+            bucket.refill();
+        }
+
+        let mut index = 0;
+        while index < state.queue.len() {
+            if state.queue[index].sender.is_canceled() {
+                state.queue.remove(index);
+                continue;
+            }
+            if let Some(max) = state.max_concurrency {
+                if state.in_flight >= max {
+                    break;
+                }
+            }
+            if let Some(max) = state.max_concurrency_per_url {
+                let url = &state.queue[index].url;
+                if state.in_flight_per_url.get(url).copied().unwrap_or(0) >= max {
+                    index += 1;
+                    continue;
+                }
+            }
+            if let Some(ref mut bucket) = state.rate_limit {
+                if bucket.tokens < 1.0 {
+                    break;
+                }
+                bucket.tokens -= 1.0;
+            }
+
+            let waiting = state.queue.remove(index);
+            state.in_flight += 1;
+            *state
+                .in_flight_per_url
+                .entry(waiting.url.clone())
+                .or_insert(0) += 1;
+            let permit = Permit {
+                scheduler: self.clone(),
+                url: waiting.url
+            };
+            ready.push((waiting.sender, permit));
+        }
+        ready
+    }
+
+    fn send(ready: Ready) {
+        for (sender, permit) in ready {
+            // If the operation was cancelled in the meantime, the permit is dropped and released
+            let _ = sender.send(permit);
+        }
+    }
+
+    fn release(self: &Arc<Self>, url: &str) {
+        let ready = {
+            let mut state = self.state.lock().unwrap();
+            state.in_flight -= 1;
+            if let Some(count) = state.in_flight_per_url.get_mut(url) {
+                *count -= 1;
+                if *count == 0 {
+                    state.in_flight_per_url.remove(url);
+                }
+            }
+            self.dispatch(&mut state)
+        };
+        Self::send(ready);
+    }
+
+    async fn acquire(self: &Arc<Self>, url: String, priority: Priority) -> Permit {
+        let (sender, mut receiver) = oneshot::channel();
+        let ready = {
+            let mut state = self.state.lock().unwrap();
+            state.enqueue(Waiting {
+                priority,
+                url,
+                sender
+            });
+            self.dispatch(&mut state)
+        };
+        Self::send(ready);
+
+        loop {
+            // Nothing else wakes up waiting operations when tokens are refilled, so check again
+            // periodically while rate limited
+            let retry_after = {
+                let state = self.state.lock().unwrap();
+                state.rate_limit.as_ref().map(TokenBucket::retry_after)
+            };
+            let permit = match retry_after {
+                Some(retry_after) => {
+                    match future::select(&mut receiver, Delay::new(retry_after)).await {
+                        Either::Left((permit, _)) => permit,
+                        Either::Right(_) => {
+                            let ready = {
+                                let mut state = self.state.lock().unwrap();
+                                self.dispatch(&mut state)
+                            };
+                            Self::send(ready);
+                            continue;
+                        }
+                    }
+                }
+                None => (&mut receiver).await
+            };
+            return permit.expect("Waiting operations are only removed once they're cancelled");
+        }
+    }
+}
+
+/// Held by a running operation, releases its slot when dropped.
+struct Permit {
+    scheduler: Arc<Scheduler>,
+    url: String
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.scheduler.release(&self.url);
+    }
+}
+
+/// Metrics of a `SchedulingExchange`. Get them from
+/// [`SchedulingExchange::metrics`](./struct.SchedulingExchange.html#method.metrics)
+/// before adding the exchange to the client.
+#[derive(Clone)]
+pub struct SchedulingMetrics {
+    scheduler: Arc<Scheduler>
+}
+
+impl SchedulingMetrics {
+    /// The number of operations waiting to be started.
+    pub fn queue_depth(&self) -> usize {
+        self.scheduler.state.lock().unwrap().queue.len()
+    }
+
+    /// The number of operations waiting to be sent to this URL.
+    pub fn queue_depth_for(&self, url: &str) -> usize {
+        let state = self.scheduler.state.lock().unwrap();
+        state
+            .queue
+            .iter()
+            .filter(|waiting| waiting.url == url)
+            .count()
+    }
+
+    /// The number of operations currently running.
+    pub fn in_flight(&self) -> usize {
+        self.scheduler.state.lock().unwrap().in_flight
+    }
+}
+
+/// An exchange that limits how many operations run at once and how fast they're started.
+///
+/// Operations that can't start yet are queued by their [`Priority`](./struct.Priority.html).
+/// Subscriptions are never queued, since they would hold on to their slot indefinitely.
+/// This should usually be added right before the `FetchExchange`, so cached results aren't queued.
+///
+/// Requires feature: `scheduling`
+///
+/// # Example
+///
+/// ```
+/// use artemis::{
+///     default_exchanges::{CacheExchange, DedupExchange, FetchExchange, SchedulingExchange},
+///     ClientBuilder
+/// };
+/// use std::time::Duration;
+///
+/// let scheduling = SchedulingExchange::new()
+///     .with_max_concurrency(8)
+///     .with_max_concurrency_per_url(4)
+///     .with_rate_limit(20, Duration::from_millis(100));
+/// let metrics = scheduling.metrics();
+///
+/// let client = ClientBuilder::new("http://localhost:8080/graphql")
+///     .with_exchange(FetchExchange)
+///     .with_exchange(scheduling)
+///     .with_exchange(CacheExchange)
+///     .with_exchange(DedupExchange)
+///     .build();
+///
+/// assert_eq!(metrics.queue_depth(), 0);
+/// ```
+#[derive(Default)]
+pub struct SchedulingExchange {
+    scheduler: Arc<Scheduler>
+}
+
+impl SchedulingExchange {
+    /// Creates a new scheduling exchange without any limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the number of operations running at once across all URLs.
+    pub fn with_max_concurrency(self, max_concurrency: usize) -> Self {
+        self.scheduler.state.lock().unwrap().max_concurrency = Some(max_concurrency.max(1));
+        self
+    }
+
+    /// Limits the number of operations running at once for each URL.
+    pub fn with_max_concurrency_per_url(self, max_concurrency: usize) -> Self {
+        self.scheduler.state.lock().unwrap().max_concurrency_per_url = Some(max_concurrency.max(1));
+        self
+    }
+
+    /// Limits how fast operations are started with a token bucket.
+    /// Up to `burst` operations can start at once, after which one more can start every
+    /// `refill_interval`.
+    pub fn with_rate_limit(self, burst: u32, refill_interval: Duration) -> Self {
+        self.scheduler.state.lock().unwrap().rate_limit =
+            Some(TokenBucket::new(burst.max(1), refill_interval));
+        self
+    }
+
+    /// Returns a handle to the metrics of this exchange, such as the queue depth.
+    pub fn metrics(&self) -> SchedulingMetrics {
+        SchedulingMetrics {
+            scheduler: self.scheduler.clone()
+        }
+    }
+}
+
+impl<TNext: Exchange> ExchangeFactory<TNext> for SchedulingExchange {
+    type Output = SchedulingExchangeImpl<TNext>;
+
+    fn build(self, next: TNext) -> Self::Output {
+        SchedulingExchangeImpl {
+            scheduler: self.scheduler,
+            next
+        }
+    }
+}
+
+pub struct SchedulingExchangeImpl<TNext: Exchange> {
+    scheduler: Arc<Scheduler>,
+    next: TNext
+}
+
+#[async_trait]
+impl<TNext: Exchange> Exchange for SchedulingExchangeImpl<TNext> {
+    async fn run<Q: GraphQLQuery, C: Client>(
+        &self,
+        operation: Operation<Q::Variables>,
+        client: C
+    ) -> ExchangeResult<Q::ResponseData> {
+        if operation.meta.operation_type == OperationType::Subscription {
+            return self.next.run::<Q, _>(operation, client).await;
+        }
+
+        let priority = operation
+            .options
+            .extensions
+            .as_ref()
+            .and_then(|ext| ext.get::<Priority, _>("Priority"))
+            .unwrap_or_default();
+        let _permit = self
+            .scheduler
+            .acquire(operation.options.url.clone(), priority)
+            .await;
+        self.next.run::<Q, _>(operation, client).await
+    }
+
+    fn reset(&self) {
+        self.next.reset();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test {
+    use super::{Priority, SchedulingExchange};
+    use crate::{
+        exchange::Operation,
+        test_utils::{data_response, make_operation, TestExchange, TestQuery, TEST_URL},
+        ClientBuilder, Exchange, ExchangeFactory, ExtensionMap
+    };
+    use futures::{
+        channel::oneshot,
+        future::{FutureExt, Shared}
+    };
+    use serde_json::json;
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant}
+    };
+    use tokio::time::sleep;
+
+    /// Records the order in which operations start and the highest number running at once. With
+    /// a `gate`, operations only finish once it's released.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        started: Arc<Mutex<Vec<u64>>>,
+        running: Arc<Mutex<(usize, usize)>>,
+        gate: Option<Shared<oneshot::Receiver<()>>>
+    }
+
+    impl Recorder {
+        fn exchange(&self) -> impl Exchange {
+            let recorder = self.clone();
+            TestExchange::new(move |request| {
+                let recorder = recorder.clone();
+                async move {
+                    recorder.started.lock().unwrap().push(request.operation.key);
+                    {
+                        let mut running = recorder.running.lock().unwrap();
+                        running.0 += 1;
+                        running.1 = running.1.max(running.0);
+                    }
+                    if let Some(gate) = recorder.gate {
+                        gate.await.unwrap();
+                    }
+                    sleep(Duration::from_millis(10)).await;
+                    recorder.running.lock().unwrap().0 -= 1;
+                    Ok(data_response(json!(1)))
+                }
+            })
+        }
+    }
+
+    fn scheduled_operation(key: u64, url: &str, priority: Option<Priority>) -> Operation<()> {
+        let mut operation = make_operation::<TestQuery>(key, ());
+        operation.options.url = url.to_string();
+        operation.options.extensions = priority.map(|priority| {
+            let mut extensions = ExtensionMap::new();
+            extensions.insert(priority);
+            Arc::new(extensions)
+        });
+        operation
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limits() {
+        let recorder = Recorder::default();
+        let scheduling = SchedulingExchange::new()
+            .with_max_concurrency(3)
+            .with_max_concurrency_per_url(2);
+        let exchange = scheduling.build(recorder.exchange());
+        let client = ClientBuilder::new(TEST_URL).build();
+
+        let operations = (0..6).map(|key| {
+            let operation = scheduled_operation(key, TEST_URL, None);
+            exchange.run::<TestQuery, _>(operation, client.0.clone())
+        });
+        futures::future::join_all(operations).await;
+        assert_eq!(recorder.running.lock().unwrap().1, 2);
+
+        let operations = (0..6).map(|key| {
+            let url = format!("http://localhost:{}/graphql", 4000 + key % 3);
+            let operation = scheduled_operation(key, &url, None);
+            exchange.run::<TestQuery, _>(operation, client.0.clone())
+        });
+        futures::future::join_all(operations).await;
+        assert_eq!(recorder.running.lock().unwrap().1, 3);
+    }
+
+    #[tokio::test]
+    async fn test_priority() {
+        let (release, gate) = oneshot::channel();
+        let recorder = Recorder {
+            gate: Some(gate.shared()),
+            ..Recorder::default()
+        };
+        let scheduling = SchedulingExchange::new().with_max_concurrency(1);
+        let metrics = scheduling.metrics();
+        let exchange = scheduling.build(recorder.exchange());
+        let client = ClientBuilder::new(TEST_URL).build();
+        let url = TEST_URL;
+
+        let blocking =
+            exchange.run::<TestQuery, _>(scheduled_operation(0, url, None), client.0.clone());
+        let low = exchange.run::<TestQuery, _>(
+            scheduled_operation(1, url, Some(Priority::LOW)),
+            client.0.clone()
+        );
+        let normal =
+            exchange.run::<TestQuery, _>(scheduled_operation(2, url, None), client.0.clone());
+        let high = exchange.run::<TestQuery, _>(
+            scheduled_operation(3, url, Some(Priority::HIGH)),
+            client.0.clone()
+        );
+        let check_depth = async {
+            tokio::task::yield_now().await;
+            assert_eq!(metrics.queue_depth(), 3);
+            assert_eq!(metrics.queue_depth_for(url), 3);
+            assert_eq!(metrics.in_flight(), 1);
+            release.send(()).unwrap();
+        };
+        let (blocking, low, normal, high, _) =
+            futures::join!(blocking, low, normal, high, check_depth);
+        for result in [blocking, low, normal, high] {
+            result.unwrap();
+        }
+
+        assert_eq!(*recorder.started.lock().unwrap(), vec![0, 3, 2, 1]);
+        assert_eq!(metrics.queue_depth(), 0);
+        assert_eq!(metrics.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let recorder = Recorder::default();
+        let scheduling = SchedulingExchange::new().with_rate_limit(2, Duration::from_millis(20));
+        let exchange = scheduling.build(recorder.exchange());
+        let client = ClientBuilder::new(TEST_URL).build();
+
+        let start = Instant::now();
+        let operations = (0..4).map(|key| {
+            let operation = scheduled_operation(key, TEST_URL, None);
+            exchange.run::<TestQuery, _>(operation, client.0.clone())
+        });
+        futures::future::join_all(operations).await;
+
+        // Two start right away, the other two have to wait for a token each
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert_eq!(recorder.started.lock().unwrap().len(), 4);
+    }
+}
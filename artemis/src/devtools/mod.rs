@@ -0,0 +1,235 @@
+//! Devtools for inspecting what the client and its exchanges are doing.
+//!
+//! Devtools are disabled by default and can be enabled with
+//! [`ClientBuilder::with_devtools`](../struct.ClientBuilder.html#method.with_devtools).
+//! Once enabled, exchanges report typed [`DevtoolsEvent`](./enum.DevtoolsEvent.html)s through
+//! [`Client::emit_event`](../exchange/trait.Client.html#method.emit_event), which can be received
+//! as a stream from [`Devtools::events`](./struct.Devtools.html#method.events).
+//! The active subscriptions and their latest results can be inspected with
+//! [`Devtools::subscriptions`](./struct.Devtools.html#method.subscriptions).
+//!
+//! With the `devtools-server` feature, the [server](./server/index.html) module can serve both
+//! as JSON over HTTP for an inspector UI.
+//!
+//! # Example
+//!
+//! ```
+//! # tokio_test::block_on(async {
+//! use artemis::{devtools::DevtoolsEvent, Client};
+//! use futures::StreamExt;
+//!
+//! let client = Client::builder("http://localhost:8080/graphql")
+//!     .with_default_exchanges()
+//!     .with_devtools()
+//!     .build();
+//!
+//! let mut events = client.devtools().unwrap().events();
+//! tokio::spawn(async move {
+//!     while let Some(event) = events.next().await {
+//!         println!("{:?}", event);
+//!     }
+//! });
+//! # });
+//! ```
+
+#[cfg(feature = "observable")]
+use crate::client::observable::Subscription;
+use crate::{OperationType, RequestPolicy};
+use futures::{
+    channel::mpsc::{self, Receiver, Sender},
+    task::{Context, Poll},
+    Stream, StreamExt
+};
+use parking_lot::Mutex;
+use std::{borrow::Cow, pin::Pin};
+#[cfg(feature = "observable")]
+use std::{collections::HashMap, sync::Arc};
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "devtools-server"))]
+pub mod server;
+
+/// The number of events buffered for each event stream.
+/// If a stream falls further behind than this, new events are dropped for that stream.
+const EVENT_BUFFER: usize = 256;
+
+/// An event emitted by the client or one of its exchanges.
+/// Events are serialized with a `type` field containing the variant name in camel case.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum DevtoolsEvent {
+    /// An operation was passed to the exchanges, either directly or by rerunning a subscription.
+    #[serde(rename_all = "camelCase")]
+    OperationStarted {
+        key: u64,
+        operation_name: Cow<'static, str>,
+        operation_type: OperationType,
+        url: String,
+        request_policy: RequestPolicy
+    },
+    /// A caching exchange answered the operation.
+    CacheHit { key: u64, exchange: &'static str },
+    /// A caching exchange couldn't answer the operation and forwarded it.
+    CacheMiss { key: u64, exchange: &'static str },
+    /// The operation joined an identical one that was already in flight.
+    DedupJoined { key: u64 },
+    /// The operation is being sent over the network.
+    FetchSent { key: u64, url: String },
+    /// A network response was received for the operation.
+    /// `error` is set if the request failed, `has_errors` if the response contained GraphQL errors.
+    #[serde(rename_all = "camelCase")]
+    FetchReceived {
+        key: u64,
+        url: String,
+        has_errors: bool,
+        error: Option<String>
+    },
+    /// The result of the operation invalidated cached data.
+    /// `dependencies` are the invalidated types or entities, depending on the exchange.
+    Invalidated {
+        key: u64,
+        exchange: &'static str,
+        dependencies: Vec<String>
+    },
+    /// A subscribed query was rerun.
+    Rerun { key: u64 }
+}
+
+/// A snapshot of an active subscription.
+///
+/// Requires feature: `observable`
+#[cfg(feature = "observable")]
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionSnapshot {
+    /// The operation key of the subscription
+    pub key: u64,
+    /// The name of the subscribed operation
+    pub operation_name: Cow<'static, str>,
+    /// The number of observables listening to this subscription
+    pub listeners: usize,
+    /// The latest result pushed to the subscription, if any.
+    /// Errors are represented as `{ "error": message }`.
+    pub latest_result: Option<serde_json::Value>
+}
+
+/// The devtools of a client. Get them with
+/// [`Client::devtools`](../struct.Client.html#method.devtools).
+pub struct Devtools {
+    listeners: Mutex<Vec<Sender<DevtoolsEvent>>>,
+    #[cfg(feature = "observable")]
+    subscriptions: Arc<Mutex<HashMap<u64, Subscription>>>
+}
+
+impl Devtools {
+    #[cfg(feature = "observable")]
+    pub(crate) fn new(subscriptions: Arc<Mutex<HashMap<u64, Subscription>>>) -> Self {
+        Self {
+            listeners: Mutex::new(Vec::new()),
+            subscriptions
+        }
+    }
+
+    #[cfg(not(feature = "observable"))]
+    pub(crate) fn new() -> Self {
+        Self {
+            listeners: Mutex::new(Vec::new())
+        }
+    }
+
+    /// Returns a stream of all events emitted from now on.
+    /// Events are dropped for this stream if it isn't consumed fast enough.
+    pub fn events(&self) -> EventStream {
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+        self.listeners.lock().push(sender);
+        EventStream { receiver }
+    }
+
+    /// Returns a snapshot of all active subscriptions and their latest results.
+    ///
+    /// Requires feature: `observable`
+    #[cfg(feature = "observable")]
+    pub fn subscriptions(&self) -> Vec<SubscriptionSnapshot> {
+        let subscriptions = self.subscriptions.lock();
+        subscriptions
+            .iter()
+            .map(|(key, subscription)| SubscriptionSnapshot {
+                key: *key,
+                operation_name: subscription.operation_name.clone(),
+                listeners: subscription.listeners.listener_count(),
+                latest_result: (subscription.latest_result)()
+            })
+            .collect()
+    }
+
+    /// Sends an event to all event streams. The event is only created if there are any.
+    pub(crate) fn emit<F: FnOnce() -> DevtoolsEvent>(&self, event: F) {
+        let mut listeners = self.listeners.lock();
+        if listeners.is_empty() {
+            return;
+        }
+        let event = event();
+        listeners.retain(|listener| match listener.clone().try_send(event.clone()) {
+            Ok(()) => true,
+            Err(e) => e.is_full()
+        });
+    }
+}
+
+/// A stream of devtools events, returned from [`Devtools::events`](./struct.Devtools.html#method.events).
+pub struct EventStream {
+    receiver: Receiver<DevtoolsEvent>
+}
+
+impl Stream for EventStream {
+    type Item = DevtoolsEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32"), feature = "observable"))]
+mod test {
+    use super::DevtoolsEvent;
+    use crate::{
+        spawner::LocalPoolSpawner,
+        test_utils::{FakeExchange, TestQuery},
+        ClientBuilder
+    };
+    use futures::{executor::LocalPool, FutureExt, StreamExt};
+
+    #[test]
+    fn test_devtools() {
+        let mut pool = LocalPool::new();
+        let client = ClientBuilder::new("http://localhost:4000/graphql")
+            .with_exchange(FakeExchange)
+            .with_spawner(LocalPoolSpawner::new(&pool.spawner()).unwrap())
+            .with_devtools()
+            .build();
+        let devtools = client.devtools().unwrap();
+        let mut events = devtools.events();
+
+        let mut observable = client.subscribe(TestQuery, ());
+        pool.run_until(observable.next()).unwrap().unwrap();
+
+        match events.next().now_or_never().flatten() {
+            Some(DevtoolsEvent::Rerun { .. }) => {}
+            event => panic!("expected rerun event, got {:?}", event)
+        }
+        match events.next().now_or_never().flatten() {
+            Some(DevtoolsEvent::OperationStarted { operation_name, .. }) => {
+                assert_eq!(operation_name, "Test")
+            }
+            event => panic!("expected operation started event, got {:?}", event)
+        }
+
+        let subscriptions = devtools.subscriptions();
+        assert_eq!(subscriptions.len(), 1);
+        assert_eq!(subscriptions[0].operation_name, "Test");
+        assert_eq!(subscriptions[0].listeners, 1);
+        assert_eq!(
+            subscriptions[0].latest_result,
+            Some(serde_json::json!({ "data": 1, "errors": null, "debugInfo": null }))
+        );
+    }
+}
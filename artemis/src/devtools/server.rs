@@ -0,0 +1,176 @@
+//! A local HTTP server that exposes the devtools as JSON, for use by an inspector UI.
+//!
+//! Routes:
+//!
+//! * `GET /subscriptions` - A JSON array of
+//!   [`SubscriptionSnapshot`](../struct.SubscriptionSnapshot.html)s.
+//!   Always empty without the `observable` feature.
+//! * `GET /events` - A [Server-Sent Events](https://html.spec.whatwg.org/multipage/server-sent-events.html)
+//!   stream, where each message is a JSON [`DevtoolsEvent`](../enum.DevtoolsEvent.html).
+//!
+//! This is meant for local development only and should never be exposed publicly.
+//!
+//! Requires feature: `devtools-server`
+//!
+//! # Example
+//!
+//! ```no_run
+//! # tokio_test::block_on(async {
+//! use artemis::{devtools::server, Client};
+//!
+//! let client = Client::builder("http://localhost:8080/graphql")
+//!     .with_default_exchanges()
+//!     .with_devtools()
+//!     .build();
+//!
+//! tokio::spawn(server::serve(client.devtools().unwrap().clone(), "127.0.0.1:9229"));
+//! # });
+//! ```
+
+use super::Devtools;
+use futures::StreamExt;
+use std::{io, sync::Arc};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream, ToSocketAddrs}
+};
+
+/// Requests with a larger head than this are dropped.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// Binds to the address and serves the devtools until an error occurs while accepting connections.
+/// Must be run on a Tokio runtime.
+pub async fn serve<A: ToSocketAddrs>(devtools: Arc<Devtools>, addr: A) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    serve_listener(devtools, listener).await
+}
+
+/// Like [`serve`](./fn.serve.html), but uses an existing listener.
+pub async fn serve_listener(devtools: Arc<Devtools>, listener: TcpListener) -> io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let devtools = devtools.clone();
+        tokio::spawn(async move {
+            // Errors only affect this connection, usually because the inspector disconnected
+            let _ = handle_connection(&devtools, stream).await;
+        });
+    }
+}
+
+async fn handle_connection(devtools: &Devtools, mut stream: TcpStream) -> io::Result<()> {
+    let path = read_request_path(&mut stream).await?;
+    match path.as_deref() {
+        Some("/subscriptions") => {
+            let body = subscriptions_json(devtools)?;
+            write_head(&mut stream, "200 OK", "application/json", Some(body.len())).await?;
+            stream.write_all(body.as_bytes()).await
+        }
+        Some("/events") => {
+            let mut events = devtools.events();
+            write_head(&mut stream, "200 OK", "text/event-stream", None).await?;
+            while let Some(event) = events.next().await {
+                let event = serde_json::to_string(&event)?;
+                stream
+                    .write_all(format!("data: {}\n\n", event).as_bytes())
+                    .await?;
+            }
+            Ok(())
+        }
+        _ => write_head(&mut stream, "404 Not Found", "text/plain", Some(0)).await
+    }
+}
+
+/// Reads the request head and returns the path if it's a `GET` request.
+async fn read_request_path(stream: &mut TcpStream) -> io::Result<Option<String>> {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_SIZE {
+            return Ok(None);
+        }
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        head.extend_from_slice(&buf[..read]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(target)) => {
+            let path = target.split('?').next().unwrap_or_default();
+            Ok(Some(path.to_string()))
+        }
+        _ => Ok(None)
+    }
+}
+
+async fn write_head(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    content_length: Option<usize>
+) -> io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nCache-Control: no-cache\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n",
+        status, content_type
+    );
+    if let Some(content_length) = content_length {
+        head.push_str(&format!("Content-Length: {}\r\n", content_length));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes()).await
+}
+
+#[cfg(feature = "observable")]
+fn subscriptions_json(devtools: &Devtools) -> io::Result<String> {
+    Ok(serde_json::to_string(&devtools.subscriptions())?)
+}
+
+#[cfg(not(feature = "observable"))]
+fn subscriptions_json(_devtools: &Devtools) -> io::Result<String> {
+    Ok("[]".to_string())
+}
+
+#[cfg(all(test, feature = "observable"))]
+mod test {
+    use crate::{
+        test_utils::{FakeExchange, TestQuery},
+        ClientBuilder
+    };
+    use futures::StreamExt;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream}
+    };
+
+    #[tokio::test]
+    async fn test_subscriptions_route() {
+        let client = ClientBuilder::new("http://localhost:4000/graphql")
+            .with_exchange(FakeExchange)
+            .with_devtools()
+            .build();
+        let mut observable = client.subscribe(TestQuery, ());
+        observable.next().await.unwrap().unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(super::serve_listener(
+            client.devtools().unwrap().clone(),
+            listener
+        ));
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /subscriptions HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains(r#""operationName":"Test""#));
+        assert!(response.contains(r#""latestResult":{"data":1,"debugInfo":null,"errors":null}"#));
+    }
+}
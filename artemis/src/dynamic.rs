@@ -0,0 +1,1115 @@
+//! Operations that are only known at runtime.
+//!
+//! Generated queries carry their metadata and selection tree as static data. Dynamic operations
+//! derive the same information by parsing the document the first time it's used, which lets
+//! them go through the same exchanges as generated ones, including deduplication and both caches.
+//! See [`Client::query_dynamic`](../client/struct.Client.html#method.query_dynamic).
+//!
+//! To tell objects apart in the normalized cache, `__typename` is added to the selection set of
+//! every object field before the document is sent. Results therefore include `__typename` even
+//! where the document didn't select it.
+//!
+//! Without a [`DynamicSchema`](./struct.DynamicSchema.html) the types of fields aren't known in
+//! advance. Dynamic operations then only report the type conditions of their fragments as
+//! involved types, and fragments on interfaces or unions only match objects of that exact type.
+//!
+//! Requires feature: `dynamic`
+
+use crate::{
+    codegen::{FieldSelector, QueryInfo},
+    exchange::OperationType,
+    utils::operation_selection_set_mut,
+    GraphQLQuery, OperationMeta, QueryBody
+};
+use graphql_parser::{
+    query::{
+        Definition, Directive, Document, Field, FragmentDefinition, OperationDefinition, Selection,
+        SelectionSet, TypeCondition, Value as GraphQLValue
+    },
+    schema::{Definition as SchemaDefinition, Type, TypeDefinition, TypeExtension},
+    Pos
+};
+use parking_lot::Mutex;
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use serde_json::Value;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    fmt,
+    num::Wrapping,
+    sync::Arc
+};
+
+const TYPENAME: &str = "__typename";
+
+/// An error that occurred while parsing a dynamic document or schema.
+#[derive(Debug)]
+pub enum DynamicError {
+    /// The document or schema couldn't be parsed.
+    ParseError(String),
+    /// The document doesn't contain exactly one operation. Contains the number of operations.
+    OperationCount(usize),
+    /// The operation of the document has no name.
+    UnnamedOperation,
+    /// A fragment that isn't defined in the document was spread.
+    UnknownFragment(String),
+    /// A fragment spreads itself, directly or through other fragments.
+    FragmentCycle(String)
+}
+
+impl Error for DynamicError {}
+
+impl fmt::Display for DynamicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynamicError::ParseError(e) => write!(f, "failed to parse document: {}", e),
+            DynamicError::OperationCount(count) => write!(
+                f,
+                "dynamic documents must contain exactly one operation, found {}",
+                count
+            ),
+            DynamicError::UnnamedOperation => write!(f, "dynamic operations must be named"),
+            DynamicError::UnknownFragment(name) => write!(f, "unknown fragment {}", name),
+            DynamicError::FragmentCycle(name) => write!(f, "fragment {} spreads itself", name)
+        }
+    }
+}
+
+/// The same hash the code generator uses for query keys.
+fn hash(x: &str) -> u32 {
+    let mut h = Wrapping(5381);
+    for byte in x.as_bytes() {
+        h = (h << 5) + h + Wrapping(*byte as u32)
+    }
+
+    h.0
+}
+
+#[derive(Debug, Default)]
+struct SchemaType {
+    is_abstract: bool,
+    /// Field names mapped to the name of their type
+    fields: HashMap<String, String>,
+    /// The objects that can be returned for an interface or union
+    possible_types: HashSet<String>
+}
+
+/// The composite types of a schema, loaded at runtime.
+/// This lets dynamic operations use the same type information as generated ones.
+///
+/// # Example
+///
+/// ```
+/// # use artemis::{dynamic::DynamicSchema, ClientBuilder};
+/// let schema = DynamicSchema::parse("type Query { me: User } type User { id: ID! }").unwrap();
+/// let client = ClientBuilder::new("http://localhost:8080/graphql")
+///     .with_default_exchanges()
+///     .with_schema(schema)
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct DynamicSchema {
+    query_type: String,
+    mutation_type: String,
+    subscription_type: String,
+    types: HashMap<String, SchemaType>
+}
+
+impl DynamicSchema {
+    /// Parses a schema from its SDL representation.
+    pub fn parse(sdl: &str) -> Result<Self, DynamicError> {
+        let document = graphql_parser::parse_schema(sdl)
+            .map_err(|e| DynamicError::ParseError(e.to_string()))?;
+        let mut schema = DynamicSchema {
+            query_type: "Query".to_string(),
+            mutation_type: "Mutation".to_string(),
+            subscription_type: "Subscription".to_string(),
+            types: HashMap::new()
+        };
+
+        for definition in document.definitions {
+            match definition {
+                SchemaDefinition::SchemaDefinition(definition) => {
+                    if let Some(query) = definition.query {
+                        schema.query_type = query;
+                    }
+                    if let Some(mutation) = definition.mutation {
+                        schema.mutation_type = mutation;
+                    }
+                    if let Some(subscription) = definition.subscription {
+                        schema.subscription_type = subscription;
+                    }
+                }
+                SchemaDefinition::TypeDefinition(TypeDefinition::Object(object)) => {
+                    schema.add_object(object.name, object.implements_interfaces, object.fields)
+                }
+                SchemaDefinition::TypeExtension(TypeExtension::Object(object)) => {
+                    schema.add_object(object.name, object.implements_interfaces, object.fields)
+                }
+                SchemaDefinition::TypeDefinition(TypeDefinition::Interface(interface)) => {
+                    schema.add_abstract(interface.name, interface.fields, Vec::new())
+                }
+                SchemaDefinition::TypeExtension(TypeExtension::Interface(interface)) => {
+                    schema.add_abstract(interface.name, interface.fields, Vec::new())
+                }
+                SchemaDefinition::TypeDefinition(TypeDefinition::Union(union)) => {
+                    schema.add_abstract(union.name, Vec::new(), union.types)
+                }
+                SchemaDefinition::TypeExtension(TypeExtension::Union(union)) => {
+                    schema.add_abstract(union.name, Vec::new(), union.types)
+                }
+                _ => {}
+            }
+        }
+
+        Ok(schema)
+    }
+
+    fn add_object(
+        &mut self,
+        name: String,
+        interfaces: Vec<String>,
+        fields: Vec<graphql_parser::schema::Field>
+    ) {
+        for interface in interfaces {
+            self.types
+                .entry(interface)
+                .or_default()
+                .possible_types
+                .insert(name.clone());
+        }
+        let object = self.types.entry(name).or_default();
+        object.fields.extend(
+            fields
+                .into_iter()
+                .map(|field| (field.name, named_type(field.field_type)))
+        );
+    }
+
+    fn add_abstract(
+        &mut self,
+        name: String,
+        fields: Vec<graphql_parser::schema::Field>,
+        possible_types: Vec<String>
+    ) {
+        let ty = self.types.entry(name).or_default();
+        ty.is_abstract = true;
+        ty.fields.extend(
+            fields
+                .into_iter()
+                .map(|field| (field.name, named_type(field.field_type)))
+        );
+        ty.possible_types.extend(possible_types);
+    }
+
+    fn root_type(&self, operation_type: &OperationType) -> &str {
+        match operation_type {
+            OperationType::Query => &self.query_type,
+            OperationType::Mutation => &self.mutation_type,
+            OperationType::Subscription => &self.subscription_type
+        }
+    }
+
+    /// Returns the type of a field and whether it's abstract, if the type is composite.
+    fn field_type(&self, parent: &str, field_name: &str) -> Option<(&str, bool)> {
+        let type_name = self.types.get(parent)?.fields.get(field_name)?;
+        let ty = self.types.get(type_name)?;
+        Some((type_name, ty.is_abstract))
+    }
+
+    fn is_possible_type(&self, type_condition: &str, typename: &str) -> bool {
+        self.types
+            .get(type_condition)
+            .map(|ty| ty.possible_types.contains(typename))
+            .unwrap_or(false)
+    }
+}
+
+fn named_type(ty: Type) -> String {
+    match ty {
+        Type::NamedType(name) => name,
+        Type::ListType(inner) | Type::NonNullType(inner) => named_type(*inner)
+    }
+}
+
+/// The `@skip` and `@include` directives of a field or fragment.
+#[derive(Debug, Default)]
+struct Conditions {
+    skip: Option<GraphQLValue>,
+    include: Option<GraphQLValue>
+}
+
+impl Conditions {
+    fn new(directives: &[Directive]) -> Self {
+        let argument = |name: &str| {
+            directives
+                .iter()
+                .find(|directive| directive.name == name)
+                .and_then(|directive| {
+                    directive
+                        .arguments
+                        .iter()
+                        .find(|(argument, _)| argument == "if")
+                        .map(|(_, value)| value.clone())
+                })
+        };
+        Conditions {
+            skip: argument("skip"),
+            include: argument("include")
+        }
+    }
+
+    fn is_included(&self, variables: &Value) -> bool {
+        let evaluate = |condition: &Option<GraphQLValue>, default: bool| match condition {
+            Some(GraphQLValue::Boolean(value)) => *value,
+            Some(GraphQLValue::Variable(name)) => variables
+                .get(name)
+                .and_then(Value::as_bool)
+                .unwrap_or(default),
+            _ => default
+        };
+        !evaluate(&self.skip, false) && evaluate(&self.include, true)
+    }
+}
+
+#[derive(Debug)]
+struct FieldNode {
+    name: String,
+    response_key: String,
+    arguments: Vec<(String, GraphQLValue)>,
+    conditions: Conditions,
+    selection: Arc<Vec<Node>>
+}
+
+#[derive(Debug)]
+struct FragmentNode {
+    type_condition: Option<String>,
+    conditions: Conditions,
+    selection: Vec<Node>
+}
+
+/// A selection with fragment spreads replaced by the fragment's selection.
+#[derive(Debug)]
+enum Node {
+    Field(FieldNode),
+    Fragment(FragmentNode)
+}
+
+struct NodeBuilder<'a> {
+    fragments: HashMap<&'a str, &'a FragmentDefinition>,
+    spreading: Vec<&'a str>
+}
+
+impl<'a> NodeBuilder<'a> {
+    fn build(&mut self, selection_set: &'a SelectionSet) -> Result<Vec<Node>, DynamicError> {
+        let mut nodes = Vec::with_capacity(selection_set.items.len());
+        for item in &selection_set.items {
+            let node = match item {
+                Selection::Field(field) => Node::Field(FieldNode {
+                    name: field.name.clone(),
+                    response_key: field.alias.as_ref().unwrap_or(&field.name).clone(),
+                    arguments: field.arguments.clone(),
+                    conditions: Conditions::new(&field.directives),
+                    selection: Arc::new(self.build(&field.selection_set)?)
+                }),
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.fragment_name.as_str();
+                    let fragment = *self
+                        .fragments
+                        .get(name)
+                        .ok_or_else(|| DynamicError::UnknownFragment(name.to_string()))?;
+                    if self.spreading.contains(&name) {
+                        return Err(DynamicError::FragmentCycle(name.to_string()));
+                    }
+                    self.spreading.push(name);
+                    let selection = self.build(&fragment.selection_set)?;
+                    self.spreading.pop();
+                    let TypeCondition::On(type_condition) = &fragment.type_condition;
+                    Node::Fragment(FragmentNode {
+                        type_condition: Some(type_condition.clone()),
+                        conditions: Conditions::new(&spread.directives),
+                        selection
+                    })
+                }
+                Selection::InlineFragment(fragment) => Node::Fragment(FragmentNode {
+                    type_condition: fragment
+                        .type_condition
+                        .as_ref()
+                        .map(|TypeCondition::On(type_condition)| type_condition.clone()),
+                    conditions: Conditions::new(&fragment.directives),
+                    selection: self.build(&fragment.selection_set)?
+                })
+            };
+            nodes.push(node);
+        }
+        Ok(nodes)
+    }
+}
+
+fn typename_field() -> Selection {
+    Selection::Field(Field {
+        position: Pos::default(),
+        alias: None,
+        name: TYPENAME.to_string(),
+        arguments: Vec::new(),
+        directives: Vec::new(),
+        selection_set: SelectionSet {
+            span: (Pos::default(), Pos::default()),
+            items: Vec::new()
+        }
+    })
+}
+
+/// Adds `__typename` to the selection sets of all object fields in the selection set.
+fn add_typenames(selection_set: &mut SelectionSet) {
+    for item in &mut selection_set.items {
+        match item {
+            Selection::Field(field) if !field.selection_set.items.is_empty() => {
+                add_typenames(&mut field.selection_set);
+                let has_typename = field.selection_set.items.iter().any(|item| {
+                    matches!(item, Selection::Field(field) if field.name == TYPENAME && field.alias.is_none())
+                });
+                if !has_typename {
+                    field.selection_set.items.insert(0, typename_field());
+                }
+            }
+            Selection::InlineFragment(fragment) => add_typenames(&mut fragment.selection_set),
+            _ => {}
+        }
+    }
+}
+
+fn format_value(value: &GraphQLValue, variables: &Value) -> String {
+    match value {
+        GraphQLValue::Variable(name) => variables.get(name).unwrap_or(&Value::Null).to_string(),
+        GraphQLValue::Int(number) => number.as_i64().unwrap_or_default().to_string(),
+        GraphQLValue::Float(number) => number.to_string(),
+        GraphQLValue::String(string) | GraphQLValue::Enum(string) => string.clone(),
+        GraphQLValue::Boolean(boolean) => boolean.to_string(),
+        GraphQLValue::Null => "null".to_string(),
+        GraphQLValue::List(values) => {
+            let entries: Vec<String> = values
+                .iter()
+                .map(|value| format_value(value, variables))
+                .collect();
+            format!("[{}]", entries.join(","))
+        }
+        GraphQLValue::Object(fields) => {
+            let entries: Vec<String> = fields
+                .iter()
+                .map(|(name, value)| format!("{}:{}", name, format_value(value, variables)))
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        }
+    }
+}
+
+/// Formats arguments the same way generated queries do, so they share cache entries.
+fn format_arguments(arguments: &[(String, GraphQLValue)], variables: &Value) -> String {
+    if arguments.is_empty() {
+        return String::new();
+    }
+    let fields: Vec<String> = arguments
+        .iter()
+        .map(|(name, value)| format!("{}:{}", name, format_value(value, variables)))
+        .collect();
+    format!("({})", fields.join(","))
+}
+
+fn type_condition_matches(
+    type_condition: &Option<String>,
+    typename: &str,
+    schema: Option<&DynamicSchema>
+) -> bool {
+    match type_condition {
+        Some(type_condition) => {
+            type_condition == typename
+                || schema
+                    .map(|schema| schema.is_possible_type(type_condition, typename))
+                    .unwrap_or(false)
+        }
+        None => true
+    }
+}
+
+/// Collects the fields that apply to an object of type `typename`, grouped by response key.
+fn collect_fields<'a>(
+    nodes: &'a [Node],
+    typename: &str,
+    schema: Option<&DynamicSchema>,
+    variables: &Value,
+    fields: &mut BTreeMap<&'a str, Vec<&'a FieldNode>>
+) {
+    for node in nodes {
+        match node {
+            Node::Field(field) if field.conditions.is_included(variables) => fields
+                .entry(field.response_key.as_str())
+                .or_default()
+                .push(field),
+            Node::Fragment(fragment)
+                if fragment.conditions.is_included(variables)
+                    && type_condition_matches(&fragment.type_condition, typename, schema) =>
+            {
+                collect_fields(&fragment.selection, typename, schema, variables, fields)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Orders fields the same way `DynamicData` serializes them, `__typename` first and the
+/// rest sorted by response key.
+fn ordered_fields<'a>(
+    mut fields: BTreeMap<&'a str, Vec<&'a FieldNode>>
+) -> impl Iterator<Item = (&'a str, Vec<&'a FieldNode>)> {
+    let typename = fields.remove(TYPENAME).map(|nodes| (TYPENAME, nodes));
+    typename.into_iter().chain(fields)
+}
+
+fn selection_for(
+    selections: &[Arc<Vec<Node>>],
+    typename: &str,
+    schema: &Option<Arc<DynamicSchema>>,
+    variables: &Arc<Value>
+) -> Vec<FieldSelector> {
+    let mut fields = BTreeMap::new();
+    for nodes in selections {
+        collect_fields(nodes, typename, schema.as_deref(), variables, &mut fields);
+    }
+
+    ordered_fields(fields)
+        .map(|(_, nodes)| {
+            let field = nodes[0];
+            let arguments = format_arguments(&field.arguments, variables);
+            let children: Vec<_> = nodes
+                .iter()
+                .filter(|node| !node.selection.is_empty())
+                .map(|node| node.selection.clone())
+                .collect();
+            let name = Cow::Owned(field.name.clone());
+            if children.is_empty() {
+                return FieldSelector::Scalar(name, arguments);
+            }
+
+            let field_type = schema
+                .as_deref()
+                .and_then(|schema| schema.field_type(typename, &field.name));
+            match field_type {
+                Some((type_name, false)) => {
+                    let selection = selection_for(&children, type_name, schema, variables);
+                    FieldSelector::Object(name, arguments, type_name.to_string().into(), selection)
+                }
+                _ => {
+                    let schema = schema.clone();
+                    let variables = variables.clone();
+                    FieldSelector::Union(
+                        name,
+                        arguments,
+                        Arc::new(move |typename| {
+                            selection_for(&children, typename, &schema, &variables)
+                        })
+                    )
+                }
+            }
+        })
+        .collect()
+}
+
+/// Renames fields that were read from a normalized cache by their name back to their alias.
+fn restore_aliases(
+    selections: &[&Vec<Node>],
+    typename: &str,
+    schema: Option<&DynamicSchema>,
+    variables: &Value,
+    value: &mut Value
+) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                restore_aliases(selections, typename, schema, variables, value);
+            }
+        }
+        Value::Object(object) => {
+            let typename = object
+                .get(TYPENAME)
+                .and_then(Value::as_str)
+                .unwrap_or(typename)
+                .to_string();
+            let mut fields = BTreeMap::new();
+            for nodes in selections {
+                collect_fields(nodes, &typename, schema, variables, &mut fields);
+            }
+
+            for (response_key, nodes) in &fields {
+                let name = nodes[0].name.as_str();
+                if *response_key != name && !object.contains_key(*response_key) {
+                    if let Some(value) = object.get(name).cloned() {
+                        object.insert(response_key.to_string(), value);
+                    }
+                }
+            }
+            let unselected: Vec<String> = object
+                .keys()
+                .filter(|key| !fields.contains_key(key.as_str()))
+                .cloned()
+                .collect();
+            for key in unselected {
+                object.remove(&key);
+            }
+
+            for (response_key, nodes) in &fields {
+                let children: Vec<&Vec<Node>> = nodes
+                    .iter()
+                    .filter(|node| !node.selection.is_empty())
+                    .map(|node| &*node.selection)
+                    .collect();
+                if children.is_empty() {
+                    continue;
+                }
+                if let Some(value) = object.get_mut(*response_key) {
+                    restore_aliases(&children, "", schema, variables, value);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn push_type(types: &mut Vec<Cow<'static, str>>, type_name: &str) {
+    if !types.iter().any(|ty| ty == type_name) {
+        types.push(Cow::Owned(type_name.to_string()));
+    }
+}
+
+fn involved_types(
+    nodes: &[Node],
+    parent: Option<&str>,
+    schema: Option<&DynamicSchema>,
+    types: &mut Vec<Cow<'static, str>>
+) {
+    for node in nodes {
+        match node {
+            Node::Field(field) if !field.selection.is_empty() => {
+                let field_type =
+                    parent.and_then(|parent| Some(schema?.field_type(parent, &field.name)?.0));
+                if let Some(field_type) = field_type {
+                    push_type(types, field_type);
+                }
+                involved_types(&field.selection, field_type, schema, types);
+            }
+            Node::Fragment(fragment) => {
+                if let Some(type_condition) = &fragment.type_condition {
+                    push_type(types, type_condition);
+                }
+                let parent = fragment.type_condition.as_deref().or(parent);
+                involved_types(&fragment.selection, parent, schema, types);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A parsed dynamic operation, with the metadata a generated query would have.
+#[derive(Debug)]
+pub struct DynamicDocument {
+    query: String,
+    operation_name: String,
+    meta: OperationMeta,
+    root_type: String,
+    selection: Arc<Vec<Node>>,
+    has_aliases: bool,
+    schema: Option<Arc<DynamicSchema>>
+}
+
+impl DynamicDocument {
+    /// Parses a document containing a single named operation and any fragments it uses.
+    /// If a schema is passed, it's used to determine the types of fields.
+    pub fn parse(document: &str, schema: Option<Arc<DynamicSchema>>) -> Result<Self, DynamicError> {
+        let mut document: Document = graphql_parser::parse_query(document)
+            .map_err(|e| DynamicError::ParseError(e.to_string()))?;
+
+        for definition in &mut document.definitions {
+            match definition {
+                Definition::Operation(operation) => {
+                    add_typenames(operation_selection_set_mut(operation))
+                }
+                Definition::Fragment(fragment) => add_typenames(&mut fragment.selection_set)
+            }
+        }
+
+        let operations: Vec<&OperationDefinition> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                Definition::Operation(operation) => Some(operation),
+                Definition::Fragment(_) => None
+            })
+            .collect();
+        let operation = match operations.as_slice() {
+            [operation] => *operation,
+            _ => return Err(DynamicError::OperationCount(operations.len()))
+        };
+        let (operation_type, operation_name, selection_set) = match operation {
+            OperationDefinition::Query(query) => {
+                (OperationType::Query, &query.name, &query.selection_set)
+            }
+            OperationDefinition::Mutation(mutation) => (
+                OperationType::Mutation,
+                &mutation.name,
+                &mutation.selection_set
+            ),
+            OperationDefinition::Subscription(subscription) => (
+                OperationType::Subscription,
+                &subscription.name,
+                &subscription.selection_set
+            ),
+            OperationDefinition::SelectionSet(_) => return Err(DynamicError::UnnamedOperation)
+        };
+        let operation_name = operation_name
+            .as_deref()
+            .ok_or(DynamicError::UnnamedOperation)?;
+
+        let mut builder = NodeBuilder {
+            fragments: document
+                .definitions
+                .iter()
+                .filter_map(|definition| match definition {
+                    Definition::Fragment(fragment) => Some((fragment.name.as_str(), fragment)),
+                    Definition::Operation(_) => None
+                })
+                .collect(),
+            spreading: Vec::new()
+        };
+        let selection = builder.build(selection_set)?;
+
+        let root_type = match &schema {
+            Some(schema) => schema.root_type(&operation_type),
+            None => operation_type.to_str()
+        }
+        .to_string();
+        let mut types = Vec::new();
+        involved_types(&selection, Some(&root_type), schema.as_deref(), &mut types);
+
+        let query = document.to_string();
+        let meta = OperationMeta {
+            query_key: hash(&query),
+            operation_type,
+            involved_types: types
+        };
+
+        Ok(DynamicDocument {
+            query,
+            operation_name: operation_name.to_string(),
+            meta,
+            root_type,
+            has_aliases: selection_has_aliases(&selection),
+            selection: Arc::new(selection),
+            schema
+        })
+    }
+
+    /// The document as it's sent to the server, including the added `__typename` fields.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// The name of the operation.
+    pub fn operation_name(&self) -> &str {
+        &self.operation_name
+    }
+
+    /// The metadata of the operation.
+    pub fn meta(&self) -> &OperationMeta {
+        &self.meta
+    }
+
+    /// Creates the selection of the operation for the given set of variables.
+    pub fn selection(&self, variables: &Value) -> Vec<FieldSelector> {
+        selection_for(
+            std::slice::from_ref(&self.selection),
+            &self.root_type,
+            &self.schema,
+            &Arc::new(variables.clone())
+        )
+    }
+
+    /// Normalized caches store fields by their name, so aliases have to be restored when
+    /// a result is read from them.
+    pub(crate) fn restore_aliases(&self, data: &mut Value, variables: &Value) {
+        if self.has_aliases {
+            restore_aliases(
+                &[&self.selection],
+                &self.root_type,
+                self.schema.as_deref(),
+                variables,
+                data
+            );
+        }
+    }
+}
+
+fn selection_has_aliases(nodes: &[Node]) -> bool {
+    nodes.iter().any(|node| match node {
+        Node::Field(field) => {
+            field.name != field.response_key || selection_has_aliases(&field.selection)
+        }
+        Node::Fragment(fragment) => selection_has_aliases(&fragment.selection)
+    })
+}
+
+/// The number of parsed documents a client keeps. When it's reached, the least recently used
+/// document is evicted, so clients running arbitrary documents don't grow without bound.
+const DOCUMENT_CACHE_SIZE: usize = 128;
+
+/// Parsed documents, so each one is only parsed once per client while it's in use.
+pub(crate) struct DocumentCache {
+    schema: Option<Arc<DynamicSchema>>,
+    documents: Mutex<CachedDocuments>
+}
+
+#[derive(Default)]
+struct CachedDocuments {
+    /// Parsed documents by their source, along with the tick they were last used at
+    documents: HashMap<String, (Arc<DynamicDocument>, u64)>,
+    tick: u64
+}
+
+impl DocumentCache {
+    pub(crate) fn new(schema: Option<Arc<DynamicSchema>>) -> Self {
+        DocumentCache {
+            schema,
+            documents: Mutex::new(CachedDocuments::default())
+        }
+    }
+
+    pub(crate) fn get(&self, document: &str) -> Result<Arc<DynamicDocument>, DynamicError> {
+        {
+            let mut cache = self.documents.lock();
+            cache.tick += 1;
+            let tick = cache.tick;
+            if let Some((parsed, last_used)) = cache.documents.get_mut(document) {
+                *last_used = tick;
+                return Ok(parsed.clone());
+            }
+        }
+
+        let parsed = Arc::new(DynamicDocument::parse(document, self.schema.clone())?);
+        let mut cache = self.documents.lock();
+        if cache.documents.len() >= DOCUMENT_CACHE_SIZE {
+            let least_recently_used = cache
+                .documents
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(source, _)| source.clone());
+            if let Some(source) = least_recently_used {
+                cache.documents.remove(&source);
+            }
+        }
+        let tick = cache.tick;
+        cache
+            .documents
+            .insert(document.to_string(), (parsed.clone(), tick));
+        Ok(parsed)
+    }
+}
+
+/// The variables of a dynamic operation. Only the values are sent to the server,
+/// the document is carried along so the selection can be built from it.
+#[derive(Clone, Debug)]
+pub struct DynamicVariables {
+    document: Arc<DynamicDocument>,
+    values: Value
+}
+
+impl DynamicVariables {
+    /// Creates the variables for an operation from their JSON values.
+    pub fn new(document: Arc<DynamicDocument>, values: Value) -> Self {
+        DynamicVariables { document, values }
+    }
+
+    /// The document the variables belong to.
+    pub fn document(&self) -> &Arc<DynamicDocument> {
+        &self.document
+    }
+
+    /// The JSON values of the variables.
+    pub fn values(&self) -> &Value {
+        &self.values
+    }
+}
+
+impl Serialize for DynamicVariables {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.values.serialize(serializer)
+    }
+}
+
+/// The response data of a dynamic operation.
+///
+/// Objects are serialized as maps with their fields in the same order as the selection of
+/// the operation, which is what normalized caches expect.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct DynamicData(pub Value);
+
+impl Serialize for DynamicData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        OrderedValue(&self.0).serialize(serializer)
+    }
+}
+
+struct OrderedValue<'a>(&'a Value);
+
+impl<'a> Serialize for OrderedValue<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Null => serializer.serialize_none(),
+            Value::Array(values) => serializer.collect_seq(values.iter().map(OrderedValue)),
+            Value::Object(object) => {
+                let mut keys: Vec<&String> = object.keys().collect();
+                keys.sort_by_key(|key| (key.as_str() != TYPENAME, key.as_str()));
+                let mut fields = serializer.serialize_map(Some(keys.len()))?;
+                for key in keys {
+                    fields.serialize_entry(key, &OrderedValue(&object[key]))?;
+                }
+                fields.end()
+            }
+            value => value.serialize(serializer)
+        }
+    }
+}
+
+impl QueryInfo<DynamicVariables> for DynamicData {
+    fn selection(variables: &DynamicVariables) -> Vec<FieldSelector> {
+        variables.document.selection(&variables.values)
+    }
+}
+
+/// The query type used to run dynamic operations through the exchanges.
+pub struct DynamicQuery;
+
+impl GraphQLQuery for DynamicQuery {
+    type Variables = DynamicVariables;
+    type ResponseData = DynamicData;
+
+    fn build_query(variables: Self::Variables) -> (QueryBody<Self::Variables>, OperationMeta) {
+        let meta = variables.document.meta.clone();
+        let body = QueryBody {
+            query: Cow::Owned(variables.document.query.clone()),
+            operation_name: Cow::Owned(variables.document.operation_name.clone()),
+            variables
+        };
+        (body, meta)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    const SCHEMA: &str = r#"
+        type Query { conference(id: ID!): Conference, node(id: ID!): Node }
+        interface Node { id: ID! }
+        type Conference implements Node { id: ID!, name: String!, talks: [Talk!] }
+        type Talk implements Node { id: ID!, title: String! }
+    "#;
+
+    const QUERY: &str = r#"
+        query GetConference($id: ID!, $withTalks: Boolean!) {
+            conference(id: $id) {
+                id
+                title: name
+                talks @include(if: $withTalks) { ...TalkFields }
+            }
+        }
+        fragment TalkFields on Talk { id title }
+    "#;
+
+    fn field_names(selection: &[FieldSelector]) -> Vec<&str> {
+        selection
+            .iter()
+            .map(|selector| match selector {
+                FieldSelector::Scalar(name, _)
+                | FieldSelector::Object(name, _, _, _)
+                | FieldSelector::Union(name, _, _) => name.as_ref()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_document() {
+        let document = DynamicDocument::parse(QUERY, None).unwrap();
+
+        assert_eq!(document.operation_name(), "GetConference");
+        assert_eq!(document.meta().operation_type, OperationType::Query);
+        assert_eq!(document.meta().involved_types, vec!["Talk"]);
+        assert_eq!(document.meta().query_key, hash(document.query()));
+        assert_eq!(document.query().matches(TYPENAME).count(), 2);
+
+        let unnamed = DynamicDocument::parse("{ conference(id: 1) { id } }", None);
+        assert!(matches!(unnamed, Err(DynamicError::UnnamedOperation)));
+        let unknown = DynamicDocument::parse("query A { node(id: 1) { ...B } }", None);
+        assert!(matches!(unknown, Err(DynamicError::UnknownFragment(_))));
+    }
+
+    #[test]
+    fn test_selection_without_schema() {
+        let document = DynamicDocument::parse(QUERY, None).unwrap();
+        let selection = document.selection(&json!({ "id": "1", "withTalks": true }));
+
+        let (args, conference) = match &selection[..] {
+            [FieldSelector::Union(name, args, conference)] if name == "conference" => {
+                (args, conference)
+            }
+            selection => panic!("unexpected selection {:?}", selection)
+        };
+        assert_eq!(args, r#"(id:"1")"#);
+        let conference = conference("Conference");
+        assert_eq!(
+            field_names(&conference),
+            vec![TYPENAME, "id", "talks", "name"]
+        );
+        match &conference[2] {
+            FieldSelector::Union(_, _, talk) => {
+                assert_eq!(field_names(&talk("Talk")), vec![TYPENAME, "id", "title"]);
+                assert_eq!(field_names(&talk("Other")), vec![TYPENAME]);
+            }
+            selector => panic!("unexpected selector {:?}", selector)
+        }
+
+        let selection = document.selection(&json!({ "id": "1", "withTalks": false }));
+        match &selection[0] {
+            FieldSelector::Union(_, _, conference) => assert_eq!(
+                field_names(&conference("Conference")),
+                vec![TYPENAME, "id", "name"]
+            ),
+            selector => panic!("unexpected selector {:?}", selector)
+        }
+    }
+
+    #[test]
+    fn test_selection_with_schema() {
+        let schema = Arc::new(DynamicSchema::parse(SCHEMA).unwrap());
+        let document = DynamicDocument::parse(QUERY, Some(schema.clone())).unwrap();
+        assert_eq!(document.meta().involved_types, vec!["Conference", "Talk"]);
+
+        let selection = document.selection(&json!({ "id": "1", "withTalks": true }));
+        match &selection[0] {
+            FieldSelector::Object(name, _, typename, fields)
+                if name == "conference" && typename == "Conference" =>
+            {
+                assert_eq!(field_names(fields), vec![TYPENAME, "id", "talks", "name"]);
+                assert!(
+                    matches!(&fields[2], FieldSelector::Object(_, _, typename, _) if typename == "Talk")
+                );
+            }
+            selector => panic!("unexpected selector {:?}", selector)
+        }
+
+        let document = DynamicDocument::parse(
+            "query GetNode { node(id: 1) { id ... on Talk { title } } }",
+            Some(schema)
+        )
+        .unwrap();
+        match &document.selection(&Value::Null)[0] {
+            FieldSelector::Union(name, args, node) if name == "node" => {
+                assert_eq!(args, "(id:1)");
+                assert_eq!(field_names(&node("Talk")), vec![TYPENAME, "id", "title"]);
+                assert_eq!(field_names(&node("Conference")), vec![TYPENAME, "id"]);
+            }
+            selector => panic!("unexpected selector {:?}", selector)
+        }
+    }
+
+    #[test]
+    fn test_serialize_in_selection_order() {
+        let data = DynamicData(json!({
+            "conference": { "title": "Test", "id": "1", "__typename": "Conference" }
+        }));
+        assert_eq!(serde_json::to_value(&data).unwrap(), data.0);
+        assert_eq!(
+            serde_json::to_string(&data).unwrap(),
+            r#"{"conference":{"__typename":"Conference","id":"1","title":"Test"}}"#
+        );
+    }
+
+    #[test]
+    fn test_restore_aliases() {
+        let document = DynamicDocument::parse(QUERY, None).unwrap();
+        let variables = json!({ "id": "1", "withTalks": false });
+        let mut data = json!({
+            "conference": { "__typename": "Conference", "id": "1", "name": "Test" }
+        });
+        document.restore_aliases(&mut data, &variables);
+
+        assert_eq!(
+            data,
+            json!({ "conference": { "__typename": "Conference", "id": "1", "title": "Test" } })
+        );
+    }
+
+    #[test]
+    fn test_document_cache_is_bounded() {
+        let cache = DocumentCache::new(None);
+        let source = |i: usize| format!("query Q{} {{ value }}", i);
+
+        let first = cache.get(&source(0)).unwrap();
+        for i in 1..DOCUMENT_CACHE_SIZE {
+            cache.get(&source(i)).unwrap();
+        }
+        // Using the first document again makes the second one the least recently used
+        assert!(Arc::ptr_eq(&first, &cache.get(&source(0)).unwrap()));
+        cache.get(&source(DOCUMENT_CACHE_SIZE)).unwrap();
+
+        let documents = &cache.documents.lock().documents;
+        assert_eq!(documents.len(), DOCUMENT_CACHE_SIZE);
+        assert!(documents.contains_key(&source(0)));
+        assert!(!documents.contains_key(&source(1)));
+    }
+
+    #[cfg(feature = "default-exchanges")]
+    #[tokio::test]
+    async fn test_query_dynamic() {
+        use crate::{
+            default_exchanges::CacheExchange,
+            test_utils::{data_response, TestExchange, TEST_URL},
+            ClientBuilder, ResultSource
+        };
+
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let recorded = queries.clone();
+        let client = ClientBuilder::new(TEST_URL)
+            .with_exchange(TestExchange::new(move |request| {
+                recorded
+                    .lock()
+                    .push(request.operation.query.query.to_string());
+                async {
+                    Ok(data_response(json!({
+                        "conference": { "__typename": "Conference", "id": "1", "title": "Test" }
+                    })))
+                }
+            }))
+            .with_exchange(CacheExchange)
+            .build();
+        let variables = json!({ "id": "1", "withTalks": false });
+
+        let result = client
+            .query_dynamic(QUERY, variables.clone())
+            .await
+            .unwrap();
+        let expected = json!({
+            "conference": { "__typename": "Conference", "id": "1", "title": "Test" }
+        });
+        assert_eq!(result.data, Some(expected.clone()));
+
+        let result = client.query_dynamic(QUERY, variables).await.unwrap();
+        assert_eq!(result.data, Some(expected));
+        assert_eq!(result.debug_info.unwrap().source, ResultSource::Cache);
+
+        let queries = queries.lock();
+        assert_eq!(queries.len(), 1);
+        assert!(queries[0].contains(TYPENAME));
+    }
+}
@@ -36,6 +36,11 @@ impl QueryError {
         self.inner.source()
     }
 
+    /// Returns a reference to the wrapped error if it's of type `E`.
+    pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> {
+        self.inner.downcast_ref::<E>()
+    }
+
     /// Gets a compatibility wrapper that implements `std::error::Error`. This is necessary until specialization lands.
     pub fn compat(self) -> QueryErrorCompat {
         QueryErrorCompat(self)
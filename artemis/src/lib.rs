@@ -53,6 +53,10 @@
 //!
 //! For details on how to use the query builder, see [artemis-build](../artemis_build/index.html)
 //!
+//! Alternatively, single queries can be generated without a build script by deriving
+//! [GraphQLQuery](./derive.GraphQLQuery.html) on the query struct, or written inline with the
+//! [graphql! macro](./macro.graphql!.html).
+//!
 //! # Exchanges
 //!
 //! Exchanges are like a bi-directional middleware.
@@ -69,6 +73,8 @@
 //! waiting for their results instead. This reduces network traffic,
 //! especially in larger applications where the same query may be used in multiple
 //! places and run multiple times simultaneously as a result.
+//! Queries are only combined if they use the same URL and request policy, and mutations are only
+//! combined if they opt in with a `DedupExtension`.
 //!
 //! ## CacheExchange
 //!
@@ -103,8 +109,17 @@
 //! # Features
 //!
 //! * `default-exchanges` **(default)** - Include default exchanges and the related builder method
-//! * `observable` **(default)** - Include support for observable and all related types. Includes
-//! `tokio` on x86.
+//! * `observable` **(default)** - Include support for observable and all related types.
+//! * `tokio` **(default)** - Include `TokioSpawner` and use it as the default spawner on native targets.
+//! * `async-std` - Include `AsyncStdSpawner`. This is the default spawner if `tokio` is disabled.
+//! * `smol` - Include `SmolSpawner`. This is the default spawner if `tokio` and `async-std` are disabled.
+//! * `blocking` - Include the blocking client in the `blocking` module. Implies `tokio`.
+//! * `devtools-server` - Include the devtools HTTP server in `devtools::server`. Implies `tokio`.
+//! * `scheduling` - Include the `SchedulingExchange` for concurrency limits, priorities and rate limiting.
+//! * `circuit-breaker` - Include the `CircuitBreakerExchange`. Implies `default-exchanges`.
+//! * `local-state` - Include the `LocalStateExchange` for resolving `@client` fields locally.
+//! * `dynamic` - Include `Client::query_dynamic` for running documents that are only known at runtime.
+//! * `network-probe` - Include `ClientBuilder::with_network_probe` for checking whether the server is reachable.
 
 //#![warn(missing_docs)]
 //#![deny(warnings)]
@@ -114,24 +129,35 @@ extern crate serde;
 #[macro_use]
 extern crate async_trait;
 
-use std::{collections::HashMap, fmt, fmt::Display};
+use std::{borrow::Cow, collections::HashMap, fmt, fmt::Display};
 use types::*;
 
+pub mod activity;
+#[cfg(all(not(target_arch = "wasm32"), feature = "blocking"))]
+pub mod blocking;
 pub mod client;
 pub mod default_exchanges;
+pub mod devtools;
+#[cfg(feature = "dynamic")]
+pub mod dynamic;
 mod error;
+mod partial;
+pub mod spawner;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test_utils;
 pub(crate) mod types;
 pub mod utils;
 
 #[doc(inline)]
-pub use artemis_codegen_proc_macro::wasm_client;
+pub use artemis_codegen_proc_macro::{graphql, wasm_client, GraphQLQuery};
 pub use client::{Client, ClientBuilder};
 pub use error::QueryError;
 use serde::{de::DeserializeOwned, Serialize};
 #[cfg(feature = "observable")]
-pub use types::Observable;
+pub use types::{Observable, ObservableError, OverflowStrategy};
 pub use types::{
-    DebugInfo, ExtensionMap, Extensions, HeaderPair, QueryOptions, RequestPolicy, ResultSource
+    BuilderError, DebugInfo, ExtensionMap, Extensions, HeaderPair, MaybeUndefined, QueryOptions,
+    RefetchQuery, RequestPolicy, ResetOptions, ResultSource
 };
 #[cfg(target_arch = "wasm32")]
 pub use utils::wasm;
@@ -147,7 +173,7 @@ pub mod exchange {
 /// Types used only by the code generator. Exchanges may use these, but they shouldn't
 /// be created/implemented manually.
 pub mod codegen {
-    pub use crate::types::{FieldSelector, QueryInfo};
+    pub use crate::types::{deserialize_some, FieldSelector, QueryInfo};
 }
 
 /// The form in which queries are sent over HTTP in most implementations. This will be built using the [GraphQLQuery](./trait.GraphQLQuery.html) trait normally.
@@ -156,10 +182,10 @@ pub struct QueryBody<Variables: Serialize + Send + Sync + Clone> {
     /// The values for the variables. They must match those declared in the queries. This should be the `Variables` struct from the generated module corresponding to the query.
     pub variables: Variables,
     /// The GraphQL query, as a string.
-    pub query: &'static str,
+    pub query: Cow<'static, str>,
     /// The GraphQL operation name, as a string.
     #[serde(rename = "operationName")]
-    pub operation_name: &'static str
+    pub operation_name: Cow<'static, str>
 }
 
 /// A convenience trait that can be used to build a GraphQL request body.
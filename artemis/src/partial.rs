@@ -0,0 +1,516 @@
+//! Deserialization of partial data.
+//!
+//! When a non-nullable field is `null`, which servers do if resolving it failed, the spec requires
+//! the `null` to propagate to the nearest nullable ancestor. The generated types can't represent
+//! the `null` itself, so the data is deserialized with a deserializer that tracks the path and
+//! which values are nullable (`Option`s). If a `null` is rejected, the nearest nullable ancestor
+//! is set to `null` and deserialization is retried, until the data fits or `data` itself is `null`.
+//! An error is added for each violation the server didn't already report.
+//!
+//! `null`s that are buffered by serde before being deserialized, such as those inside unions and
+//! interfaces, are propagated from the buffered value instead, since the exact field isn't known.
+//!
+//! Complete data, which is the common case, is deserialized directly without any of this.
+
+use crate::{Error, PathFragment, Response};
+use serde::{
+    de::{
+        self, value::BorrowedStrDeserializer, DeserializeOwned, DeserializeSeed, MapAccess,
+        SeqAccess, Visitor
+    },
+    forward_to_deserialize_any, Deserialize, Deserializer
+};
+use serde_json::Value;
+use std::cell::RefCell;
+
+const NULL_MESSAGE: &str = "Cannot return null for non-nullable field";
+
+struct Violation {
+    /// The path of the non-nullable field
+    path: Vec<PathFragment>,
+    /// The length of the path of the nearest nullable ancestor, or `None` if there isn't one
+    nullable_ancestor: Option<usize>
+}
+
+struct State {
+    path: Vec<PathFragment>,
+    /// Whether the value at each depth was deserialized as an `Option`, starting with `data`
+    nullable: Vec<bool>,
+    /// Whether a `null` was buffered by serde inside the value at each depth, starting with `data`
+    buffered_null: Vec<bool>,
+    violation: Option<Violation>
+}
+
+impl State {
+    fn new() -> Self {
+        Self {
+            path: Vec::new(),
+            nullable: vec![false],
+            buffered_null: vec![false],
+            violation: None
+        }
+    }
+
+    fn enter(&mut self, fragment: PathFragment) {
+        self.path.push(fragment);
+        self.nullable.push(false);
+        self.buffered_null.push(false);
+    }
+
+    /// Leaves the current value. Errors in a value with buffered `null`s are caused by them, so
+    /// they're recorded as violations of the value.
+    fn exit_value<T>(&mut self, result: &Result<T, serde_json::Error>) {
+        if result.is_err() && self.has_buffered_null() {
+            self.violate();
+        }
+        self.path.pop();
+        self.nullable.pop();
+        let buffered_null = self.buffered_null.pop().unwrap();
+        *self.buffered_null.last_mut().unwrap() |= buffered_null;
+    }
+
+    fn has_buffered_null(&self) -> bool {
+        *self.buffered_null.last().unwrap()
+    }
+
+    /// Records a violation at the current path. Only the innermost one is kept.
+    fn violate(&mut self) {
+        if self.violation.is_none() {
+            let nullable_ancestor = (1..=self.path.len())
+                .rev()
+                .find(|depth| self.nullable[*depth]);
+            self.violation = Some(Violation {
+                path: self.path.clone(),
+                nullable_ancestor
+            });
+        }
+    }
+}
+
+struct ValueDeserializer<'de> {
+    value: &'de Value,
+    state: &'de RefCell<State>
+}
+
+impl<'de> ValueDeserializer<'de> {
+    fn new(value: &'de Value, state: &'de RefCell<State>) -> Self {
+        Self { value, state }
+    }
+
+    /// Rejects a `null` in a non-nullable value. The violation is recorded in the state, which is
+    /// how it's told apart from other errors.
+    fn null_violation<T>(&self) -> Result<T, serde_json::Error> {
+        self.state.borrow_mut().violate();
+        Err(de::Error::custom(NULL_MESSAGE))
+    }
+
+    /// Visits objects and arrays with access that tracks the path.
+    fn visit_nested<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, serde_json::Error> {
+        match self.value {
+            Value::Object(map) => visitor.visit_map(ObjectAccess {
+                iter: map.iter(),
+                value: None,
+                state: self.state
+            }),
+            Value::Array(items) => visitor.visit_seq(ArrayAccess {
+                iter: items.iter().enumerate(),
+                state: self.state
+            }),
+            value => value.deserialize_any(visitor)
+        }
+    }
+}
+
+macro_rules! deserialize_non_null {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.value {
+                    Value::Null => self.null_violation(),
+                    Value::Object(_) | Value::Array(_) => self.visit_nested(visitor),
+                    value => value.$method(visitor)
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => {
+                // Only self-describing values are deserialized like this, which is how serde
+                // buffers them
+                *self.state.borrow_mut().buffered_null.last_mut().unwrap() = true;
+                visitor.visit_unit()
+            }
+            _ => self.visit_nested(visitor)
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => {
+                *self.state.borrow_mut().nullable.last_mut().unwrap() = true;
+                visitor.visit_some(self)
+            }
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => self.null_violation(),
+            value => value.deserialize_enum(name, variants, visitor)
+        }
+    }
+
+    deserialize_non_null! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string deserialize_bytes
+        deserialize_byte_buf deserialize_seq deserialize_map deserialize_identifier
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 unit unit_struct tuple tuple_struct
+    }
+}
+
+struct ObjectAccess<'de> {
+    iter: serde_json::map::Iter<'de>,
+    value: Option<(&'de String, &'de Value)>,
+    state: &'de RefCell<State>
+}
+
+impl<'de> MapAccess<'de> for ObjectAccess<'de> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some((key, value));
+                seed.deserialize(BorrowedStrDeserializer::new(key))
+                    .map(Some)
+            }
+            None => Ok(None)
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V
+    ) -> Result<V::Value, Self::Error> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value is missing"))?;
+        self.state
+            .borrow_mut()
+            .enter(PathFragment::Key(key.clone()));
+        let result = seed.deserialize(ValueDeserializer::new(value, self.state));
+        self.state.borrow_mut().exit_value(&result);
+        result
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct ArrayAccess<'de> {
+    iter: std::iter::Enumerate<std::slice::Iter<'de, Value>>,
+    state: &'de RefCell<State>
+}
+
+impl<'de> SeqAccess<'de> for ArrayAccess<'de> {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((index, value)) => {
+                self.state
+                    .borrow_mut()
+                    .enter(PathFragment::Index(index as i32));
+                let result = seed.deserialize(ValueDeserializer::new(value, self.state));
+                self.state.borrow_mut().exit_value(&result);
+                result.map(Some)
+            }
+            None => Ok(None)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+fn value_at_mut<'a>(value: &'a mut Value, path: &[PathFragment]) -> Option<&'a mut Value> {
+    path.iter()
+        .try_fold(value, |value, fragment| match fragment {
+            PathFragment::Key(key) => value.get_mut(key.as_str()),
+            PathFragment::Index(index) => value.get_mut(*index as usize)
+        })
+}
+
+/// Deserializes response data, propagating `null`s in non-nullable fields.
+/// Returns `None` if the `null`s propagated all the way up to `data`.
+fn deserialize_data<T: DeserializeOwned>(
+    mut data: Value,
+    errors: &mut Vec<Error>
+) -> Result<Option<T>, serde_json::Error> {
+    loop {
+        let state = RefCell::new(State::new());
+        let error = match T::deserialize(ValueDeserializer::new(&data, &state)) {
+            Ok(data) => return Ok(Some(data)),
+            Err(e) => e
+        };
+        let mut state = state.into_inner();
+        if state.has_buffered_null() {
+            state.violate();
+        }
+        let violation = match state.violation {
+            Some(violation) => violation,
+            None => return Err(error)
+        };
+
+        let is_reported = errors
+            .iter()
+            .any(|error| error.path.as_ref() == Some(&violation.path));
+        if !is_reported {
+            errors.push(Error {
+                message: NULL_MESSAGE.to_string(),
+                locations: None,
+                path: Some(violation.path.clone()),
+                extensions: None
+            });
+        }
+
+        let ancestor = violation
+            .nullable_ancestor
+            .and_then(|depth| value_at_mut(&mut data, &violation.path[..depth]));
+        match ancestor {
+            Some(ancestor) => *ancestor = Value::Null,
+            None => return Ok(None)
+        }
+    }
+}
+
+impl<T: DeserializeOwned + Clone> Response<T> {
+    /// Deserializes a response body. If non-nullable fields are `null`, the `null` propagates to
+    /// the nearest nullable ancestor as required by the spec, and the partial data is returned
+    /// with an error for each such field, instead of failing the entire response.
+    pub fn from_json(value: Value) -> Result<Self, serde_json::Error> {
+        if let Ok(response) = Response::deserialize(&value) {
+            return Ok(response);
+        }
+
+        #[derive(Deserialize)]
+        struct RawResponse {
+            data: Option<Value>,
+            errors: Option<Vec<Error>>
+        }
+
+        let RawResponse { data, errors } = RawResponse::deserialize(value)?;
+        let mut errors = errors.unwrap_or_default();
+        let data = match data {
+            Some(data) => deserialize_data(data, &mut errors)?,
+            None => None
+        };
+
+        Ok(Response {
+            debug_info: None,
+            data,
+            errors: if errors.is_empty() {
+                None
+            } else {
+                Some(errors)
+            }
+        })
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test {
+    use crate::{Error, PathFragment, Response};
+    use serde_json::json;
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    struct Data {
+        conference: Option<Conference>
+    }
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    struct Conference {
+        id: String,
+        name: String,
+        talks: Option<Vec<Option<Talk>>>,
+        speaker: Option<Speaker>
+    }
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    struct Talk {
+        id: String,
+        title: String
+    }
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    #[serde(tag = "__typename")]
+    enum Speaker {
+        Person { name: String },
+        Organization { title: String }
+    }
+
+    fn path(path: serde_json::Value) -> Option<Vec<PathFragment>> {
+        Some(serde_json::from_value(path).unwrap())
+    }
+
+    #[test]
+    fn test_complete_data() {
+        let response = Response::<Data>::from_json(json!({
+            "data": {
+                "conference": {
+                    "id": "1",
+                    "name": "test",
+                    "talks": [{ "id": "2", "title": "talk" }],
+                    "speaker": { "__typename": "Person", "name": "speaker" }
+                }
+            }
+        }))
+        .unwrap();
+
+        let conference = response.data.unwrap().conference.unwrap();
+        assert_eq!(conference.talks.unwrap().len(), 1);
+        assert_eq!(
+            conference.speaker,
+            Some(Speaker::Person {
+                name: "speaker".to_string()
+            })
+        );
+        assert_eq!(response.errors, None);
+    }
+
+    #[test]
+    fn test_null_bubbles_to_nullable_ancestor() {
+        let response = Response::<Data>::from_json(json!({
+            "data": { "conference": { "id": "1", "name": null, "talks": null } },
+            "errors": [{ "message": "failed", "path": ["conference", "name"] }]
+        }))
+        .unwrap();
+
+        assert_eq!(response.data, Some(Data { conference: None }));
+        let errors = response.errors.unwrap();
+        assert_eq!(errors.len(), 1, "reported error was duplicated");
+        assert_eq!(errors[0].message, "failed");
+    }
+
+    #[test]
+    fn test_null_in_list_item() {
+        let response = Response::<Data>::from_json(json!({
+            "data": {
+                "conference": {
+                    "id": "1",
+                    "name": "test",
+                    "talks": [{ "id": "2", "title": "talk" }, { "id": "3", "title": null }]
+                }
+            }
+        }))
+        .unwrap();
+
+        let talks = response.data.unwrap().conference.unwrap().talks.unwrap();
+        assert_eq!(talks.len(), 2);
+        assert!(talks[0].is_some());
+        assert_eq!(talks[1], None);
+        assert_eq!(
+            response.errors,
+            Some(vec![Error {
+                message: super::NULL_MESSAGE.to_string(),
+                locations: None,
+                path: path(json!(["conference", "talks", 1, "title"])),
+                extensions: None
+            }])
+        );
+    }
+
+    #[test]
+    fn test_null_in_union() {
+        let response = Response::<Data>::from_json(json!({
+            "data": {
+                "conference": {
+                    "id": "1",
+                    "name": "test",
+                    "speaker": { "__typename": "Person", "name": null }
+                }
+            }
+        }))
+        .unwrap();
+
+        let conference = response.data.unwrap().conference.unwrap();
+        assert_eq!(conference.name, "test");
+        assert_eq!(conference.speaker, None);
+        let errors = response.errors.unwrap();
+        assert_eq!(errors[0].path, path(json!(["conference", "speaker"])));
+    }
+
+    #[test]
+    fn test_null_bubbles_to_data() {
+        #[derive(Debug, Clone, PartialEq, Deserialize)]
+        struct Data {
+            conference: Conference
+        }
+
+        let response = Response::<Data>::from_json(json!({
+            "data": { "conference": { "id": null, "name": "test" } }
+        }))
+        .unwrap();
+
+        assert_eq!(response.data, None);
+        assert_eq!(
+            response.errors.unwrap()[0].path,
+            path(json!(["conference", "id"]))
+        );
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        let response = Response::<Data>::from_json(json!({
+            "data": { "conference": { "id": 1, "name": "test" } }
+        }));
+
+        assert!(response.is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! Executor abstraction used by the client to run background tasks, such as rerunning
+//! subscribed queries. By default the client picks a spawner based on the enabled features,
+//! but any executor can be used by implementing [`Spawner`](./trait.Spawner.html) and passing it to
+//! [`ClientBuilder::with_spawner`](../struct.ClientBuilder.html#method.with_spawner).
+//!
+//! The default is chosen in this order:
+//!
+//! * `wasm32`: [`WasmSpawner`](./struct.WasmSpawner.html)
+//! * feature `tokio`: [`TokioSpawner`](./struct.TokioSpawner.html)
+//! * feature `async-std`: [`AsyncStdSpawner`](./struct.AsyncStdSpawner.html)
+//! * feature `smol`: [`SmolSpawner`](./struct.SmolSpawner.html)
+//! * otherwise: [`ThreadSpawner`](./struct.ThreadSpawner.html)
+
+use futures::future::BoxFuture;
+use std::sync::Arc;
+
+/// Spawns a future onto an executor, detaching it.
+///
+/// # Example
+///
+/// ```
+/// use artemis::spawner::Spawner;
+/// use futures::future::BoxFuture;
+///
+/// struct MySpawner;
+///
+/// impl Spawner for MySpawner {
+///     fn spawn(&self, future: BoxFuture<'static, ()>) {
+///         std::thread::spawn(move || futures::executor::block_on(future));
+///     }
+/// }
+/// ```
+pub trait Spawner: Send + Sync + 'static {
+    /// Run the future to completion in the background.
+    fn spawn(&self, future: BoxFuture<'static, ()>);
+}
+
+impl<S: Spawner + ?Sized> Spawner for Arc<S> {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        (**self).spawn(future)
+    }
+}
+
+/// Spawns tasks on a Tokio runtime. Without a handle this uses the runtime of the calling thread,
+/// falling back to [`ThreadSpawner`](./struct.ThreadSpawner.html) if there is none.
+///
+/// Requires feature: `tokio`
+#[cfg(all(not(target_arch = "wasm32"), feature = "tokio"))]
+#[derive(Clone, Debug, Default)]
+pub struct TokioSpawner {
+    handle: Option<tokio::runtime::Handle>
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "tokio"))]
+impl TokioSpawner {
+    /// Spawn tasks on the runtime of the calling thread.
+    pub fn new() -> Self {
+        Self { handle: None }
+    }
+
+    /// Spawn tasks on a specific runtime, regardless of where the client is used.
+    pub fn with_handle(handle: tokio::runtime::Handle) -> Self {
+        Self {
+            handle: Some(handle)
+        }
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "tokio"))]
+impl Spawner for TokioSpawner {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        let handle = self
+            .handle
+            .clone()
+            .or_else(|| tokio::runtime::Handle::try_current().ok());
+        if let Some(handle) = handle {
+            handle.spawn(future);
+        } else {
+            ThreadSpawner.spawn(future);
+        }
+    }
+}
+
+/// Spawns tasks on the global async-std executor.
+///
+/// Requires feature: `async-std`
+#[cfg(all(not(target_arch = "wasm32"), feature = "async-std"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AsyncStdSpawner;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "async-std"))]
+impl Spawner for AsyncStdSpawner {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        async_std::task::spawn(future);
+    }
+}
+
+/// Spawns tasks on the global smol executor.
+///
+/// Requires feature: `smol`
+#[cfg(all(not(target_arch = "wasm32"), feature = "smol"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SmolSpawner;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "smol"))]
+impl Spawner for SmolSpawner {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        smol::spawn(future).detach();
+    }
+}
+
+/// Spawns tasks on a single-threaded `futures::executor::LocalPool`.
+/// Tasks are sent to the pool over a channel, so the spawner itself can be shared across threads,
+/// but they only make progress while the pool is being run.
+///
+/// # Example
+///
+/// ```
+/// use artemis::spawner::{LocalPoolSpawner, Spawner};
+/// use futures::executor::LocalPool;
+///
+/// let mut pool = LocalPool::new();
+/// let spawner = LocalPoolSpawner::new(&pool.spawner()).unwrap();
+///
+/// spawner.spawn(Box::pin(async {}));
+/// pool.run_until_stalled();
+/// ```
+#[derive(Clone, Debug)]
+pub struct LocalPoolSpawner {
+    sender: futures::channel::mpsc::UnboundedSender<BoxFuture<'static, ()>>
+}
+
+impl LocalPoolSpawner {
+    /// Creates a spawner that runs its tasks on the pool the `LocalSpawner` belongs to.
+    /// Returns an error if the pool has already been shut down.
+    pub fn new(
+        spawner: &futures::executor::LocalSpawner
+    ) -> Result<Self, futures::task::SpawnError> {
+        use futures::{task::LocalSpawnExt, StreamExt};
+
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        spawner.spawn_local(receiver.for_each_concurrent(None, |future| future))?;
+        Ok(Self { sender })
+    }
+}
+
+impl Spawner for LocalPoolSpawner {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        // If the pool is gone there's nothing left to run the task, so it's dropped
+        let _ = self.sender.unbounded_send(future);
+    }
+}
+
+/// Runs each task to completion on a new thread.
+/// This works without any async runtime, but is fairly expensive and should only be used as a
+/// fallback.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadSpawner;
+
+impl Spawner for ThreadSpawner {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        std::thread::spawn(move || futures::executor::block_on(future));
+    }
+}
+
+/// Spawns tasks on the JavaScript event loop.
+#[cfg(target_arch = "wasm32")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WasmSpawner;
+
+#[cfg(target_arch = "wasm32")]
+impl Spawner for WasmSpawner {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        wasm_bindgen_futures::spawn_local(future);
+    }
+}
+
+/// The spawner used when none is set on the `ClientBuilder`.
+pub(crate) fn default_spawner() -> Arc<dyn Spawner> {
+    #[cfg(target_arch = "wasm32")]
+    let spawner = WasmSpawner;
+    #[cfg(all(not(target_arch = "wasm32"), feature = "tokio"))]
+    let spawner = TokioSpawner::new();
+    #[cfg(all(
+        not(target_arch = "wasm32"),
+        not(feature = "tokio"),
+        feature = "async-std"
+    ))]
+    let spawner = AsyncStdSpawner;
+    #[cfg(all(
+        not(target_arch = "wasm32"),
+        not(feature = "tokio"),
+        not(feature = "async-std"),
+        feature = "smol"
+    ))]
+    let spawner = SmolSpawner;
+    #[cfg(all(
+        not(target_arch = "wasm32"),
+        not(feature = "tokio"),
+        not(feature = "async-std"),
+        not(feature = "smol")
+    ))]
+    let spawner = ThreadSpawner;
+
+    Arc::new(spawner)
+}
@@ -0,0 +1,198 @@
+//! Query and exchange stand-ins for unit tests that need a full client.
+
+use crate::{
+    activity::NetworkStatus,
+    codegen::{FieldSelector, QueryInfo},
+    exchange::{Client, Operation, OperationMeta, OperationResult, OperationType},
+    Exchange, ExchangeFactory, ExchangeResult, GraphQLQuery, QueryBody, QueryError, Response
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+
+pub(crate) const TEST_URL: &str = "http://localhost:4000/graphql";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Data(pub u32);
+
+impl QueryInfo<()> for Data {
+    fn selection(_variables: &()) -> Vec<FieldSelector> {
+        Vec::new()
+    }
+}
+
+pub(crate) struct TestQuery;
+
+impl GraphQLQuery for TestQuery {
+    type Variables = ();
+    type ResponseData = Data;
+
+    fn build_query(variables: ()) -> (QueryBody<()>, OperationMeta) {
+        let body = QueryBody {
+            variables,
+            query: "query Test { value }".into(),
+            operation_name: "Test".into()
+        };
+        let meta = OperationMeta {
+            query_key: 1,
+            operation_type: OperationType::Query,
+            involved_types: Vec::new()
+        };
+        (body, meta)
+    }
+}
+
+pub(crate) struct TestMutation;
+
+impl GraphQLQuery for TestMutation {
+    type Variables = ();
+    type ResponseData = Data;
+
+    fn build_query(variables: ()) -> (QueryBody<()>, OperationMeta) {
+        let body = QueryBody {
+            variables,
+            query: "mutation TestMutation { value }".into(),
+            operation_name: "TestMutation".into()
+        };
+        let meta = OperationMeta {
+            query_key: 2,
+            operation_type: OperationType::Mutation,
+            involved_types: Vec::new()
+        };
+        (body, meta)
+    }
+}
+
+pub(crate) struct FakeExchange;
+
+impl<TNext: Exchange> ExchangeFactory<TNext> for FakeExchange {
+    type Output = FakeExchange;
+
+    fn build(self, _next: TNext) -> FakeExchange {
+        Self
+    }
+}
+
+#[async_trait]
+impl Exchange for FakeExchange {
+    async fn run<Q: GraphQLQuery, C: Client>(
+        &self,
+        operation: Operation<Q::Variables>,
+        _client: C
+    ) -> ExchangeResult<Q::ResponseData> {
+        Ok(OperationResult {
+            key: operation.key,
+            meta: operation.meta,
+            response: Response {
+                debug_info: None,
+                data: Some(serde_json::from_value(serde_json::json!(1)).unwrap()),
+                errors: None
+            }
+        })
+    }
+}
+
+/// Creates a network-only operation for `Q` against `TEST_URL`.
+#[cfg(any(
+    feature = "circuit-breaker",
+    feature = "local-state",
+    feature = "scheduling"
+))]
+pub(crate) fn make_operation<Q: GraphQLQuery>(
+    key: u64,
+    variables: Q::Variables
+) -> Operation<Q::Variables> {
+    let (query, meta) = Q::build_query(variables);
+    Operation {
+        key,
+        meta,
+        query,
+        options: crate::exchange::OperationOptions {
+            url: TEST_URL.to_string(),
+            extra_headers: None,
+            request_policy: crate::RequestPolicy::NetworkOnly,
+            extensions: None
+        }
+    }
+}
+
+/// Creates a response with `data` from its JSON representation.
+pub(crate) fn data_response(data: Value) -> Response<Value> {
+    Response {
+        debug_info: None,
+        data: Some(data),
+        errors: None
+    }
+}
+
+/// An operation as seen by a `TestExchange`.
+pub(crate) struct TestRequest {
+    pub operation: Operation<Value>,
+    pub report_network_status: Box<dyn Fn(NetworkStatus) + Send + Sync>
+}
+
+/// An exchange that handles operations with a closure. The closure returns the response as
+/// JSON, which is deserialized into the response data of the query.
+pub(crate) struct TestExchange<F>(F);
+
+impl<F, Fut> TestExchange<F>
+where
+    F: Fn(TestRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Response<Value>, QueryError>> + Send
+{
+    pub fn new(handler: F) -> Self {
+        TestExchange(handler)
+    }
+}
+
+impl<F, Fut, TNext> ExchangeFactory<TNext> for TestExchange<F>
+where
+    F: Fn(TestRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Response<Value>, QueryError>> + Send,
+    TNext: Exchange
+{
+    type Output = TestExchange<F>;
+
+    fn build(self, _next: TNext) -> TestExchange<F> {
+        self
+    }
+}
+
+#[async_trait]
+impl<F, Fut> Exchange for TestExchange<F>
+where
+    F: Fn(TestRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Response<Value>, QueryError>> + Send
+{
+    async fn run<Q: GraphQLQuery, C: Client>(
+        &self,
+        operation: Operation<Q::Variables>,
+        client: C
+    ) -> ExchangeResult<Q::ResponseData> {
+        let key = operation.key;
+        let meta = operation.meta.clone();
+        let request = TestRequest {
+            operation: Operation {
+                key,
+                meta: operation.meta,
+                query: QueryBody {
+                    variables: serde_json::to_value(&operation.query.variables)?,
+                    query: operation.query.query,
+                    operation_name: operation.query.operation_name
+                },
+                options: operation.options
+            },
+            report_network_status: Box::new(move |status| client.report_network_status(status))
+        };
+        let response = (self.0)(request).await?;
+        Ok(OperationResult {
+            key,
+            meta,
+            response: Response {
+                debug_info: response.debug_info,
+                data: response.data.map(serde_json::from_value).transpose()?,
+                errors: response.errors
+            }
+        })
+    }
+}
@@ -1,18 +1,27 @@
-use crate::{client::ClientImpl, GraphQLQuery, QueryBody, QueryError, Response};
 #[cfg(feature = "observable")]
-use futures::{channel::mpsc::Receiver, task::Context, Stream};
-use serde::{de::DeserializeOwned, Serialize};
+use crate::client::{
+    observable::{Listener, Received},
+    ClientImpl
+};
+use crate::{
+    activity::NetworkStatus, devtools::DevtoolsEvent, utils::progressive_hash, GraphQLQuery,
+    QueryBody, QueryError, Response
+};
+use futures::future::{self, BoxFuture};
+#[cfg(feature = "observable")]
+use futures::{task::Context, Stream};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     any::{Any, TypeId},
+    borrow::Cow,
     collections::HashMap,
     fmt,
-    pin::Pin,
-    sync::Arc,
-    task::Poll
+    sync::Arc
 };
+#[cfg(feature = "observable")]
+use std::{pin::Pin, task::Poll};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::JsValue;
-use std::marker::PhantomData;
 
 /// The result type returned by exchanges
 pub type ExchangeResult<R> = Result<OperationResult<R>, QueryError>;
@@ -43,6 +52,13 @@ pub trait Exchange: Send + Sync + 'static {
         operation: Operation<Q::Variables>,
         client: C
     ) -> ExchangeResult<Q::ResponseData>;
+
+    /// Clear any state held by this exchange, such as cached results.
+    /// This is called by `Client::reset_store`, usually when the user logs out.
+    ///
+    /// The default does nothing, but exchanges that wrap a `next` exchange must always forward
+    /// the call, even if they don't hold any state themselves.
+    fn reset(&self) {}
 }
 
 /// An exchange factory. This must be passed to the ClientBuilder by the user,
@@ -64,9 +80,138 @@ pub trait QueryInfo<TVars> {
     fn selection(variables: &TVars) -> Vec<FieldSelector>;
 }
 
+/// Internal function used in codegen
+///
+/// Deserializes a field that is present in the response as `Some`, even if its value is `null`.
+/// Fields missing from the response, like those skipped with `@skip` or `@include`,
+/// default to `None` instead.
+pub fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// A nullable input value that can also be left out of the request entirely.
+///
+/// GraphQL treats an input field that's set to `null` differently from one that's missing,
+/// e.g. a partial update can clear a field with `Null` and leave it unchanged with `Undefined`.
+/// The code generator uses this instead of `Option` for nullable variables and input fields when
+/// `CodegenBuilder::with_maybe_undefined` is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MaybeUndefined<T> {
+    /// The value is left out of the request
+    Undefined,
+    /// The value is explicitly set to `null`
+    Null,
+    /// The value is set
+    Value(T)
+}
+
+impl<T> Default for MaybeUndefined<T> {
+    fn default() -> Self {
+        MaybeUndefined::Undefined
+    }
+}
+
+impl<T> MaybeUndefined<T> {
+    /// Returns `true` if the value is left out of the request.
+    pub fn is_undefined(&self) -> bool {
+        matches!(self, MaybeUndefined::Undefined)
+    }
+
+    /// Returns `true` if the value is explicitly set to `null`.
+    pub fn is_null(&self) -> bool {
+        matches!(self, MaybeUndefined::Null)
+    }
+
+    /// Returns `true` if the value is set.
+    pub fn is_value(&self) -> bool {
+        matches!(self, MaybeUndefined::Value(_))
+    }
+
+    /// Converts from `&MaybeUndefined<T>` to `MaybeUndefined<&T>`.
+    pub fn as_ref(&self) -> MaybeUndefined<&T> {
+        match self {
+            MaybeUndefined::Undefined => MaybeUndefined::Undefined,
+            MaybeUndefined::Null => MaybeUndefined::Null,
+            MaybeUndefined::Value(value) => MaybeUndefined::Value(value)
+        }
+    }
+
+    /// Returns the value if it's set, treating `Undefined` and `Null` the same.
+    pub fn into_option(self) -> Option<T> {
+        match self {
+            MaybeUndefined::Value(value) => Some(value),
+            _ => None
+        }
+    }
+
+    /// Returns the value if it's set, or `default` otherwise.
+    pub fn unwrap_or(self, default: T) -> T {
+        self.into_option().unwrap_or(default)
+    }
+}
+
+impl<T> From<Option<T>> for MaybeUndefined<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => MaybeUndefined::Value(value),
+            None => MaybeUndefined::Null
+        }
+    }
+}
+
+impl<T> From<T> for MaybeUndefined<T> {
+    fn from(value: T) -> Self {
+        MaybeUndefined::Value(value)
+    }
+}
+
+impl<T: Serialize> Serialize for MaybeUndefined<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // `Undefined` fields are skipped by the generated code, so this only sees them in a list
+        match self {
+            MaybeUndefined::Value(value) => serializer.serialize_some(value),
+            _ => serializer.serialize_none()
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for MaybeUndefined<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Missing fields default to `Undefined` with `#[serde(default)]`
+        Option::<T>::deserialize(deserializer).map(Into::into)
+    }
+}
+
+/// An error returned by the generated builders for `Variables` and input objects.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuilderError {
+    /// A non-null field without a default value wasn't set
+    MissingField {
+        /// The name of the type that was being built
+        type_name: &'static str,
+        /// The name of the field that wasn't set
+        field: &'static str
+    }
+}
+
+impl std::error::Error for BuilderError {}
+impl fmt::Display for BuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuilderError::MissingField { type_name, field } => {
+                write!(f, "missing required field `{}` of `{}`", field, type_name)
+            }
+        }
+    }
+}
+
 /// The type of the operation. This corresponds directly to the GraphQL syntax,
 /// `query`, `mutation` and `subscription`.
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub enum OperationType {
     Query,
     Mutation,
@@ -112,7 +257,7 @@ impl ToString for OperationType {
 /// * `CacheAndNetwork` - Returns the result from the cache if it exists, but also refetch from the network and push the result to a subscription.
 /// This acts the same as CacheFirst without subscriptions, but has overhead.
 #[repr(u8)]
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub enum RequestPolicy {
     /// Prefers results from the cache, if it's not found it is fetched
     CacheFirst = 1,
@@ -143,15 +288,21 @@ pub struct HeaderPair(pub String, pub String);
 
 /// An internal struct used in codegen.
 /// This represents the recursive selection of a query and is used for normalization.
+/// Names are borrowed for generated queries and owned for operations only known at runtime.
 #[derive(Clone)]
 pub enum FieldSelector {
     /// field name, arguments
-    Scalar(&'static str, String),
+    Scalar(Cow<'static, str>, String),
     /// field_name, arguments, typename, inner selection
-    Object(&'static str, String, &'static str, Vec<FieldSelector>),
+    Object(
+        Cow<'static, str>,
+        String,
+        Cow<'static, str>,
+        Vec<FieldSelector>
+    ),
     /// field name, arguments, inner selection by type
     Union(
-        &'static str,
+        Cow<'static, str>,
         String,
         Arc<dyn Fn(&str) -> Vec<FieldSelector>>
     )
@@ -183,7 +334,7 @@ pub struct OperationMeta {
     /// The type of the operation, query, mutation or subscription
     pub operation_type: OperationType,
     /// A list of types that are returned by the query
-    pub involved_types: Vec<&'static str>
+    pub involved_types: Vec<Cow<'static, str>>
 }
 
 /// Options for the operation.
@@ -242,7 +393,9 @@ pub struct DebugInfo {
     pub source: ResultSource,
     /// Whether the query was actually run (`false`) or combined with another query in a deduplication exchange (`true`)
     #[serde(rename = "didDedup")]
-    pub did_dedup: bool
+    pub did_dedup: bool,
+    /// The HTTP status code of the response, if it came from the network
+    pub status: Option<u16>
 }
 
 /// The result of a successful operation.
@@ -256,23 +409,63 @@ pub struct OperationResult<R: DeserializeOwned + Send + Sync + Clone> {
     pub response: Response<R>
 }
 
+/// How an `Observable` handles results that arrive faster than they're consumed.
+/// Results are never awaited by the client, so a slow consumer can't hold up other subscriptions.
+#[cfg(feature = "observable")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OverflowStrategy {
+    /// Only keep the most recent result. Any result that wasn't consumed yet is replaced.
+    KeepLatest,
+    /// Keep up to this many results. If the queue is full the oldest result is dropped and
+    /// the observable yields `ObservableError::Lagged` before the next result.
+    Bounded(usize)
+}
+
+#[cfg(feature = "observable")]
+impl Default for OverflowStrategy {
+    fn default() -> Self {
+        OverflowStrategy::Bounded(8)
+    }
+}
+
+/// An error yielded by an `Observable` in place of a result.
+#[cfg(feature = "observable")]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObservableError {
+    /// The consumer fell behind a bounded queue and this many results were dropped.
+    Lagged(u64)
+}
+
+#[cfg(feature = "observable")]
+impl std::error::Error for ObservableError {}
+
+#[cfg(feature = "observable")]
+impl fmt::Display for ObservableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObservableError::Lagged(count) => {
+                write!(f, "observable lagged behind and skipped {} results", count)
+            }
+        }
+    }
+}
+
 /// An observable result. This implements `Stream` and unsubscribes on drop.
 /// It will receive early (partial or stale) results, as well as refreshing when the query is
 /// rerun after being invalidated by mutations.
 #[cfg(feature = "observable")]
 pub struct Observable<T, M: Exchange> {
-    inner: Receiver<Arc<dyn Any + Send + Sync>>,
+    inner: Arc<Listener<T>>,
     client: Arc<ClientImpl<M>>,
     key: u64,
-    index: usize,
-    t: PhantomData<T>
+    index: usize
 }
 
 #[cfg(feature = "observable")]
-impl<T: Clone, M: Exchange> Observable<T, M> {
+impl<T, M: Exchange> Observable<T, M> {
     pub(crate) fn new(
         key: u64,
-        inner: Receiver<Arc<dyn Any + Send + Sync>>,
+        inner: Arc<Listener<T>>,
         client: Arc<ClientImpl<M>>,
         index: usize
     ) -> Self {
@@ -280,14 +473,13 @@ impl<T: Clone, M: Exchange> Observable<T, M> {
             inner,
             client,
             key,
-            index,
-            t: PhantomData
+            index
         }
     }
 }
 
 #[cfg(feature = "observable")]
-impl<T: Clone, M: Exchange> Observable<T, M> {
+impl<T, M: Exchange> Observable<T, M> {
     /// Manually cause the client to rerun this query.
     /// Note this doesn't invalidate any caching, so if the query is in the cache it will simply be re-read
     pub fn rerun(&self) {
@@ -296,21 +488,18 @@ impl<T: Clone, M: Exchange> Observable<T, M> {
 }
 
 #[cfg(feature = "observable")]
-impl<T, M: Exchange> Stream for Observable<T, M>
-where
-    T: 'static + Unpin + Clone
-{
-    type Item = T;
+impl<R: Clone, M: Exchange> Stream for Observable<Result<Response<R>, QueryError>, M> {
+    type Item = Result<Response<R>, QueryError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let inner = &mut self.get_mut().inner;
-        let poll = <Receiver<Arc<dyn Any + Send + Sync>> as Stream>::poll_next(Pin::new(inner), cx);
-        match poll {
-            Poll::Ready(Some(boxed)) => {
-                let cast: &T = (&*boxed).downcast_ref::<T>().unwrap();
-                Poll::Ready(Some(cast.clone()))
+        match self.inner.poll_recv(cx) {
+            Poll::Ready(Received::Value(value)) => {
+                let value = Arc::try_unwrap(value).unwrap_or_else(|shared| (*shared).clone());
+                Poll::Ready(Some(value))
+            }
+            Poll::Ready(Received::Lagged(count)) => {
+                Poll::Ready(Some(Err(ObservableError::Lagged(count).into())))
             }
-            Poll::Ready(None) => Poll::Ready(None),
             Poll::Pending => Poll::Pending
         }
     }
@@ -360,8 +549,9 @@ pub trait Extension: Sized + Clone + Send + Sync + 'static {
 /// the Rust version uses the type as the key.
 ///
 /// This is usually instantiated by the [`ext![]`](./macro.ext!.html) macro.
+#[derive(Clone)]
 pub struct ExtensionMap {
-    rust: HashMap<TypeId, Box<dyn Any>>,
+    rust: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
     #[cfg(target_arch = "wasm32")]
     js: JsValue
 }
@@ -408,7 +598,7 @@ impl ExtensionMap {
     /// Insert a value into the map.
     /// This is usually called by the [`ext![]`](./macro.ext!.html) macro.
     pub fn insert<T: Extension>(&mut self, value: T) {
-        self.rust.insert(TypeId::of::<T>(), Box::new(value));
+        self.rust.insert(TypeId::of::<T>(), Arc::new(value));
     }
 
     /// Get a value from the map.
@@ -434,7 +624,7 @@ impl ExtensionMap {
     fn get_rust<T: Extension>(&self) -> Option<T> {
         self.rust
             .get(&TypeId::of::<T>())
-            .map(|boxed| (&*boxed).downcast_ref().unwrap())
+            .map(|value| value.downcast_ref().unwrap())
             .map(Clone::clone)
     }
 }
@@ -454,7 +644,58 @@ pub struct QueryOptions {
     /// The policy to use for this request. See `RequestPolicy`
     pub request_policy: Option<RequestPolicy>,
     /// Extra extensions passed to the exchanges. Allows for configuration of custom exchanges.
-    pub extensions: Option<Extensions>
+    pub extensions: Option<Extensions>,
+    /// Active subscriptions to rerun after a mutation succeeds. This is useful when type-based
+    /// invalidation or update functions don't catch a change.
+    pub refetch_queries: Vec<RefetchQuery>,
+    /// Wait for all refetched queries to finish before resolving the mutation.
+    /// By default they're run in the background.
+    pub await_refetch_queries: bool
+}
+
+/// An active subscription to refetch after a mutation. See `QueryOptions::refetch_queries`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RefetchQuery {
+    /// Refetch all subscriptions to the operation with this name, regardless of variables.
+    Name(String),
+    /// Refetch the subscription to a query with specific variables.
+    /// This is the key of the operation, use `RefetchQuery::query` to create it.
+    Key(u64)
+}
+
+impl RefetchQuery {
+    /// Refetch all subscriptions to the operation with this name, regardless of variables.
+    pub fn name<T: Into<String>>(operation_name: T) -> Self {
+        RefetchQuery::Name(operation_name.into())
+    }
+
+    /// Refetch the subscription to this query with these exact variables.
+    pub fn query<Q: GraphQLQuery>(_query: Q, variables: Q::Variables) -> Self {
+        let (query, meta) = Q::build_query(variables);
+        RefetchQuery::Key(progressive_hash(meta.query_key, &query.variables))
+    }
+
+    #[cfg(feature = "observable")]
+    pub(crate) fn matches(&self, key: u64, operation_name: &str) -> bool {
+        match self {
+            RefetchQuery::Name(name) => name == operation_name,
+            RefetchQuery::Key(refetch_key) => *refetch_key == key
+        }
+    }
+}
+
+impl From<&str> for RefetchQuery {
+    fn from(operation_name: &str) -> Self {
+        RefetchQuery::Name(operation_name.to_string())
+    }
+}
+
+/// Options that can be passed to `Client::reset_store_with_options`.
+#[derive(Default, Clone, Debug)]
+pub struct ResetOptions {
+    /// Don't rerun active subscriptions after clearing the store.
+    /// Instead, they receive an empty response.
+    pub clear_only: bool
 }
 
 /// Client trait passed to exchanges. Only exposes methods useful to exchanges
@@ -466,4 +707,48 @@ pub trait Client: Clone + Send + Sync + 'static {
     fn push_result<R>(&self, query_key: u64, result: ExchangeResult<R>)
     where
         R: DeserializeOwned + Send + Sync + Clone + 'static;
+
+    /// Emit an event to the client's [devtools](../devtools/index.html).
+    /// The event is only created if devtools are enabled and someone is listening.
+    fn emit_event<F: FnOnce() -> DevtoolsEvent>(&self, _event: F) {}
+
+    /// Report whether the server could be reached, updating the client's
+    /// [network status](../activity/index.html).
+    /// Exchanges that send operations over the network should call this with every outcome.
+    fn report_network_status(&self, _status: NetworkStatus) {}
+
+    /// Resolves once the client's network status is `Online`, which is immediately if it already is.
+    /// Exchanges can use this to pause work such as retries while the server isn't reachable.
+    fn wait_until_online(&self) -> BoxFuture<'static, ()> {
+        Box::pin(future::ready(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Input {
+        #[serde(default, skip_serializing_if = "MaybeUndefined::is_undefined")]
+        city: MaybeUndefined<String>
+    }
+
+    #[test]
+    fn maybe_undefined_round_trips() {
+        let cases = vec![
+            (MaybeUndefined::Undefined, r#"{}"#),
+            (MaybeUndefined::Null, r#"{"city":null}"#),
+            (
+                MaybeUndefined::Value("Berlin".to_string()),
+                r#"{"city":"Berlin"}"#
+            ),
+        ];
+
+        for (city, json) in cases {
+            let input = Input { city };
+            assert_eq!(serde_json::to_string(&input).unwrap(), json);
+            assert_eq!(serde_json::from_str::<Input>(json).unwrap(), input);
+        }
+    }
 }
@@ -1,6 +1,8 @@
 //! Contains utility functions mainly used internally, but they're public for use in
 //! exchanges and macros.
 
+#[cfg(any(feature = "dynamic", feature = "local-state"))]
+use graphql_parser::query::{OperationDefinition, SelectionSet};
 use serde::Serialize;
 use std::num::Wrapping;
 
@@ -18,6 +20,19 @@ pub fn progressive_hash<V: Serialize>(h: u32, x: &V) -> u64 {
     h.0
 }
 
+/// The top-level selection set of an operation, whatever kind of operation it is.
+#[cfg(any(feature = "dynamic", feature = "local-state"))]
+pub(crate) fn operation_selection_set_mut(
+    operation: &mut OperationDefinition
+) -> &mut SelectionSet {
+    match operation {
+        OperationDefinition::SelectionSet(selection_set) => selection_set,
+        OperationDefinition::Query(query) => &mut query.selection_set,
+        OperationDefinition::Mutation(mutation) => &mut mutation.selection_set,
+        OperationDefinition::Subscription(subscription) => &mut subscription.selection_set
+    }
+}
+
 /// Creates a new `ExtensionMap` and fills it with the passed values.
 ///
 /// # Example
@@ -43,7 +58,10 @@ macro_rules! ext {
 /// script and are not designed to be used manually
 #[cfg(target_arch = "wasm32")]
 pub mod wasm {
-    use crate::{client::ClientImpl, Exchange, ExtensionMap, HeaderPair, QueryError, QueryOptions};
+    use crate::{
+        client::ClientImpl, Exchange, ExtensionMap, HeaderPair, QueryError, QueryOptions,
+        RefetchQuery
+    };
     use futures::{future::BoxFuture, Stream, StreamExt};
     use js_sys::Function;
     use serde::Serialize;
@@ -105,7 +123,9 @@ export type QueryOptions = {
     url?: string,
     headers?: () => Headers,
     requestPolicy?: RequestPolicy,
-    extensions?: ExtensionMap
+    extensions?: ExtensionMap,
+    refetchQueries?: string[],
+    awaitRefetchQueries?: boolean
 };
 
 export type ExtensionMap = { [K: string]: Extension };
@@ -153,6 +173,10 @@ export interface ArtemisClient<Q> {
         pub fn request_policy2(this: &JsQueryOptions) -> Option<u8>;
         #[wasm_bindgen(method, getter = extensions, structural)]
         pub fn extensions2(this: &JsQueryOptions) -> JsValue;
+        #[wasm_bindgen(method, getter = refetchQueries, structural)]
+        pub fn refetch_queries2(this: &JsQueryOptions) -> Option<js_sys::Array>;
+        #[wasm_bindgen(method, getter = awaitRefetchQueries, structural)]
+        pub fn await_refetch_queries2(this: &JsQueryOptions) -> Option<bool>;
     }
 
     impl From<JsQueryOptions> for QueryOptions {
@@ -162,7 +186,18 @@ export interface ArtemisClient<Q> {
                 url: options.url2().map(|url| url.parse().unwrap()),
                 extra_headers: options.headers2().map(convert_header_fn),
                 request_policy: options.request_policy2().map(Into::into),
-                extensions: extensions.map(Arc::new)
+                extensions: extensions.map(Arc::new),
+                refetch_queries: options
+                    .refetch_queries2()
+                    .map(|names| {
+                        names
+                            .iter()
+                            .filter_map(|name| name.as_string())
+                            .map(RefetchQuery::Name)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                await_refetch_queries: options.await_refetch_queries2().unwrap_or(false)
             }
         }
     }
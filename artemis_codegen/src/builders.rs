@@ -0,0 +1,93 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+/// A field of a `Variables` or input struct, as seen by its builder.
+pub(crate) struct BuilderField {
+    /// The name of the field in the GraphQL document or schema, used in errors.
+    pub graphql_name: String,
+    /// The name of the field in the generated struct.
+    pub name: Ident,
+    /// The type of the field in the generated struct.
+    pub ty: TokenStream,
+    /// The value used when the field isn't set. Required fields without a default make `build`
+    /// fail, optional ones are `None` or `Undefined`.
+    pub default: Option<TokenStream>,
+    /// Whether the field is non-null.
+    pub required: bool
+}
+
+/// Methods of the generated builder that setters can't be named after.
+const RESERVED_SETTERS: &[&str] = &["build", "builder"];
+
+/// The name of the setter for a field. Fields named like one of the builder's own methods get a
+/// `set_` prefix.
+fn setter_name(name: &Ident) -> Ident {
+    if RESERVED_SETTERS.contains(&name.to_string().as_str()) {
+        Ident::new(&format!("set_{}", name), name.span())
+    } else {
+        name.clone()
+    }
+}
+
+/// Generates a builder for a `Variables` or input struct. Each field has a setter, and `build`
+/// fills in the default values and checks that the non-null fields were set.
+pub(crate) fn builder(type_name: &str, fields: &[BuilderField]) -> TokenStream {
+    let type_ident = Ident::new(type_name, Span::call_site());
+    let builder_ident = Ident::new(&format!("{}Builder", type_name), Span::call_site());
+    let doc = format!("A builder for [`{}`]({}).", type_name, type_name);
+
+    let names: Vec<_> = fields.iter().map(|field| &field.name).collect();
+    let setters: Vec<_> = fields
+        .iter()
+        .map(|field| setter_name(&field.name))
+        .collect();
+    let types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    let values = fields.iter().map(|field| {
+        let name = &field.name;
+        match (&field.default, field.required) {
+            (Some(default), _) => quote!(self.#name.unwrap_or_else(|| #default)),
+            (None, true) => {
+                let graphql_name = &field.graphql_name;
+                quote! {
+                    self.#name.ok_or(::artemis::BuilderError::MissingField {
+                        type_name: #type_name,
+                        field: #graphql_name
+                    })?
+                }
+            }
+            (None, false) => quote!(self.#name.unwrap_or_default())
+        }
+    });
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Clone, Default)]
+        pub struct #builder_ident {
+            #(#names: Option<#types>,)*
+        }
+
+        impl #builder_ident {
+            #(
+                pub fn #setters(mut self, value: impl Into<#types>) -> Self {
+                    self.#names = Some(value.into());
+                    self
+                }
+            )*
+
+            /// Builds the value, with the default values for any fields that weren't set.
+            /// Returns an error if a non-null field without a default value wasn't set.
+            pub fn build(self) -> Result<#type_ident, ::artemis::BuilderError> {
+                Ok(#type_ident {
+                    #(#names: #values,)*
+                })
+            }
+        }
+
+        impl #type_ident {
+            /// Creates a builder, to set the fields one by one.
+            pub fn builder() -> #builder_ident {
+                #builder_ident::default()
+            }
+        }
+    }
+}
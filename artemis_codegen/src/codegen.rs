@@ -32,23 +32,14 @@ pub(crate) fn all_operations(query: &query::Document) -> Vec<Operation<'_>> {
     operations
 }
 
-pub(crate) fn typescript_for_query(
-    schema: &schema::Schema<'_>,
-    query: &query::Document,
-    operation: &Operation<'_>,
-    options: &crate::GraphQLClientCodegenOptions
-) -> Result<String, CodegenError> {
-    let mut context = QueryContext::new(
-        schema,
-        options.deprecation_strategy(),
-        options.normalization(),
-        options.include_query_info,
-        options.wasm_bindgen
-    );
-
-    let mut definitions: Vec<String> = Vec::new();
-
-    for definition in &query.definitions {
+/// Adds the fragments defined in a document to the context.
+fn add_fragments<'query>(
+    context: &mut QueryContext<'query, '_>,
+    schema: &'query schema::Schema<'_>,
+    document: &'query query::Document,
+    shared: bool
+) -> Result<(), CodegenError> {
+    for definition in &document.definitions {
         match definition {
             query::Definition::Operation(_op) => (),
             query::Definition::Fragment(fragment) => {
@@ -66,13 +57,41 @@ pub(crate) fn typescript_for_query(
                         name: &fragment.name,
                         selection: Selection::from(&fragment.selection_set),
                         on,
-                        is_required: false.into()
+                        is_required: false.into(),
+                        shared
                     }
                 );
             }
         }
     }
 
+    Ok(())
+}
+
+pub(crate) fn typescript_for_query(
+    schema: &schema::Schema<'_>,
+    query: &query::Document,
+    shared_fragments: &[query::Document],
+    operation: &Operation<'_>,
+    options: &crate::GraphQLClientCodegenOptions
+) -> Result<String, CodegenError> {
+    let mut context = QueryContext::new(
+        schema,
+        options.deprecation_strategy(),
+        options.normalization(),
+        options.include_query_info,
+        options.wasm_bindgen
+    );
+    context.maybe_undefined = options.maybe_undefined;
+
+    let mut definitions: Vec<String> = Vec::new();
+
+    for document in shared_fragments {
+        add_fragments(&mut context, schema, document, true)?;
+    }
+    // Fragments in the query document take precedence over shared ones with the same name.
+    add_fragments(&mut context, schema, query, false)?;
+
     let response_data_fields = {
         let root_name = operation.root_name(&context.schema);
         let opt_definition = context.schema.objects.get(&root_name);
@@ -169,6 +188,7 @@ pub(crate) fn typescript_for_query(
 pub(crate) fn response_for_query(
     schema: &schema::Schema<'_>,
     query: &query::Document,
+    shared_fragments: &[query::Document],
     operation: &Operation<'_>,
     options: &crate::GraphQLClientCodegenOptions
 ) -> Result<(TokenStream, HashSet<String>), CodegenError> {
@@ -179,6 +199,7 @@ pub(crate) fn response_for_query(
         options.include_query_info,
         options.wasm_bindgen
     );
+    context.maybe_undefined = options.maybe_undefined;
 
     if let Some(derives) = options.variables_derives() {
         context.ingest_variables_derives(&derives)?;
@@ -187,34 +208,16 @@ pub(crate) fn response_for_query(
     if let Some(derives) = options.response_derives() {
         context.ingest_response_derives(&derives)?;
     }
+    context.variables = operation.variables.clone();
 
     let mut definitions: Vec<TokenStream> = Vec::new();
     let mut types: HashSet<String> = HashSet::new();
 
-    for definition in &query.definitions {
-        match definition {
-            query::Definition::Operation(_op) => (),
-            query::Definition::Fragment(fragment) => {
-                let &query::TypeCondition::On(ref on) = &fragment.type_condition;
-                let on = schema.fragment_target(on).ok_or_else(|| {
-                    let msg = format!(
-                        "Fragment {} is defined on unknown type: {}",
-                        &fragment.name, on,
-                    );
-                    CodegenError::SyntaxError(msg)
-                })?;
-                context.fragments.insert(
-                    &fragment.name,
-                    GqlFragment {
-                        name: &fragment.name,
-                        selection: Selection::from(&fragment.selection_set),
-                        on,
-                        is_required: false.into()
-                    }
-                );
-            }
-        }
+    for document in shared_fragments {
+        add_fragments(&mut context, schema, document, true)?;
     }
+    // Fragments in the query document take precedence over shared ones with the same name.
+    add_fragments(&mut context, schema, query, false)?;
 
     let (response_data_selection, response_data_fields) = {
         let root_name = operation.root_name(&context.schema);
@@ -243,18 +246,12 @@ pub(crate) fn response_for_query(
         definition.response_fields_for_selection(&context, &selection, &prefix)?
     };
 
-    let enum_definitions = context.schema.enums.values().filter_map(|enm| {
-        if enm.is_required.get() {
-            Some(enm.to_rust(&context))
-        } else {
-            None
-        }
-    });
+    // Shared fragments are generated in the shared `fragments` module.
     let fragment_definitions: Result<Vec<TokenStream>, _> = context
         .fragments
         .values()
         .filter_map(|fragment| {
-            if fragment.is_required.get() {
+            if fragment.is_required.get() && !fragment.shared {
                 Some(fragment.to_rust(&context))
             } else {
                 None
@@ -264,32 +261,7 @@ pub(crate) fn response_for_query(
     let fragment_definitions = fragment_definitions?;
     let variables_struct = operation.expand_variables(&context);
 
-    let input_object_definitions: Result<Vec<TokenStream>, _> = context
-        .schema
-        .inputs
-        .values()
-        .filter_map(|i| {
-            if i.is_required.get() {
-                Some(i.to_rust(&context))
-            } else {
-                None
-            }
-        })
-        .collect();
-    let input_object_definitions = input_object_definitions?;
-
-    let scalar_definitions: Vec<TokenStream> = context
-        .schema
-        .scalars
-        .values()
-        .filter_map(|s| {
-            if s.is_required.get() {
-                Some(s.to_rust(context.normalization))
-            } else {
-                None
-            }
-        })
-        .collect();
+    let type_definitions = type_definitions(&context, options, quote!(super::super::types))?;
 
     let response_derives = context.response_derives();
 
@@ -297,9 +269,7 @@ pub(crate) fn response_for_query(
         quote! {
             impl ::artemis::codegen::QueryInfo<Variables> for ResponseData {
                 fn selection(variables: &Variables) -> Vec<::artemis::codegen::FieldSelector> {
-                    vec![
-                        #(#response_data_selection,)*
-                    ]
+                    #response_data_selection
                 }
             }
         }
@@ -308,22 +278,7 @@ pub(crate) fn response_for_query(
     };
 
     let tokens = quote! {
-        use serde::{Serialize, Deserialize};
-
-        #[allow(dead_code)]
-        type Boolean = bool;
-        #[allow(dead_code)]
-        type Float = f64;
-        #[allow(dead_code)]
-        type Int = i64;
-        #[allow(dead_code)]
-        type ID = String;
-
-        #(#scalar_definitions)*
-
-        #(#input_object_definitions)*
-
-        #(#enum_definitions)*
+        #type_definitions
 
         #(#fragment_definitions)*
 
@@ -341,3 +296,188 @@ pub(crate) fn response_for_query(
 
     Ok((tokens, types))
 }
+
+/// Generates the shared `fragments` module, with the types for all fragments in the shared
+/// fragment files.
+pub(crate) fn response_for_fragments(
+    schema: &schema::Schema<'_>,
+    shared_fragments: &[query::Document],
+    options: &crate::GraphQLClientCodegenOptions
+) -> Result<TokenStream, CodegenError> {
+    let mut context = QueryContext::new(
+        schema,
+        options.deprecation_strategy(),
+        options.normalization(),
+        options.include_query_info,
+        options.wasm_bindgen
+    );
+    context.maybe_undefined = options.maybe_undefined;
+
+    if let Some(derives) = options.variables_derives() {
+        context.ingest_variables_derives(derives)?;
+    }
+
+    if let Some(derives) = options.response_derives() {
+        context.ingest_response_derives(derives)?;
+    }
+
+    for document in shared_fragments {
+        add_fragments(&mut context, schema, document, false)?;
+    }
+
+    let fragment_definitions = context
+        .fragments
+        .values()
+        .map(|fragment| {
+            // The fragments are spread in operations with different variables.
+            if fragment.selection.uses_variables() {
+                return Err(CodegenError::TypeError(format!(
+                    "The shared fragment {} uses variables. Only fragments in query documents can \
+                     use the variables of their operations.",
+                    fragment.name
+                )));
+            }
+            fragment.to_rust(&context)
+        })
+        .collect::<Result<Vec<TokenStream>, CodegenError>>()?;
+
+    let type_definitions = type_definitions(&context, options, quote!(super::types))?;
+    let variables_derives = context.variables_derives();
+
+    Ok(quote! {
+        #type_definitions
+
+        #(#fragment_definitions)*
+
+        /// Shared fragments don't use variables, so they're selected with these empty ones.
+        #variables_derives
+        pub struct Variables;
+    })
+}
+
+/// Generates the shared `types` module, with the scalars, input objects and enums required by
+/// any of the modules generated with the schema so far.
+pub(crate) fn response_for_types(
+    schema: &schema::Schema<'_>,
+    options: &crate::GraphQLClientCodegenOptions
+) -> Result<TokenStream, CodegenError> {
+    let mut context = QueryContext::new(
+        schema,
+        options.deprecation_strategy(),
+        options.normalization(),
+        options.include_query_info,
+        options.wasm_bindgen
+    );
+    context.maybe_undefined = options.maybe_undefined;
+
+    if let Some(derives) = options.variables_derives() {
+        context.ingest_variables_derives(derives)?;
+    }
+
+    if let Some(derives) = options.response_derives() {
+        context.ingest_response_derives(derives)?;
+    }
+
+    let builtin_scalars = builtin_scalars();
+    let schema_types = schema_types(&context, options, &quote!(pub))?;
+
+    Ok(quote! {
+        #[allow(unused_imports)]
+        use serde::{Serialize, Deserialize};
+
+        #builtin_scalars
+
+        #schema_types
+    })
+}
+
+/// The built-in scalar aliases and the scalars, input objects and enums that are required by the
+/// generated code. With shared types, the latter are re-exported from the module at `types_path`,
+/// so they can still be named through the query module.
+fn type_definitions(
+    context: &QueryContext<'_, '_>,
+    options: &crate::GraphQLClientCodegenOptions,
+    types_path: TokenStream
+) -> Result<TokenStream, CodegenError> {
+    let builtin_scalars = builtin_scalars();
+    let schema_types = if options.shared_types {
+        quote! {
+            #[allow(unused_imports)]
+            pub use #types_path::*;
+        }
+    } else {
+        schema_types(context, options, &quote!())?
+    };
+
+    Ok(quote! {
+        use serde::{Serialize, Deserialize};
+
+        #builtin_scalars
+
+        #schema_types
+    })
+}
+
+fn builtin_scalars() -> TokenStream {
+    quote! {
+        #[allow(dead_code)]
+        type Boolean = bool;
+        #[allow(dead_code)]
+        type Float = f64;
+        #[allow(dead_code)]
+        type Int = i64;
+        #[allow(dead_code)]
+        type ID = String;
+    }
+}
+
+/// The scalars, input objects and enums that are required by the generated code.
+fn schema_types(
+    context: &QueryContext<'_, '_>,
+    options: &crate::GraphQLClientCodegenOptions,
+    scalar_visibility: &TokenStream
+) -> Result<TokenStream, CodegenError> {
+    let enum_definitions = context.schema.enums.values().filter_map(|enm| {
+        if enm.is_required.get() {
+            Some(enm.to_rust(context))
+        } else {
+            None
+        }
+    });
+
+    let input_object_definitions: Result<Vec<TokenStream>, _> = context
+        .schema
+        .inputs
+        .values()
+        .filter_map(|i| {
+            if i.is_required.get() {
+                Some(i.to_rust(context))
+            } else {
+                None
+            }
+        })
+        .collect();
+    let input_object_definitions = input_object_definitions?;
+
+    let scalar_definitions: Result<Vec<TokenStream>, _> = context
+        .schema
+        .scalars
+        .values()
+        .filter_map(|s| {
+            if s.is_required.get() {
+                Some(s.to_rust(context.normalization, options, scalar_visibility))
+            } else {
+                None
+            }
+        })
+        .collect();
+    let scalar_definitions = scalar_definitions?;
+
+    Ok(quote! {
+        #(#scalar_definitions)*
+
+        #(#input_object_definitions)*
+
+        #(#enum_definitions)*
+    })
+}
@@ -1,6 +1,9 @@
 use crate::{deprecation::DeprecationStrategy, normalization::Normalization};
 use proc_macro2::Ident;
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf}
+};
 use syn::Visibility;
 
 /// Which context is this code generation effort taking place.
@@ -37,12 +40,27 @@ pub struct GraphQLClientCodegenOptions {
     /// A path to a file to include in the module to force Cargo to take into account changes in
     /// the schema files when recompiling.
     schema_file: Option<PathBuf>,
+    /// Paths to client-only schema extensions, used for fields with the `@client` directive.
+    client_schema_files: Vec<PathBuf>,
+    /// Paths to documents with fragments shared by all queries.
+    fragment_files: Vec<PathBuf>,
+    /// Rust types for custom scalars, by scalar name.
+    scalars: BTreeMap<String, String>,
     /// Normalization pattern for query types and names.
     normalization: Normalization,
     /// Include query info? (Needed for things like normalized caching)
     pub include_query_info: bool,
     /// Include wasm_bindgen utils? Requires wasm-bindgen and wasm-typescript-definition
-    pub wasm_bindgen: bool
+    pub wasm_bindgen: bool,
+    /// Refer to custom scalars without a mapping as `crate::ScalarName` instead of failing.
+    /// Used by the macros, where the type alias can be declared next to the query.
+    pub crate_scalars: bool,
+    /// Import enums, input objects and custom scalars from the shared `types` module instead of
+    /// generating them in every query module.
+    pub shared_types: bool,
+    /// Use `MaybeUndefined` instead of `Option` for nullable variables and input fields, so they
+    /// can be left out of the request instead of being sent as `null`.
+    pub maybe_undefined: bool
 }
 
 impl GraphQLClientCodegenOptions {
@@ -59,9 +77,15 @@ impl GraphQLClientCodegenOptions {
             struct_name: Default::default(),
             query_file: Default::default(),
             schema_file: Default::default(),
+            client_schema_files: Default::default(),
+            fragment_files: Default::default(),
+            scalars: Default::default(),
             normalization: Normalization::None,
             include_query_info: true,
-            wasm_bindgen: true
+            wasm_bindgen: true,
+            crate_scalars: false,
+            shared_types: false,
+            maybe_undefined: false
         }
     }
 
@@ -124,12 +148,40 @@ impl GraphQLClientCodegenOptions {
         self.operation_name = Some(operation_name);
     }
 
+    /// A path to a file to include in the module to force Cargo to take into account changes in
+    /// the schema files when recompiling.
+    pub fn set_schema_file(&mut self, path: PathBuf) {
+        self.schema_file = Some(path);
+    }
+
     /// A path to a file to include in the module to force Cargo to take into account changes in
     /// the schema files when recompiling.
     pub fn schema_file(&self) -> Option<&Path> {
         self.schema_file.as_deref()
     }
 
+    /// Add a client-only schema extension file. Its types and type extensions are merged into the
+    /// schema, so fields with the `@client` directive can be selected.
+    pub fn add_client_schema_file(&mut self, path: PathBuf) {
+        self.client_schema_files.push(path);
+    }
+
+    /// The client-only schema extension files.
+    pub fn client_schema_files(&self) -> &[PathBuf] {
+        &self.client_schema_files
+    }
+
+    /// Add a document with fragments that all queries can spread. The fragments are generated once,
+    /// into the shared `fragments` module, instead of into each query module.
+    pub fn add_fragment_file(&mut self, path: PathBuf) {
+        self.fragment_files.push(path);
+    }
+
+    /// The documents with fragments shared by all queries.
+    pub fn fragment_files(&self) -> &[PathBuf] {
+        &self.fragment_files
+    }
+
     /// A path to a file to include in the module to force Cargo to take into account changes in
     /// the query files when recompiling.
     pub fn query_file(&self) -> Option<&Path> {
@@ -146,6 +198,16 @@ impl GraphQLClientCodegenOptions {
         self.struct_ident.as_ref()
     }
 
+    /// Map a custom scalar to a Rust type, e.g. `chrono::DateTime<chrono::Utc>`.
+    pub fn add_scalar(&mut self, name: String, rust_type: String) {
+        self.scalars.insert(name, rust_type);
+    }
+
+    /// The Rust types for custom scalars, by scalar name.
+    pub fn scalars(&self) -> &BTreeMap<String, String> {
+        &self.scalars
+    }
+
     /// Set the normalization mode for the generated code.
     pub fn set_normalization(&mut self, norm: Normalization) {
         self.normalization = norm;
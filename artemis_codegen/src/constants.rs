@@ -2,6 +2,15 @@ use crate::{deprecation::DeprecationStatus, field_type::FieldType, objects::GqlO
 
 pub(crate) const TYPENAME_FIELD: &str = "__typename";
 
+/// Fields with this directive are resolved locally and never sent to the server.
+pub(crate) const CLIENT_DIRECTIVE: &str = "client";
+
+/// Fields and fragments with this directive are only selected if its `if` argument is true.
+pub(crate) const INCLUDE_DIRECTIVE: &str = "include";
+
+/// Fields and fragments with this directive are only selected if its `if` argument is false.
+pub(crate) const SKIP_DIRECTIVE: &str = "skip";
+
 pub(crate) fn string_type() -> &'static str {
     "String"
 }
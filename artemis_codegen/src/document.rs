@@ -0,0 +1,228 @@
+use crate::{fragments::spread_fragments, operations::Operation, selection::Selection};
+use graphql_parser::query::{Definition, Document, FragmentDefinition, OperationDefinition};
+
+/// The document sent to the server for an operation: the operation itself, followed by the local
+/// and shared fragments it spreads, directly or through other fragments, printed and minified.
+/// Fragments defined in the query document take precedence over the shared ones.
+pub(crate) fn operation_document(
+    query: &Document,
+    shared_fragments: &[Document],
+    operation: &Operation<'_>
+) -> String {
+    let local: Vec<_> = fragment_definitions(query).collect();
+    let shared: Vec<_> = shared_fragments
+        .iter()
+        .flat_map(fragment_definitions)
+        .collect();
+
+    let mut definitions: Vec<Definition> = query
+        .definitions
+        .iter()
+        .filter(|definition| match definition {
+            Definition::Operation(definition) => {
+                operation_name(definition) == Some(operation.name.as_str())
+            }
+            Definition::Fragment(_) => false
+        })
+        .cloned()
+        .collect();
+
+    let mut visited = Vec::new();
+    let mut pending = Vec::new();
+    spread_fragments(&operation.selection, &mut pending);
+    while let Some(name) = pending.pop() {
+        if visited.contains(&name) {
+            continue;
+        }
+        visited.push(name.clone());
+
+        let fragment = match local.iter().chain(shared.iter()).find(|f| f.name == name) {
+            Some(fragment) => fragment,
+            None => continue
+        };
+        spread_fragments(&Selection::from(&fragment.selection_set), &mut pending);
+        definitions.push(Definition::Fragment((*fragment).clone()));
+    }
+
+    minify(&Document { definitions }.to_string())
+}
+
+fn fragment_definitions(document: &Document) -> impl Iterator<Item = &FragmentDefinition> {
+    document
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Fragment(fragment) => Some(fragment),
+            Definition::Operation(_) => None
+        })
+}
+
+fn operation_name(definition: &OperationDefinition) -> Option<&str> {
+    match definition {
+        OperationDefinition::Query(query) => query.name.as_deref(),
+        OperationDefinition::Mutation(mutation) => mutation.name.as_deref(),
+        OperationDefinition::Subscription(subscription) => subscription.name.as_deref(),
+        OperationDefinition::SelectionSet(_) => None
+    }
+}
+
+/// Removes the characters that don't change the meaning of a document: whitespace, commas and
+/// comments outside of strings. Two tokens are only separated by a space when neither of them is
+/// punctuation.
+fn minify(document: &str) -> String {
+    fn is_punctuator(c: char) -> bool {
+        matches!(
+            c,
+            '!' | '$' | '&' | '(' | ')' | '.' | ':' | '=' | '@' | '[' | ']' | '{' | '|' | '}'
+        )
+    }
+
+    let mut minified = String::with_capacity(document.len());
+    let mut chars = document.chars().peekable();
+    let mut separated = false;
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() || c == ',' {
+            separated = true;
+            continue;
+        }
+        if c == '#' {
+            while let Some(&c) = chars.peek() {
+                if c == '\n' || c == '\r' {
+                    break;
+                }
+                chars.next();
+            }
+            separated = true;
+            continue;
+        }
+
+        let follows_token = matches!(minified.chars().last(), Some(last) if !is_punctuator(last));
+        if separated && follows_token && !is_punctuator(c) {
+            minified.push(' ');
+        }
+        separated = false;
+        minified.push(c);
+
+        if c == '"' {
+            // Strings are copied as they are, block strings included
+            let block = chars.peek() == Some(&'"') && {
+                let mut lookahead = chars.clone();
+                lookahead.next();
+                lookahead.next() == Some('"')
+            };
+            if block {
+                minified.push_str("\"\"");
+                chars.next();
+                chars.next();
+            }
+            let mut quotes = 0;
+            while let Some(c) = chars.next() {
+                minified.push(c);
+                match c {
+                    '\\' => {
+                        if let Some(escaped) = chars.next() {
+                            minified.push(escaped);
+                        }
+                        quotes = 0;
+                    }
+                    '"' if !block => break,
+                    '"' => {
+                        quotes += 1;
+                        if quotes == 3 {
+                            break;
+                        }
+                    }
+                    _ => quotes = 0
+                }
+            }
+        }
+    }
+
+    minified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minify_removes_ignored_characters() {
+        let document = r#"
+            # A comment
+            query Hero($episode: Episode = JEDI, $ids: [ID!]) {
+              hero(episode: $episode, filter: { name: "R2, D2  # not a comment" }) {
+                ... on Droid { primaryFunction }
+                names: friends(first: 10) { name }
+                list(values: ["a", "", -1, 2])
+              }
+            }
+        "#;
+
+        assert_eq!(
+            minify(document),
+            concat!(
+                "query Hero($episode:Episode=JEDI$ids:[ID!]){",
+                "hero(episode:$episode filter:{name:\"R2, D2  # not a comment\"}){",
+                "...on Droid{primaryFunction}",
+                "names:friends(first:10){name}",
+                "list(values:[\"a\" \"\" -1 2])",
+                "}}"
+            )
+        );
+    }
+
+    #[test]
+    fn minify_keeps_block_strings() {
+        let document = "{ field(text: \"\"\"\n  a \"quoted\"  text\n\"\"\") }";
+
+        assert_eq!(
+            minify(document),
+            "{field(text:\"\"\"\n  a \"quoted\"  text\n\"\"\")}"
+        );
+    }
+
+    #[test]
+    fn operation_document_only_has_reachable_fragments() {
+        let query = graphql_parser::parse_query(
+            r#"
+            query First {
+              hero { ...HeroFields }
+            }
+
+            query Second {
+              hero { name }
+            }
+
+            fragment HeroFields on Character {
+              ...Shared
+            }
+
+            fragment Unused on Character {
+              id
+            }
+            "#
+        )
+        .unwrap();
+        let shared = graphql_parser::parse_query(
+            r#"
+            fragment Shared on Character { name }
+            fragment OtherShared on Character { id }
+            "#
+        )
+        .unwrap();
+        let operations = crate::codegen::all_operations(&query);
+
+        assert_eq!(
+            operation_document(&query, &[shared.clone()], &operations[0]),
+            concat!(
+                "query First{hero{...HeroFields}}",
+                "fragment HeroFields on Character{...Shared}",
+                "fragment Shared on Character{name}"
+            )
+        );
+        assert_eq!(
+            operation_document(&query, &[shared], &operations[1]),
+            "query Second{hero{name}}"
+        );
+    }
+}
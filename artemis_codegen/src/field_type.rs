@@ -137,21 +137,30 @@ impl<'a> FieldType<'a> {
 
         if ctx.is_scalar(self.name) || ctx.is_enum(self.name) {
             quote! {
-                ::artemis::codegen::FieldSelector::Scalar(#field_name, #args)
+                ::artemis::codegen::FieldSelector::Scalar(::std::borrow::Cow::Borrowed(#field_name), #args)
             }
-        } else if ctx.is_union(self.name) {
+        } else if ctx.is_union(self.name) || ctx.is_interface(self.name) {
+            // The selection depends on the concrete type, which is only known once there's data.
             let type_ident = Ident::new(prefix, Span::call_site());
-            let selection_fn = quote! { ::std::sync::Arc::new(|typename| #type_ident::selection(typename, variables)) };
+            let selection_fn = quote! {{
+                let variables = variables.clone();
+                ::std::sync::Arc::new(move |typename: &str| #type_ident::selection(typename, &variables))
+            }};
 
             quote! {
-                ::artemis::codegen::FieldSelector::Union(#field_name, #args, #selection_fn)
+                ::artemis::codegen::FieldSelector::Union(::std::borrow::Cow::Borrowed(#field_name), #args, #selection_fn)
             }
         } else {
             let type_ident = Ident::new(prefix, Span::call_site());
             let typename = self.name;
 
             quote! {
-                ::artemis::codegen::FieldSelector::Object(#field_name, #args, #typename, #type_ident::selection(variables))
+                ::artemis::codegen::FieldSelector::Object(
+                    ::std::borrow::Cow::Borrowed(#field_name),
+                    #args,
+                    ::std::borrow::Cow::Borrowed(#typename),
+                    #type_ident::selection(variables)
+                )
             }
         }
     }
@@ -219,6 +228,40 @@ impl<'a> FieldType<'a> {
         qualified
     }
 
+    /// Takes the type of a variable or input field. With `MaybeUndefined` enabled, nullable values
+    /// can also be left out, so they're `MaybeUndefined` instead of `Option`.
+    pub(crate) fn to_rust_input(&self, context: &QueryContext<'_, '_>, boxed: bool) -> TokenStream {
+        if self.is_maybe_undefined(context) {
+            let mut inner = self.clone();
+            inner.qualifiers.insert(0, GraphqlTypeQualifier::Required);
+            let inner = inner.to_rust(context, "");
+            let inner = if boxed { quote!(Box<#inner>) } else { inner };
+            quote!(::artemis::MaybeUndefined<#inner>)
+        } else {
+            let ty = self.to_rust(context, "");
+            if boxed {
+                quote!(Box<#ty>)
+            } else {
+                ty
+            }
+        }
+    }
+
+    /// The serde attribute for a variable or input field, leaving out `MaybeUndefined` values
+    /// that are undefined.
+    pub(crate) fn input_serde_attribute(&self, context: &QueryContext<'_, '_>) -> TokenStream {
+        if self.is_maybe_undefined(context) {
+            quote!(#[serde(default, skip_serializing_if = "::artemis::MaybeUndefined::is_undefined")])
+        } else {
+            quote!()
+        }
+    }
+
+    /// Whether a variable or input field of this type is a `MaybeUndefined`.
+    pub(crate) fn is_maybe_undefined(&self, context: &QueryContext<'_, '_>) -> bool {
+        context.maybe_undefined && self.is_optional()
+    }
+
     /// Return the innermost name - we mostly use this for looking types up in our Schema struct.
     pub fn inner_name_str(&self) -> &str {
         self.name
@@ -487,9 +530,9 @@ mod tests {
                 let selector = ty.field_selector(ctx, "Cat", "cat_field", Vec::new());
                 let expected = quote! {
                     ::artemis::codegen::FieldSelector::Object(
-                        "cat_field",
+                        ::std::borrow::Cow::Borrowed("cat_field"),
                         String::new(),
-                        "Cat",
+                        ::std::borrow::Cow::Borrowed("Cat"),
                         Cat::selection(variables)
                     )
                 };
@@ -505,7 +548,10 @@ mod tests {
 
                 let selector = ty.field_selector(ctx, "Age", "age_field", Vec::new());
                 let expected = quote! {
-                    ::artemis::codegen::FieldSelector::Scalar("age_field", String::new())
+                    ::artemis::codegen::FieldSelector::Scalar(
+                        ::std::borrow::Cow::Borrowed("age_field"),
+                        String::new()
+                    )
                 };
 
                 assert_eq!(selector.to_string(), expected.to_string())
@@ -519,7 +565,10 @@ mod tests {
 
                 let selector = ty.field_selector(ctx, "Animal", "animal_field", Vec::new());
                 let expected = quote! {
-                    ::artemis::codegen::FieldSelector::Scalar("animal_field", String::new())
+                    ::artemis::codegen::FieldSelector::Scalar(
+                        ::std::borrow::Cow::Borrowed("animal_field"),
+                        String::new()
+                    )
                 };
 
                 assert_eq!(selector.to_string(), expected.to_string())
@@ -534,9 +583,12 @@ mod tests {
                 let selector = ty.field_selector(ctx, "Union", "union_field", Vec::new());
                 let expected = quote! {
                     ::artemis::codegen::FieldSelector::Union(
-                        "union_field",
+                        ::std::borrow::Cow::Borrowed("union_field"),
                         String::new(),
-                        ::std::sync::Arc::new(|typename| Union::selection(typename, variables))
+                        {
+                            let variables = variables.clone();
+                            ::std::sync::Arc::new(move |typename: &str| Union::selection(typename, &variables))
+                        }
                     )
                 };
 
@@ -556,7 +608,7 @@ mod tests {
                 let selector = ty.field_selector(ctx, "Age", "age_field", args);
                 let expected = quote! {
                     ::artemis::codegen::FieldSelector::Scalar(
-                        "age_field",
+                        ::std::borrow::Cow::Borrowed("age_field"),
                         format!("(arg1:5,arg2:false)")
                     )
                 };
@@ -583,7 +635,7 @@ mod tests {
                 let selector = ty.field_selector(ctx, "Age", "age_field", args);
                 let expected = quote! {
                     ::artemis::codegen::FieldSelector::Scalar(
-                        "age_field",
+                        ::std::borrow::Cow::Borrowed("age_field"),
                         format!("(arg1:{:?},arg2:{:?})", variables.arg_1, variables.arg_2)
                     )
                 };
@@ -1,5 +1,6 @@
 use crate::{query::QueryContext, selection::Selection, CodegenError};
 use proc_macro2::TokenStream;
+use quote::quote;
 use std::cell::Cell;
 
 /// Represents which type a fragment is defined on. This is the type mentioned in the fragment's `on` clause.
@@ -30,7 +31,16 @@ pub(crate) struct GqlFragment<'query> {
     /// The selected fields.
     pub selection: Selection<'query>,
     /// Whether the fragment is used in the current query
-    pub is_required: Cell<bool>
+    pub is_required: Cell<bool>,
+    /// Whether the fragment comes from a shared fragment file. Its types are generated in the
+    /// shared `fragments` module instead of the query's module.
+    pub shared: bool
+}
+
+/// The path of the shared `fragments` module from inside a query module. The query module is
+/// nested in a file next to `fragments.rs` in the output directory.
+pub(crate) fn shared_fragments_path() -> TokenStream {
+    quote!(super::super::fragments)
 }
 
 impl<'query> GqlFragment<'query> {
@@ -69,6 +79,17 @@ impl<'query> GqlFragment<'query> {
         }
     }
 
+    /// The path of the fragment's type from the module being generated.
+    pub(crate) fn type_path(&self) -> TokenStream {
+        let name = proc_macro2::Ident::new(self.name, proc_macro2::Span::call_site());
+        if self.shared {
+            let fragments = shared_fragments_path();
+            quote!(#fragments::#name)
+        } else {
+            quote!(#name)
+        }
+    }
+
     pub(crate) fn is_recursive(&self) -> bool {
         self.selection.contains_fragment(&self.name)
     }
@@ -78,3 +99,18 @@ impl<'query> GqlFragment<'query> {
         self.selection.require_items(context);
     }
 }
+
+/// The names of the fragments spread anywhere in the selection.
+pub(crate) fn spread_fragments(selection: &Selection<'_>, names: &mut Vec<String>) {
+    use crate::selection::SelectionItem;
+
+    for item in selection.0.iter() {
+        match item {
+            SelectionItem::Field(field) => spread_fragments(&field.fields, names),
+            SelectionItem::FragmentSpread(spread) => names.push(spread.fragment_name.to_string()),
+            SelectionItem::InlineFragment(inline_fragment) => {
+                spread_fragments(&inline_fragment.fields, names)
+            }
+        }
+    }
+}
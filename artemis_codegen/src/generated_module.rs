@@ -9,6 +9,7 @@ pub(crate) struct GeneratedModule<'a> {
     pub operation: &'a crate::operations::Operation<'a>,
     pub query_string: &'a str,
     pub query_document: &'a graphql_parser::query::Document,
+    pub shared_fragments: &'a [graphql_parser::query::Document],
     pub schema: &'a crate::schema::Schema<'a>,
     pub options: &'a crate::GraphQLClientCodegenOptions
 }
@@ -19,6 +20,7 @@ impl<'a> GeneratedModule<'a> {
         Ok(crate::codegen::response_for_query(
             &self.schema,
             &self.query_document,
+            self.shared_fragments,
             &self.operation,
             &self.options
         )?)
@@ -28,6 +30,7 @@ impl<'a> GeneratedModule<'a> {
         Ok(crate::codegen::typescript_for_query(
             &self.schema,
             &self.query_document,
+            self.shared_fragments,
             &self.operation,
             &self.options
         )?)
@@ -44,7 +47,7 @@ impl<'a> GeneratedModule<'a> {
             .operation(operation_name_literal);
         let operation_name_ident = Ident::new(&operation_name_ident, Span::call_site());
 
-        // Force cargo to refresh the generated code when the query file changes.
+        // Force cargo to refresh the generated code when the query or schema file changes.
         let query_include = self
             .options
             .query_file()
@@ -55,6 +58,16 @@ impl<'a> GeneratedModule<'a> {
                 )
             })
             .unwrap_or_else(|| quote! {});
+        let schema_include = self
+            .options
+            .schema_file()
+            .map(|path| {
+                let path = path.to_str();
+                quote!(
+                    const __SCHEMA_WORKAROUND: &[u8] = include_bytes!(#path);
+                )
+            })
+            .unwrap_or_else(|| quote! {});
 
         let query_string = &self.query_string;
         let query_string_hash = hash(query_string);
@@ -75,8 +88,10 @@ impl<'a> GeneratedModule<'a> {
             CodegenMode::Derive => None
         };
 
-        let types: Vec<_> = types.into_iter().collect();
-        let involved_types = quote!(vec![#(#types,)*]);
+        // Sorted so the generated code is the same every time
+        let mut types: Vec<_> = types.into_iter().collect();
+        types.sort();
+        let involved_types = quote!(vec![#(::std::borrow::Cow::Borrowed(#types),)*]);
 
         #[allow(unused_mut)]
         let mut typescript = format!(
@@ -104,13 +119,18 @@ impl<'a> GeneratedModule<'a> {
             };
         }
 
-        let typescript = quote! {
-            #[cfg(target_arch = "wasm32")]
-            use wasm_bindgen::prelude::*;
+        // The derive doesn't generate a query enum, so it doesn't export TypeScript definitions.
+        let typescript = if self.options.wasm_bindgen {
+            quote! {
+                #[cfg(target_arch = "wasm32")]
+                use wasm_bindgen::prelude::*;
 
-            #[cfg(target_arch = "wasm32")]
-            #[wasm_bindgen(typescript_custom_section)]
-            const TS_APPEND_CONTENT: &'static str = #typescript;
+                #[cfg(target_arch = "wasm32")]
+                #[wasm_bindgen(typescript_custom_section)]
+                const TS_APPEND_CONTENT: &'static str = #typescript;
+            }
+        } else {
+            quote!()
         };
 
         Ok(quote!(
@@ -127,6 +147,7 @@ impl<'a> GeneratedModule<'a> {
                 pub const QUERY: &str = #query_string;
 
                 #query_include
+                #schema_include
 
                 #impls
             }
@@ -145,8 +166,8 @@ impl<'a> GeneratedModule<'a> {
 
                     let body = ::artemis::QueryBody {
                         variables,
-                        query: #module_name::QUERY,
-                        operation_name: #module_name::OPERATION_NAME,
+                        query: ::std::borrow::Cow::Borrowed(#module_name::QUERY),
+                        operation_name: ::std::borrow::Cow::Borrowed(#module_name::OPERATION_NAME),
                     };
 
                     (body, meta)
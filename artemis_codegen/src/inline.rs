@@ -0,0 +1,259 @@
+use crate::{
+    build_schema, generate_modules, parse_schema, validation::validate_document, CodegenMode,
+    GraphQLClientCodegenOptions
+};
+use graphql_parser::Pos;
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use std::path::PathBuf;
+use syn::{
+    parse::{Parse, ParseStream},
+    Ident, LitStr, Result, Token
+};
+
+/// The input of the `graphql!` macro: a list of `key = "value",` options followed by the query
+/// document, written as Rust tokens.
+pub struct InlineQueryInput {
+    schema_path: Option<LitStr>,
+    variables_derives: Option<String>,
+    response_derives: Option<String>,
+    document: TokenStream
+}
+
+impl Parse for InlineQueryInput {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let mut schema_path = None;
+        let mut variables_derives = None;
+        let mut response_derives = None;
+
+        while input.peek(Ident) && input.peek2(Token![=]) {
+            let key = input.parse::<Ident>()?;
+            input.parse::<Token![=]>()?;
+            let value = input.parse::<LitStr>()?;
+            input.parse::<Token![,]>()?;
+
+            match key.to_string().as_str() {
+                "schema" => schema_path = Some(value),
+                "variables_derives" => variables_derives = Some(value.value()),
+                "response_derives" => response_derives = Some(value.value()),
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "unknown option, expected one of `schema`, `variables_derives` or \
+                         `response_derives`"
+                    ))
+                }
+            }
+        }
+
+        Ok(InlineQueryInput {
+            schema_path,
+            variables_derives,
+            response_derives,
+            document: input.parse()?
+        })
+    }
+}
+
+/// The query document as a string, along with the span of the token at each column.
+/// Everything is written on a single line, so only columns are needed to map positions back.
+#[derive(Default)]
+struct DocumentText {
+    text: String,
+    len: usize,
+    token_spans: Vec<(usize, Span)>,
+    glue_next: bool
+}
+
+impl DocumentText {
+    fn push(&mut self, token: &str, span: Span, glue_next: bool) {
+        if !self.text.is_empty() && !self.glue_next {
+            self.text.push(' ');
+            self.len += 1;
+        }
+        self.token_spans.push((self.len, span));
+        self.text.push_str(token);
+        self.len += token.chars().count();
+        self.glue_next = glue_next;
+    }
+
+    fn push_tokens(&mut self, tokens: TokenStream) {
+        for token in tokens {
+            match token {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", "")
+                    };
+                    if !open.is_empty() {
+                        self.push(open, group.span_open(), false);
+                    }
+                    self.push_tokens(group.stream());
+                    if !close.is_empty() {
+                        self.push(close, group.span_close(), false);
+                    }
+                }
+                TokenTree::Punct(punct) => {
+                    // `...` is lexed as joint puncts, and `$`, `@` and `-` read better
+                    // attached to the following name or number.
+                    let glue = punct.spacing() == proc_macro2::Spacing::Joint
+                        || ['$', '@', '-'].contains(&punct.as_char());
+                    self.push(&punct.as_char().to_string(), punct.span(), glue);
+                }
+                TokenTree::Ident(ident) => self.push(&ident.to_string(), ident.span(), false),
+                TokenTree::Literal(literal) => {
+                    self.push(&literal.to_string(), literal.span(), false)
+                }
+            }
+        }
+    }
+
+    /// The span of the token at the given position in the text.
+    fn span_at(&self, position: Pos) -> Span {
+        let column = position.column.saturating_sub(1);
+        self.token_spans
+            .iter()
+            .take_while(|(start, _)| *start <= column)
+            .last()
+            .or_else(|| self.token_spans.last())
+            .map(|(_, span)| *span)
+            .unwrap_or_else(Span::call_site)
+    }
+}
+
+/// Extracts the position from a `graphql_parser` error message, e.g. `Parse error at 1:14`.
+fn parse_error_position(message: &str) -> Option<Pos> {
+    let position = message.split("error at ").nth(1)?;
+    let mut parts = position.split(|c: char| !c.is_ascii_digit());
+    let line = parts.next()?.parse().ok()?;
+    let column = parts.next()?.parse().ok()?;
+    Some(Pos { line, column })
+}
+
+/// Paths in the macro are relative to the root of the crate using it.
+fn resolve_path(path: &str) -> PathBuf {
+    let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(root).join(path)
+}
+
+fn generate(input: InlineQueryInput) -> Result<TokenStream> {
+    let schema_path = input.schema_path.ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            r#"missing schema, add it with `schema = "...",` before the query"#
+        )
+    })?;
+
+    let mut document = DocumentText::default();
+    document.push_tokens(input.document);
+    let query = graphql_parser::parse_query(&document.text).map_err(|e| {
+        let message = e.to_string();
+        match parse_error_position(&message) {
+            // The position in the generated text is meaningless to users, so leave it out.
+            Some(position) => {
+                let details: Vec<_> = message.lines().skip(1).map(str::trim).collect();
+                let message = format!("query parse error: {}", details.join("\n").trim());
+                syn::Error::new(document.span_at(position), message)
+            }
+            None => syn::Error::new(Span::call_site(), message)
+        }
+    })?;
+
+    let mut options = GraphQLClientCodegenOptions::new(CodegenMode::Cli);
+    options.set_module_visibility(
+        syn::VisPublic {
+            pub_token: <Token![pub]>::default()
+        }
+        .into()
+    );
+    options.wasm_bindgen = false;
+    options.crate_scalars = true;
+    if let Some(variables_derives) = input.variables_derives {
+        options.set_variables_derives(variables_derives);
+    }
+    // Responses are serialized by the client, so `Serialize` can't be left out.
+    let response_derives = match input.response_derives {
+        Some(response_derives) => format!("Serialize,{}", response_derives),
+        None => "Serialize".to_string()
+    };
+    options.set_response_derives(response_derives);
+    // Include the schema in the generated modules, so changes to it trigger a rebuild.
+    let resolved_schema_path = resolve_path(&schema_path.value());
+    options.set_schema_file(resolved_schema_path.clone());
+
+    let schema_error = |e: crate::CodegenError| syn::Error::new(schema_path.span(), e);
+    let (parsed_schema, client_schemas) =
+        parse_schema(&resolved_schema_path, &options).map_err(schema_error)?;
+    let schema = build_schema(&parsed_schema, &client_schemas).map_err(schema_error)?;
+
+    validate_document(&query, &schema)
+        .map_err(|e| syn::Error::new(document.span_at(e.position), e))?;
+
+    generate_modules(&query, &[], &schema, options)
+        .map(|(tokens, _)| tokens)
+        .map_err(|e| syn::Error::new(Span::call_site(), e))
+}
+
+/// Generates the query modules for a document embedded in the `graphql!` macro.
+/// Errors are returned as `compile_error!`s pointing at the offending token.
+pub fn inline_query(input: InlineQueryInput) -> TokenStream {
+    generate(input).unwrap_or_else(|e| e.to_compile_error())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn document_text() {
+        let mut document = DocumentText::default();
+        document.push_tokens(quote! {
+            query Hero($episode: Episode = NEWHOPE, $limit: Int = -1) {
+                hero(episode: $episode) @include(if: true) {
+                    ...HeroFields
+                }
+            }
+        });
+
+        assert_eq!(
+            document.text,
+            "query Hero ( $episode : Episode = NEWHOPE , $limit : Int = -1 ) { \
+             hero ( episode : $episode ) @include ( if : true ) { ... HeroFields } }"
+        );
+        graphql_parser::parse_query(&document.text).unwrap();
+    }
+
+    #[test]
+    fn error_position() {
+        let mut document = DocumentText::default();
+        document.push_tokens(quote!(query Hero { hero { name } : }));
+        let error = graphql_parser::parse_query(&document.text).unwrap_err();
+
+        let position = parse_error_position(&error.to_string()).unwrap();
+        assert_eq!(position.line, 1);
+        let column = position.column - 1;
+        assert_eq!(&document.text[column..column + 1], ":");
+    }
+
+    #[test]
+    fn schema_is_tracked() {
+        let input: InlineQueryInput = syn::parse2(quote! {
+            schema = "src/tests/star_wars_schema.graphql",
+            query Hero {
+                hero {
+                    __typename
+                    name
+                }
+            }
+        })
+        .unwrap();
+
+        let generated_code = generate(input).unwrap().to_string();
+        let schema_path = resolve_path("src/tests/star_wars_schema.graphql");
+        let schema_path = schema_path.to_str();
+        let include = quote!(include_bytes!(#schema_path));
+        assert!(generated_code.contains(&include.to_string()));
+    }
+}
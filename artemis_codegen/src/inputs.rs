@@ -1,7 +1,14 @@
 use crate::{
-    deprecation::DeprecationStatus, introspection_response, objects::GqlObjectField,
-    query::QueryContext, schema::Schema, CodegenError
+    builders::{builder, BuilderField},
+    deprecation::DeprecationStatus,
+    introspection_response,
+    objects::GqlObjectField,
+    query::QueryContext,
+    schema::Schema,
+    variables::graphql_parser_value_to_literal,
+    CodegenError
 };
+use graphql_parser::query::Value;
 use heck::SnakeCase;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
@@ -13,6 +20,8 @@ pub struct GqlInput<'schema> {
     pub description: Option<&'schema str>,
     pub name: &'schema str,
     pub fields: HashMap<&'schema str, GqlObjectField<'schema>>,
+    /// The default values of the fields that have one in the schema.
+    pub default_values: HashMap<&'schema str, Value>,
     pub is_required: Cell<bool>
 }
 
@@ -74,7 +83,11 @@ impl<'schema> GqlInput<'schema> {
 
                 context.schema.require(&field.type_.inner_name_str());
 
-                format!("{}: {}", field.name, ty)
+                if field.type_.is_maybe_undefined(context) {
+                    format!("{}?: {}", field.name, ty)
+                } else {
+                    format!("{}: {}", field.name, ty)
+                }
             })
             .collect();
 
@@ -98,28 +111,47 @@ impl<'schema> GqlInput<'schema> {
         let norm = context.normalization;
         let mut fields: Vec<&GqlObjectField<'_>> = self.fields.values().collect();
         fields.sort_unstable_by(|a, b| a.name.cmp(&b.name));
-        let fields = fields.iter().map(|field| {
-            let ty = field.type_.to_rust(&context, "");
+        let mut builder_fields = Vec::with_capacity(fields.len());
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|field| {
+                // If the type is recursive, we have to box it
+                let boxed = context
+                    .schema
+                    .inputs
+                    .get(field.type_.inner_name_str())
+                    .map(|input| input.is_recursive_without_indirection(context))
+                    .unwrap_or(false);
+                let ty = field.type_.to_rust_input(context, boxed);
+                let serde_attribute = field.type_.input_serde_attribute(context);
 
-            // If the type is recursive, we have to box it
-            let ty = if let Some(input) = context.schema.inputs.get(field.type_.inner_name_str()) {
-                if input.is_recursive_without_indirection(context) {
-                    quote! { Box<#ty> }
-                } else {
-                    quote!(#ty)
-                }
-            } else {
-                quote!(#ty)
-            };
+                context.schema.require(field.type_.inner_name_str());
+                let name = crate::shared::keyword_replace(&field.name.to_snake_case());
+                let rename = crate::shared::field_rename_annotation(field.name, &name);
+                let name = norm.field_name(name);
+                let name = Ident::new(&name, Span::call_site());
 
-            context.schema.require(&field.type_.inner_name_str());
-            let name = crate::shared::keyword_replace(&field.name.to_snake_case());
-            let rename = crate::shared::field_rename_annotation(&field.name, &name);
-            let name = norm.field_name(name);
-            let name = Ident::new(&name, Span::call_site());
+                // Boxed defaults aren't supported, they're only needed for recursive inputs
+                let default = self
+                    .default_values
+                    .get(field.name)
+                    .filter(|_| !boxed)
+                    .map(|value| {
+                        let optional = field.type_.is_optional();
+                        graphql_parser_value_to_literal(value, context, &field.type_, optional)
+                    });
 
-            quote!(#rename pub #name: #ty)
-        });
+                let rust_field = quote!(#rename #serde_attribute pub #name: #ty);
+                builder_fields.push(BuilderField {
+                    graphql_name: field.name.to_string(),
+                    name,
+                    ty,
+                    default,
+                    required: !field.type_.is_optional()
+                });
+                rust_field
+            })
+            .collect();
         let variables_derives = context.variables_derives();
         let wasm_variables_derives = if context.wasm_bindgen {
             let filtered: Vec<_> = vec!["Deserialize"]
@@ -140,6 +172,7 @@ impl<'schema> GqlInput<'schema> {
         // This works in tandem with renamed struct Variables field types, eg: pub struct Variables { pub criteria : crate_ , }
         let name = crate::shared::keyword_replace(&self.name);
         let name = norm.input_name(name);
+        let builder = builder(&name, &builder_fields);
         let name = Ident::new(&name, Span::call_site());
         Ok(quote! {
             #variables_derives
@@ -147,6 +180,8 @@ impl<'schema> GqlInput<'schema> {
             pub struct #name {
                 #(#fields,)*
             }
+
+            #builder
         })
     }
 }
@@ -172,6 +207,14 @@ impl<'schema> std::convert::From<&'schema graphql_parser::schema::InputObjectTyp
                     (name, field)
                 })
                 .collect(),
+            default_values: schema_input
+                .fields
+                .iter()
+                .filter_map(|field| {
+                    let default = field.default_value.as_ref()?;
+                    Some((field.name.as_str(), default.clone()))
+                })
+                .collect(),
             is_required: false.into()
         }
     }
@@ -209,11 +252,41 @@ impl<'schema> std::convert::From<&'schema introspection_response::FullType> for
                     (name, field)
                 })
                 .collect(),
+            default_values: schema_input
+                .input_fields
+                .iter()
+                .flatten()
+                .filter_map(Option::as_ref)
+                .filter_map(|f| {
+                    let name = f.input_value.name.as_ref()?;
+                    let default = f.input_value.default_value.as_ref()?;
+                    Some((name.as_str(), parse_default_value(default)?))
+                })
+                .collect(),
             is_required: false.into()
         }
     }
 }
 
+/// Parses a default value from an introspection result, which is a GraphQL literal.
+fn parse_default_value(literal: &str) -> Option<Value> {
+    use graphql_parser::query::{Definition, OperationDefinition, Selection};
+
+    // The parser only takes whole documents, so the value is parsed as an argument
+    let document = graphql_parser::parse_query(&format!("{{ field(value: {}) }}", literal)).ok()?;
+    match document.definitions.into_iter().next()? {
+        Definition::Operation(OperationDefinition::SelectionSet(selection_set)) => {
+            match selection_set.items.into_iter().next()? {
+                Selection::Field(field) => {
+                    field.arguments.into_iter().next().map(|(_, value)| value)
+                }
+                _ => None
+            }
+        }
+        _ => None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -255,6 +328,7 @@ mod tests {
             ]
             .into_iter()
             .collect(),
+            default_values: HashMap::new(),
             is_required: false.into()
         };
 
@@ -265,6 +339,37 @@ mod tests {
             "# [serde (rename = \"pawsCount\")] ",
             "pub paws_count : Float , ",
             "pub requirements : Option < CatRequirements > , ",
+            "} ",
+            "# [doc = \"A builder for [`Cat`](Cat).\"] ",
+            "# [derive (Clone , Default)] ",
+            "pub struct CatBuilder { ",
+            "offsprings : Option < Vec < Cat > > , ",
+            "paws_count : Option < Float > , ",
+            "requirements : Option < Option < CatRequirements > > , ",
+            "} ",
+            "impl CatBuilder { ",
+            "pub fn offsprings (mut self , value : impl Into < Vec < Cat > >) -> Self { ",
+            "self . offsprings = Some (value . into ()) ; self ",
+            "} ",
+            "pub fn paws_count (mut self , value : impl Into < Float >) -> Self { ",
+            "self . paws_count = Some (value . into ()) ; self ",
+            "} ",
+            "pub fn requirements (mut self , value : impl Into < Option < CatRequirements > >) -> Self { ",
+            "self . requirements = Some (value . into ()) ; self ",
+            "} ",
+            "# [doc = r\" Builds the value, with the default values for any fields that weren't set.\"] ",
+            "# [doc = r\" Returns an error if a non-null field without a default value wasn't set.\"] ",
+            "pub fn build (self) -> Result < Cat , :: artemis :: BuilderError > { ",
+            "Ok (Cat { ",
+            "offsprings : self . offsprings . ok_or (:: artemis :: BuilderError :: MissingField { type_name : \"Cat\" , field : \"offsprings\" }) ? , ",
+            "paws_count : self . paws_count . ok_or (:: artemis :: BuilderError :: MissingField { type_name : \"Cat\" , field : \"pawsCount\" }) ? , ",
+            "requirements : self . requirements . unwrap_or_default () , ",
+            "}) ",
+            "} ",
+            "} ",
+            "impl Cat { ",
+            "# [doc = r\" Creates a builder, to set the fields one by one.\"] ",
+            "pub fn builder () -> CatBuilder { CatBuilder :: default () } ",
             "}",
         ]
         .into_iter()
@@ -275,12 +380,57 @@ mod tests {
         let mut context = QueryContext::new_empty(&schema);
         context.ingest_variables_derives("Clone").unwrap();
 
-        assert_eq!(
-            format!(
-                "{}",
-                context.schema.inputs["Cat"].to_rust(&context).unwrap()
-            ),
-            expected
+        let generated = format!(
+            "{}",
+            context.schema.inputs["Cat"].to_rust(&context).unwrap()
         );
+        assert_eq!(generated, expected);
+    }
+
+    #[test]
+    fn gql_input_builder_uses_defaults() {
+        let cat = GqlInput {
+            description: None,
+            name: "Cat",
+            fields: vec![
+                (
+                    "name",
+                    GqlObjectField {
+                        description: None,
+                        name: "name",
+                        type_: FieldType::new(string_type()).nonnull(),
+                        deprecation: DeprecationStatus::Current
+                    }
+                ),
+                (
+                    "lives",
+                    GqlObjectField {
+                        description: None,
+                        name: "lives",
+                        type_: FieldType::new("Int"),
+                        deprecation: DeprecationStatus::Current
+                    }
+                )
+            ]
+            .into_iter()
+            .collect(),
+            default_values: vec![("lives", parse_default_value("9").unwrap())]
+                .into_iter()
+                .collect(),
+            is_required: false.into()
+        };
+
+        let mut schema = crate::schema::Schema::new();
+        schema.inputs.insert(cat.name, cat);
+        let context = QueryContext::new_empty(&schema);
+        let generated = context.schema.inputs["Cat"]
+            .to_rust(&context)
+            .unwrap()
+            .to_string();
+
+        assert!(generated.contains("lives : self . lives . unwrap_or_else (|| Some (9i64))"));
+        assert!(generated.contains(
+            r#"name : self . name . ok_or (:: artemis :: BuilderError :: MissingField { type_name : "Cat" , field : "name" }) ?"#
+        ));
     }
 }
@@ -9,7 +9,10 @@ use crate::{
 };
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
-use std::{cell::Cell, collections::HashSet};
+use std::{
+    cell::Cell,
+    collections::{BTreeSet, HashSet}
+};
 
 /// A GraphQL interface (simplified schema representation).
 ///
@@ -19,7 +22,7 @@ pub struct GqlInterface<'schema> {
     /// The documentation for the interface. Extracted from the schema.
     pub description: Option<&'schema str>,
     /// The set of object types implementing this interface.
-    pub implemented_by: HashSet<&'schema str>,
+    pub implemented_by: BTreeSet<&'schema str>,
     /// The name of the interface. Should match 1-to-1 to its name in the GraphQL schema.
     pub name: &'schema str,
     /// The interface's fields. Analogous to object fields.
@@ -31,17 +34,22 @@ impl<'schema> GqlInterface<'schema> {
     /// filters the selection to keep only the fields that refer to the interface's own.
     ///
     /// This does not include the __typename field because it is translated into the `on` enum.
+    /// Inline fragments on the interface itself are merged into the selection.
     fn object_selection<'query>(
         &self,
-        selection: &'query Selection<'query>,
+        selection: &Selection<'query>,
         query_context: &QueryContext<'_, '_>
     ) -> Selection<'query> {
-        (&selection)
+        selection
+            .flatten_inline_fragments(query_context, self.name)
+            .0
             .into_iter()
             // Only keep what we can handle
             .filter(|f| match f {
                 SelectionItem::Field(f) => f.name != TYPENAME_FIELD,
-                SelectionItem::FragmentSpread(SelectionFragmentSpread { fragment_name }) => {
+                SelectionItem::FragmentSpread(SelectionFragmentSpread {
+                    fragment_name, ..
+                }) => {
                     // only if the fragment refers to the interface’s own fields (to take into account type-refining fragments)
                     let fragment = query_context
                         .fragments
@@ -54,23 +62,27 @@ impl<'schema> GqlInterface<'schema> {
 
                     fragment.on.name() == self.name
                 }
+                // What remains are inline fragments on the implementing types
                 SelectionItem::InlineFragment(_) => false
             })
-            .map(|a| (*a).clone())
             .collect()
     }
 
     fn union_selection<'query>(
         &self,
-        selection: &'query Selection<'_>,
+        selection: &Selection<'query>,
         query_context: &QueryContext<'_, '_>
     ) -> Selection<'query> {
-        (&selection)
+        selection
+            .flatten_inline_fragments(query_context, self.name)
+            .0
             .into_iter()
             // Only keep what we can handle
             .filter(|f| match f {
                 SelectionItem::InlineFragment(_) => true,
-                SelectionItem::FragmentSpread(SelectionFragmentSpread { fragment_name }) => {
+                SelectionItem::FragmentSpread(SelectionFragmentSpread {
+                    fragment_name, ..
+                }) => {
                     let fragment = query_context
                         .fragments
                         .get(fragment_name)
@@ -85,7 +97,6 @@ impl<'schema> GqlInterface<'schema> {
                 }
                 SelectionItem::Field(SelectionField { name, .. }) => *name == "__typename"
             })
-            .map(|a| (*a).clone())
             .collect()
     }
 
@@ -97,7 +108,7 @@ impl<'schema> GqlInterface<'schema> {
         GqlInterface {
             name,
             description,
-            implemented_by: HashSet::new(),
+            implemented_by: BTreeSet::new(),
             fields: vec![],
             is_required: false.into()
         }
@@ -109,6 +120,7 @@ impl<'schema> GqlInterface<'schema> {
         selection: &Selection<'_>
     ) -> Result<Vec<String>, CodegenError> {
         crate::shared::typescript_definitions_for_selection(
+            self.name,
             &self.fields,
             context,
             &self.object_selection(selection, context)
@@ -123,6 +135,7 @@ impl<'schema> GqlInterface<'schema> {
         prefix: &str
     ) -> Result<(Vec<TokenStream>, HashSet<String>), CodegenError> {
         crate::shared::field_impls_for_selection(
+            self.name,
             &self.fields,
             context,
             &self.object_selection(selection, context),
@@ -151,7 +164,7 @@ impl<'schema> GqlInterface<'schema> {
         context: &QueryContext<'_, '_>,
         selection: &Selection<'_>,
         prefix: &str
-    ) -> Result<(Vec<TokenStream>, Vec<TokenStream>), CodegenError> {
+    ) -> Result<(TokenStream, Vec<TokenStream>), CodegenError> {
         response_fields_for_selection(
             &self.name,
             &self.fields,
@@ -167,6 +180,7 @@ impl<'schema> GqlInterface<'schema> {
         selection: &Selection<'_>,
         prefix: &str
     ) -> Result<String, CodegenError> {
+        let selection = &selection.flatten_inline_fragments(query_context, self.name);
         selection.extract_typename(query_context).ok_or_else(|| {
             CodegenError::InternalError(format!(
                 "Missing __typename in selection for the {} interface (type: {})",
@@ -274,6 +288,7 @@ impl<'schema> GqlInterface<'schema> {
             quote!()
         };
 
+        let selection = &selection.flatten_inline_fragments(query_context, self.name);
         selection.extract_typename(query_context).ok_or_else(|| {
             CodegenError::InternalError(format!(
                 "Missing __typename in selection for the {} interface (type: {})",
@@ -281,7 +296,7 @@ impl<'schema> GqlInterface<'schema> {
             ))
         })?;
 
-        let (selection_fields, object_fields) =
+        let (object_selection, object_fields) =
             self.response_fields_for_selection(query_context, &selection, prefix)?;
 
         let (object_children, _) =
@@ -301,6 +316,30 @@ impl<'schema> GqlInterface<'schema> {
             }
         }
 
+        // The fields selected on the implementing types depend on the typename.
+        let variant_selections: Vec<_> = used_variants
+            .iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|variant| {
+                let variant_type =
+                    Ident::new(&format!("{}On{}", prefix, variant), Span::call_site());
+                quote!(#variant => #variant_type::selection(variables))
+            })
+            .collect();
+        let selection_fn_body = if variant_selections.is_empty() {
+            object_selection
+        } else {
+            quote! {
+                let mut selection = #object_selection;
+                selection.extend(match typename {
+                    #(#variant_selections,)*
+                    _ => Vec::new()
+                });
+                selection
+            }
+        };
+
         // Add the non-selected variants to the generated enum's variants.
         union_variants.extend(
             self.implemented_by
@@ -346,10 +385,8 @@ impl<'schema> GqlInterface<'schema> {
 
             impl #name {
                 #[allow(unused_variables)]
-                fn selection(variables: &Variables) -> Vec<::artemis::codegen::FieldSelector> {
-                    vec![
-                        #(#selection_fields,)*
-                    ]
+                pub(crate) fn selection(typename: &str, variables: &Variables) -> Vec<::artemis::codegen::FieldSelector> {
+                    #selection_fn_body
                 }
             }
         };
@@ -367,7 +404,7 @@ mod tests {
     fn union_selection_works() {
         let iface = GqlInterface {
             description: None,
-            implemented_by: HashSet::new(),
+            implemented_by: BTreeSet::new(),
             name: "MyInterface",
             fields: vec![],
             is_required: Cell::new(true)
@@ -381,7 +418,9 @@ mod tests {
                 alias: None,
                 name: "__typename",
                 fields: Selection::new_empty(),
-                arguments: Vec::new()
+                arguments: Vec::new(),
+                client: false,
+                conditions: Vec::new()
             });
         let selection = Selection::from_vec(vec![typename_field.clone()]);
 
@@ -396,7 +435,7 @@ mod tests {
     fn object_selection_works() {
         let iface = GqlInterface {
             description: None,
-            implemented_by: HashSet::new(),
+            implemented_by: BTreeSet::new(),
             name: "MyInterface",
             fields: vec![],
             is_required: Cell::new(true)
@@ -410,7 +449,9 @@ mod tests {
                 alias: None,
                 name: "__typename",
                 fields: Selection::new_empty(),
-                arguments: Vec::new()
+                arguments: Vec::new(),
+                client: false,
+                conditions: Vec::new()
             });
         let selection: Selection<'_> = vec![typename_field].into_iter().collect();
 
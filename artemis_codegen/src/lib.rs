@@ -11,6 +11,7 @@ use lazy_static::*; // TODO: Need to use later
 use proc_macro2::TokenStream;
 use quote::*;
 
+mod builders;
 mod codegen;
 mod codegen_options;
 /// Deprecation-related code
@@ -20,10 +21,13 @@ mod query;
 pub mod schema;
 
 mod constants;
+mod document;
 mod enums;
 mod field_type;
 mod fragments;
 mod generated_module;
+/// The `graphql!` macro, for queries embedded in Rust code.
+pub mod inline;
 mod inputs;
 mod interfaces;
 mod introspection_response;
@@ -36,6 +40,7 @@ mod selection;
 mod shared;
 mod unions;
 mod utils;
+mod validation;
 mod variables;
 ///
 pub mod wasm;
@@ -206,41 +211,160 @@ pub fn generate_module_token_stream(
     schema_path: &std::path::Path,
     options: GraphQLClientCodegenOptions
 ) -> Result<(TokenStream, Vec<(syn::Ident, u32)>), CodegenError> {
-    use std::collections::hash_map;
     // We need to qualify the query with the path to the crate it is part of
-    let (query_string, query) = {
-        let mut lock = QUERY_CACHE.lock().expect("query cache is poisoned");
-        match lock.entry(query_path) {
-            hash_map::Entry::Occupied(o) => o.get().clone(),
-            hash_map::Entry::Vacant(v) => {
-                let query_string = read_file(v.key())?;
-                let query = graphql_parser::parse_query(&query_string)
-                    .map_err(CodegenError::QueryParsingError)?;
-                v.insert((query_string, query)).clone()
-            }
+    let (_, query) = parse_query_file(query_path)?;
+
+    let shared_fragments = parse_fragment_files(&options)?;
+    let (parsed_schema, client_schemas) = parse_schema(schema_path, &options)?;
+    let schema = build_schema(&parsed_schema, &client_schemas)?;
+
+    generate_modules(&query, &shared_fragments, &schema, options)
+}
+
+/// Generates the shared `fragments` module for the fragment files in the options. Query modules
+/// generated with the same options refer to the fragment types in it.
+pub fn generate_fragments_token_stream(
+    schema_path: &std::path::Path,
+    options: GraphQLClientCodegenOptions
+) -> Result<TokenStream, CodegenError> {
+    let shared_fragments = parse_fragment_files(&options)?;
+    let (parsed_schema, client_schemas) = parse_schema(schema_path, &options)?;
+    let schema = build_schema(&parsed_schema, &client_schemas)?;
+
+    codegen::response_for_fragments(&schema, &shared_fragments, &options)
+}
+
+/// Generates the shared `types` module with the enums, input objects and custom scalars used by
+/// any operation in the query files or by the shared fragments. Query modules generated with
+/// `shared_types` set import them from it.
+pub fn generate_types_token_stream(
+    query_paths: &[std::path::PathBuf],
+    schema_path: &std::path::Path,
+    options: GraphQLClientCodegenOptions
+) -> Result<TokenStream, CodegenError> {
+    let shared_fragments = parse_fragment_files(&options)?;
+    let (parsed_schema, client_schemas) = parse_schema(schema_path, &options)?;
+    let schema = build_schema(&parsed_schema, &client_schemas)?;
+
+    // Generating the code for each module marks the types it needs as required in the schema.
+    if !shared_fragments.is_empty() {
+        codegen::response_for_fragments(&schema, &shared_fragments, &options)?;
+    }
+    for query_path in query_paths {
+        let (_, query) = parse_query_file(query_path.clone())?;
+        for operation in codegen::all_operations(&query) {
+            codegen::response_for_query(&schema, &query, &shared_fragments, &operation, &options)?;
         }
-    };
+    }
 
+    codegen::response_for_types(&schema, &options)
+}
+
+/// Reads and parses a query document, or gets it from the cache.
+fn parse_query_file(
+    query_path: std::path::PathBuf
+) -> Result<(String, graphql_parser::query::Document), CodegenError> {
+    use std::collections::hash_map;
+
+    let mut lock = QUERY_CACHE.lock().expect("query cache is poisoned");
+    match lock.entry(query_path) {
+        hash_map::Entry::Occupied(o) => Ok(o.get().clone()),
+        hash_map::Entry::Vacant(v) => {
+            let query_string = read_file(v.key())?;
+            let query = graphql_parser::parse_query(&query_string)
+                .map_err(CodegenError::QueryParsingError)?;
+            Ok(v.insert((query_string, query)).clone())
+        }
+    }
+}
+
+/// Reads and parses the shared fragment files.
+fn parse_fragment_files(
+    options: &GraphQLClientCodegenOptions
+) -> Result<Vec<graphql_parser::query::Document>, CodegenError> {
+    options
+        .fragment_files()
+        .iter()
+        .map(|path| parse_query_file(path.clone()).map(|(_, query)| query))
+        .collect()
+}
+
+/// Generates the modules for the operations in an already parsed query document.
+pub(crate) fn generate_modules(
+    query: &graphql_parser::query::Document,
+    shared_fragments: &[graphql_parser::query::Document],
+    schema: &schema::Schema<'_>,
+    options: GraphQLClientCodegenOptions
+) -> Result<(TokenStream, Vec<(syn::Ident, u32)>), CodegenError> {
     // Determine which operation we are generating code for. This will be used in operationName.
     let operations = options
         .operation_name
         .as_ref()
         .and_then(|operation_name| {
-            codegen::select_operation(&query, &operation_name, options.normalization())
+            codegen::select_operation(query, &operation_name, options.normalization())
         })
         .map(|op| vec![op]);
 
     let operations = match (operations, &options.mode) {
         (Some(ops), _) => ops,
-        (None, &CodegenMode::Cli) => codegen::all_operations(&query),
+        (None, &CodegenMode::Cli) => codegen::all_operations(query),
         (None, &CodegenMode::Derive) => {
             return Err(derive_operation_not_found_error(
                 options.struct_ident(),
-                &query
+                query
             ));
         }
     };
 
+    // The generated modules.
+    let mut modules = Vec::with_capacity(operations.len());
+    let mut variants = if options.wasm_bindgen {
+        Vec::with_capacity(operations.len())
+    } else {
+        Vec::new()
+    };
+
+    for operation in &operations {
+        // Each operation is sent on its own, with the fragments it uses.
+        let query_string = &document::operation_document(query, shared_fragments, operation);
+
+        if options.wasm_bindgen {
+            let operation_name_ident = options.normalization().operation(&operation.name);
+            let key = hash(query_string);
+            let variant_ident = syn::Ident::new(&operation_name_ident, Span::call_site());
+            variants.push((variant_ident, key));
+        }
+
+        let generated = generated_module::GeneratedModule {
+            query_string,
+            schema,
+            query_document: query,
+            shared_fragments,
+            operation,
+            options: &options
+        }
+        .to_token_stream()?;
+        modules.push(generated);
+    }
+
+    let modules = quote! { #(#modules)* };
+
+    Ok((modules, variants))
+}
+
+/// Reads and parses the schema and any client schema extensions.
+pub(crate) fn parse_schema(
+    schema_path: &std::path::Path,
+    options: &GraphQLClientCodegenOptions
+) -> Result<
+    (
+        schema::ParsedSchema,
+        Vec<graphql_parser::schema::Document>
+    ),
+    CodegenError
+> {
+    use std::collections::hash_map;
+
     let schema_extension = schema_path
         .extension()
         .and_then(std::ffi::OsStr::to_str)
@@ -270,38 +394,29 @@ pub fn generate_module_token_stream(
                         extension => panic!("Unsupported extension for the GraphQL schema: {} (only .json and .graphql are supported)", extension)
                     };
 
-    let schema = schema::Schema::from(&parsed_schema);
-
-    // The generated modules.
-    let mut modules = Vec::with_capacity(operations.len());
-    let mut variants = if options.wasm_bindgen {
-        Vec::with_capacity(operations.len())
-    } else {
-        Vec::new()
-    };
+    let client_schemas = options
+        .client_schema_files()
+        .iter()
+        .map(|path| {
+            graphql_parser::schema::parse_schema(&read_file(path)?)
+                .map_err(CodegenError::SchemaParsingError)
+        })
+        .collect::<Result<Vec<_>, CodegenError>>()?;
 
-    for operation in &operations {
-        if options.wasm_bindgen {
-            let operation_name_ident = options.normalization().operation(&operation.name);
-            let key = hash(query_string.as_str());
-            let variant_ident = syn::Ident::new(&operation_name_ident, Span::call_site());
-            variants.push((variant_ident, key));
-        }
+    Ok((parsed_schema, client_schemas))
+}
 
-        let generated = generated_module::GeneratedModule {
-            query_string: query_string.as_str(),
-            schema: &schema,
-            query_document: &query,
-            operation,
-            options: &options
-        }
-        .to_token_stream()?;
-        modules.push(generated);
+/// Builds the schema, merging in the client schema extensions.
+pub(crate) fn build_schema<'schema>(
+    parsed_schema: &'schema schema::ParsedSchema,
+    client_schemas: &'schema [graphql_parser::schema::Document]
+) -> Result<schema::Schema<'schema>, CodegenError> {
+    let mut schema = schema::Schema::from(parsed_schema);
+    for client_schema in client_schemas {
+        schema.ingest_document(client_schema)?;
     }
 
-    let modules = quote! { #(#modules)* };
-
-    Ok((modules, variants))
+    Ok(schema)
 }
 
 fn read_file(path: &std::path::Path) -> Result<String, CodegenError> {
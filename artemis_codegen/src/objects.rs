@@ -56,6 +56,17 @@ fn parse_deprecation_info(field: &schema::Field) -> DeprecationStatus {
     }
 }
 
+impl<'schema> GqlObjectField<'schema> {
+    pub(crate) fn from_graphql_parser_field(field: &'schema schema::Field) -> Self {
+        GqlObjectField {
+            description: field.description.as_deref(),
+            name: &field.name,
+            type_: FieldType::from(&field.field_type),
+            deprecation: parse_deprecation_info(field)
+        }
+    }
+}
+
 impl<'schema> GqlObject<'schema> {
     pub fn new(name: &'schema str, description: Option<&'schema str>) -> GqlObject<'schema> {
         GqlObject {
@@ -69,15 +80,11 @@ impl<'schema> GqlObject<'schema> {
     pub fn from_graphql_parser_object(obj: &'schema schema::ObjectType) -> Self {
         let description = obj.description.as_deref();
         let mut item = GqlObject::new(&obj.name, description);
-        item.fields.extend(obj.fields.iter().map(|f| {
-            let deprecation = parse_deprecation_info(&f);
-            GqlObjectField {
-                description: f.description.as_deref(),
-                name: &f.name,
-                type_: FieldType::from(&f.field_type),
-                deprecation
-            }
-        }));
+        item.fields.extend(
+            obj.fields
+                .iter()
+                .map(GqlObjectField::from_graphql_parser_field)
+        );
         item
     }
 
@@ -192,7 +199,7 @@ impl<'schema> GqlObject<'schema> {
             quote!()
         };
         let name = Ident::new(prefix, Span::call_site());
-        let (field_infos, fields) =
+        let (field_selection, fields) =
             self.response_fields_for_selection(query_context, selection, prefix)?;
         let (field_impls, types) =
             self.field_impls_for_selection(query_context, selection, &prefix)?;
@@ -209,11 +216,10 @@ impl<'schema> GqlObject<'schema> {
             }
 
             impl #name {
+                // Shared fragments are selected from the query modules
                 #[allow(unused_variables)]
-                fn selection(variables: &Variables) -> Vec<::artemis::codegen::FieldSelector> {
-                    vec![
-                        #(#field_infos),*
-                    ]
+                pub(crate) fn selection(variables: &Variables) -> Vec<::artemis::codegen::FieldSelector> {
+                    #field_selection
                 }
             }
         };
@@ -226,7 +232,7 @@ impl<'schema> GqlObject<'schema> {
         selection: &Selection<'_>,
         prefix: &str
     ) -> Result<(Vec<TokenStream>, HashSet<String>), CodegenError> {
-        field_impls_for_selection(&self.fields, query_context, selection, prefix)
+        field_impls_for_selection(self.name, &self.fields, query_context, selection, prefix)
     }
 
     pub(crate) fn typescript_definitions_for_selection(
@@ -234,7 +240,7 @@ impl<'schema> GqlObject<'schema> {
         query_context: &QueryContext<'_, '_>,
         selection: &Selection<'_>
     ) -> Result<Vec<String>, CodegenError> {
-        typescript_definitions_for_selection(&self.fields, query_context, selection)
+        typescript_definitions_for_selection(self.name, &self.fields, query_context, selection)
     }
 
     pub(crate) fn typescript_fields_for_selection(
@@ -251,7 +257,7 @@ impl<'schema> GqlObject<'schema> {
         query_context: &QueryContext<'_, '_>,
         selection: &Selection<'_>,
         prefix: &str
-    ) -> Result<(Vec<TokenStream>, Vec<TokenStream>), CodegenError> {
+    ) -> Result<(TokenStream, Vec<TokenStream>), CodegenError> {
         response_fields_for_selection(&self.name, &self.fields, query_context, selection, prefix)
     }
 }
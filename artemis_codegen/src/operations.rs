@@ -1,4 +1,10 @@
-use crate::{constants::*, query::QueryContext, selection::Selection, variables::Variable};
+use crate::{
+    builders::{builder, BuilderField},
+    constants::*,
+    query::QueryContext,
+    selection::Selection,
+    variables::Variable
+};
 use graphql_parser::query::OperationDefinition;
 use heck::SnakeCase;
 use proc_macro2::{Span, TokenStream};
@@ -49,7 +55,11 @@ impl<'query> Operation<'query> {
             .iter()
             .map(|variable| {
                 let ty = variable.ty.to_typescript(context, "");
-                format!("{}: {}", variable.name, ty)
+                if variable.ty.is_maybe_undefined(context) {
+                    format!("{}?: {}", variable.name, ty)
+                } else {
+                    format!("{}: {}", variable.name, ty)
+                }
             })
             .collect();
 
@@ -90,16 +100,32 @@ impl<'query> Operation<'query> {
             };
         }
 
-        let fields = variables.iter().map(|variable| {
-            let ty = variable.ty.to_rust(context, "");
-            let rust_safe_field_name =
-                crate::shared::keyword_replace(&variable.name.to_snake_case());
-            let rename =
-                crate::shared::field_rename_annotation(&variable.name, &rust_safe_field_name);
-            let name = Ident::new(&rust_safe_field_name, Span::call_site());
+        let mut builder_fields = Vec::with_capacity(variables.len());
+        let fields: Vec<_> = variables
+            .iter()
+            .map(|variable| {
+                let ty = variable.ty.to_rust_input(context, false);
+                let serde_attribute = variable.ty.input_serde_attribute(context);
+                let rust_safe_field_name =
+                    crate::shared::keyword_replace(&variable.name.to_snake_case());
+                let rename =
+                    crate::shared::field_rename_annotation(variable.name, &rust_safe_field_name);
+                let name = Ident::new(&rust_safe_field_name, Span::call_site());
 
-            quote!(#rename pub #name: #ty)
-        });
+                let field = quote!(#rename #serde_attribute pub #name: #ty);
+                builder_fields.push(BuilderField {
+                    graphql_name: variable.name.to_string(),
+                    name,
+                    ty,
+                    default: variable
+                        .default_value_constructor_name()
+                        .map(|constructor| quote!(Variables::#constructor())),
+                    required: !variable.ty.is_optional()
+                });
+                field
+            })
+            .collect();
+        let builder = builder("Variables", &builder_fields);
 
         let default_constructors = variables
             .iter()
@@ -115,6 +141,8 @@ impl<'query> Operation<'query> {
             impl Variables {
                 #(#default_constructors)*
             }
+
+            #builder
         }
     }
 }
@@ -4,6 +4,7 @@ use crate::{
     normalization::Normalization,
     schema::{Schema, DEFAULT_SCALARS},
     selection::Selection,
+    variables::Variable,
     CodegenError
 };
 use proc_macro2::{Span, TokenStream};
@@ -20,7 +21,11 @@ pub(crate) struct QueryContext<'query, 'schema: 'query> {
     pub include_query_info: bool,
     pub wasm_bindgen: bool,
     pub variables_derives: Vec<Ident>,
-    pub response_derives: Vec<Ident>
+    pub response_derives: Vec<Ident>,
+    /// The variables of the operation, used to evaluate `@skip` and `@include`.
+    pub variables: Vec<Variable<'query>>,
+    /// Whether nullable variables and input fields can be left out, see `MaybeUndefined`.
+    pub maybe_undefined: bool
 }
 
 impl<'query, 'schema> QueryContext<'query, 'schema> {
@@ -46,7 +51,9 @@ impl<'query, 'schema> QueryContext<'query, 'schema> {
                 Ident::new("Deserialize", Span::call_site()),
                 Ident::new("Clone", Span::call_site()),
             ],
-            wasm_bindgen
+            wasm_bindgen,
+            variables: Vec::new(),
+            maybe_undefined: false
         }
     }
 
@@ -75,7 +82,9 @@ impl<'query, 'schema> QueryContext<'query, 'schema> {
                 Ident::new("Deserialize", Span::call_site()),
                 Ident::new("Clone", Span::call_site()),
             ],
-            wasm_bindgen: false
+            wasm_bindgen: false,
+            variables: Vec::new(),
+            maybe_undefined: false
         }
     }
 
@@ -132,6 +141,10 @@ impl<'query, 'schema> QueryContext<'query, 'schema> {
         self.schema.unions.contains_key(&name)
     }
 
+    pub(crate) fn is_interface(&self, name: &str) -> bool {
+        self.schema.interfaces.contains_key(&name)
+    }
+
     /// Expand the deserialization data structures for the given field.
     pub(crate) fn maybe_expand_field(
         &self,
@@ -1,4 +1,4 @@
-use crate::normalization::Normalization;
+use crate::{normalization::Normalization, CodegenError, GraphQLClientCodegenOptions};
 use quote::quote;
 use std::cell::Cell;
 
@@ -9,15 +9,115 @@ pub struct Scalar<'schema> {
     pub is_required: Cell<bool>
 }
 
+/// The Rust types of common custom scalars. Each mapping is enabled by a cargo feature, and the
+/// crate using the generated code needs to depend on the crate the type comes from.
+fn builtin_scalar(name: &str) -> Option<&'static str> {
+    match name {
+        #[cfg(feature = "chrono")]
+        "DateTime" => Some("::chrono::DateTime<::chrono::Utc>"),
+        #[cfg(feature = "chrono")]
+        "Date" => Some("::chrono::NaiveDate"),
+        #[cfg(feature = "uuid")]
+        "UUID" | "Uuid" => Some("::uuid::Uuid"),
+        #[cfg(feature = "json")]
+        "JSON" | "Json" => Some("::serde_json::Value"),
+        // Servers send these as strings so they don't lose precision in JavaScript clients
+        #[cfg(feature = "bigint")]
+        "BigInt" => Some("String"),
+        #[cfg(feature = "decimal")]
+        "Decimal" => Some("::rust_decimal::Decimal"),
+        #[cfg(feature = "url")]
+        "URL" | "Url" => Some("::url::Url"),
+        _ => None
+    }
+}
+
 impl<'schema> Scalar<'schema> {
-    // TODO: do something smarter here
-    pub fn to_rust(&self, norm: Normalization) -> proc_macro2::TokenStream {
+    pub fn to_rust(
+        &self,
+        norm: Normalization,
+        options: &GraphQLClientCodegenOptions,
+        visibility: &proc_macro2::TokenStream
+    ) -> Result<proc_macro2::TokenStream, CodegenError> {
         use proc_macro2::{Ident, Span};
 
         let name = norm.scalar_name(self.name);
         let ident = Ident::new(&name, Span::call_site());
         let description = &self.description.map(|d| quote!(#[doc = #d]));
 
-        quote!(#description type #ident = crate::#ident;)
+        let rust_type = options
+            .scalars()
+            .get(self.name)
+            .map(String::as_str)
+            .or_else(|| builtin_scalar(self.name));
+        let rust_type = match rust_type {
+            Some(rust_type) => syn::parse_str::<syn::Type>(rust_type).map_err(|e| {
+                CodegenError::InputError(format!(
+                    "Invalid Rust type `{}` for the custom scalar `{}`: {}",
+                    rust_type, self.name, e
+                ))
+            })?,
+            None if options.crate_scalars => syn::parse_quote!(crate::#ident),
+            None => {
+                return Err(CodegenError::TypeError(format!(
+                    "No Rust type for the custom scalar `{}`. Map it to one with \
+                     `CodegenBuilder::with_scalar(\"{}\", \"path::to::Type\")`.",
+                    self.name, self.name
+                )))
+            }
+        };
+
+        Ok(quote!(#description #visibility type #ident = #rust_type;))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CodegenMode;
+
+    fn long_scalar() -> Scalar<'static> {
+        Scalar {
+            name: "Long",
+            description: None,
+            is_required: Cell::new(true)
+        }
+    }
+
+    #[test]
+    fn mapped_scalar() {
+        let mut options = GraphQLClientCodegenOptions::new(CodegenMode::Cli);
+        options.add_scalar("Long".to_string(), "i64".to_string());
+
+        let tokens = long_scalar()
+            .to_rust(Normalization::None, &options, &quote!())
+            .unwrap();
+
+        assert_eq!(tokens.to_string(), "type Long = i64 ;");
+
+        let tokens = long_scalar()
+            .to_rust(Normalization::None, &options, &quote!(pub))
+            .unwrap();
+
+        assert_eq!(tokens.to_string(), "pub type Long = i64 ;");
+    }
+
+    #[test]
+    fn unmapped_scalar() {
+        let mut options = GraphQLClientCodegenOptions::new(CodegenMode::Cli);
+        let error = long_scalar()
+            .to_rust(Normalization::None, &options, &quote!())
+            .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("No Rust type for the custom scalar `Long`"));
+
+        options.crate_scalars = true;
+        let tokens = long_scalar()
+            .to_rust(Normalization::None, &options, &quote!())
+            .unwrap();
+
+        assert_eq!(tokens.to_string(), "type Long = crate :: Long ;");
     }
 }
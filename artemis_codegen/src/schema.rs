@@ -54,7 +54,7 @@ impl<'schema> Schema<'schema> {
                 let iface = self.interfaces.get_mut(&iface_name).ok_or_else(|| {
                     CodegenError::TypeError(format!("interface not found: {}", iface_name))
                 })?;
-                iface.implemented_by = implementors.iter().cloned().collect();
+                iface.implemented_by.extend(implementors);
                 Ok(())
             })
             .collect()
@@ -110,10 +110,23 @@ impl<'schema> Schema<'schema> {
 impl<'schema> std::convert::From<&'schema graphql_parser::schema::Document> for Schema<'schema> {
     fn from(ast: &'schema graphql_parser::schema::Document) -> Schema<'schema> {
         let mut schema = Schema::new();
+        schema.ingest_document(ast).expect("schema ingestion");
+        schema
+    }
+}
 
+impl<'schema> Schema<'schema> {
+    /// Adds the definitions and type extensions of a GraphQL schema document to the schema.
+    /// This is also used to merge client-only schema extensions into the server schema.
+    pub(crate) fn ingest_document(
+        &mut self,
+        ast: &'schema graphql_parser::schema::Document
+    ) -> Result<(), CodegenError> {
         // Holds which objects implement which interfaces so we can populate GqlInterface#implemented_by later.
         // It maps interface names to a vec of implementation names.
         let mut interface_implementations: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        // Extensions are applied last, since they may come before the type they extend.
+        let mut extensions = Vec::new();
 
         for definition in &ast.definitions {
             match definition {
@@ -127,12 +140,11 @@ impl<'schema> std::convert::From<&'schema graphql_parser::schema::Document> for
                                 .or_insert_with(|| vec![name]);
                         }
 
-                        schema
-                            .objects
+                        self.objects
                             .insert(&obj.name, GqlObject::from_graphql_parser_object(&obj));
                     }
                     schema::TypeDefinition::Enum(enm) => {
-                        schema.enums.insert(
+                        self.enums.insert(
                             &enm.name,
                             GqlEnum {
                                 name: &enm.name,
@@ -150,7 +162,7 @@ impl<'schema> std::convert::From<&'schema graphql_parser::schema::Document> for
                         );
                     }
                     schema::TypeDefinition::Scalar(scalar) => {
-                        schema.scalars.insert(
+                        self.scalars.insert(
                             &scalar.name,
                             Scalar {
                                 name: &scalar.name,
@@ -162,7 +174,7 @@ impl<'schema> std::convert::From<&'schema graphql_parser::schema::Document> for
                     schema::TypeDefinition::Union(union) => {
                         let variants: BTreeSet<&str> =
                             union.types.iter().map(String::as_str).collect();
-                        schema.unions.insert(
+                        self.unions.insert(
                             &union.name,
                             GqlUnion {
                                 name: &union.name,
@@ -183,27 +195,91 @@ impl<'schema> std::convert::From<&'schema graphql_parser::schema::Document> for
                                 type_: FieldType::from(&f.field_type),
                                 deprecation: DeprecationStatus::Current
                             }));
-                        schema.interfaces.insert(&interface.name, iface);
+                        self.interfaces.insert(&interface.name, iface);
                     }
                     schema::TypeDefinition::InputObject(input) => {
-                        schema.inputs.insert(&input.name, GqlInput::from(input));
+                        self.inputs.insert(&input.name, GqlInput::from(input));
                     }
                 },
                 schema::Definition::DirectiveDefinition(_) => (),
-                schema::Definition::TypeExtension(_extension) => (),
+                schema::Definition::TypeExtension(extension) => extensions.push(extension),
                 schema::Definition::SchemaDefinition(definition) => {
-                    schema.query_type = definition.query.as_deref();
-                    schema.mutation_type = definition.mutation.as_deref();
-                    schema.subscription_type = definition.subscription.as_deref();
+                    self.query_type = definition.query.as_deref();
+                    self.mutation_type = definition.mutation.as_deref();
+                    self.subscription_type = definition.subscription.as_deref();
                 }
             }
         }
 
-        schema
-            .ingest_interface_implementations(interface_implementations)
-            .expect("schema ingestion");
+        self.ingest_interface_implementations(interface_implementations)?;
+        extensions
+            .into_iter()
+            .try_for_each(|extension| self.ingest_type_extension(extension))
+    }
 
-        schema
+    fn ingest_type_extension(
+        &mut self,
+        extension: &'schema schema::TypeExtension
+    ) -> Result<(), CodegenError> {
+        let unknown_type =
+            |name: &str| CodegenError::TypeError(format!("cannot extend unknown type: {}", name));
+
+        match extension {
+            schema::TypeExtension::Object(ext) => {
+                let object = self
+                    .objects
+                    .get_mut(ext.name.as_str())
+                    .ok_or_else(|| unknown_type(&ext.name))?;
+                object.fields.extend(
+                    ext.fields
+                        .iter()
+                        .map(GqlObjectField::from_graphql_parser_field)
+                );
+                let implementations = ext
+                    .implements_interfaces
+                    .iter()
+                    .map(|iface| (iface.as_str(), vec![object.name]))
+                    .collect();
+                self.ingest_interface_implementations(implementations)?;
+            }
+            schema::TypeExtension::Interface(ext) => {
+                let iface = self
+                    .interfaces
+                    .get_mut(ext.name.as_str())
+                    .ok_or_else(|| unknown_type(&ext.name))?;
+                iface.fields.extend(
+                    ext.fields
+                        .iter()
+                        .map(GqlObjectField::from_graphql_parser_field)
+                );
+            }
+            schema::TypeExtension::Enum(ext) => {
+                let enm = self
+                    .enums
+                    .get_mut(ext.name.as_str())
+                    .ok_or_else(|| unknown_type(&ext.name))?;
+                enm.variants.extend(ext.values.iter().map(|v| EnumVariant {
+                    description: v.description.as_deref(),
+                    name: &v.name
+                }));
+            }
+            schema::TypeExtension::Union(ext) => {
+                let union = self
+                    .unions
+                    .get_mut(ext.name.as_str())
+                    .ok_or_else(|| unknown_type(&ext.name))?;
+                union.variants.extend(ext.types.iter().map(String::as_str));
+            }
+            schema::TypeExtension::Scalar(_) => (),
+            schema::TypeExtension::InputObject(ext) => {
+                return Err(CodegenError::UnimplementedError(format!(
+                    "extending input object {}",
+                    ext.name
+                )))
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -1,27 +1,100 @@
 use crate::{constants::*, shared::ArgumentValue, CodegenError};
-use graphql_parser::query::SelectionSet;
+use graphql_parser::query::{Directive, SelectionSet, Value};
 use std::collections::BTreeMap;
 
+/// An `@include(if: ...)` or `@skip(if: ...)` directive on a field or fragment.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SelectionCondition {
+    Include(ArgumentValue),
+    Skip(ArgumentValue),
+    /// The field is selected more than once with different conditions. It's included if all
+    /// the conditions of any of the selections hold.
+    Any(Vec<Vec<SelectionCondition>>)
+}
+
+impl SelectionCondition {
+    /// The conditions set by `@include` and `@skip`, leaving out those that always hold.
+    fn from_directives(directives: &[Directive]) -> Vec<SelectionCondition> {
+        directives
+            .iter()
+            .filter_map(|directive| {
+                let (_, value) = directive
+                    .arguments
+                    .iter()
+                    .find(|(argument, _)| argument == "if")?;
+                match (directive.name.as_str(), value) {
+                    (INCLUDE_DIRECTIVE, Value::Boolean(true))
+                    | (SKIP_DIRECTIVE, Value::Boolean(false)) => None,
+                    (INCLUDE_DIRECTIVE, value) => {
+                        Some(SelectionCondition::Include(value.clone().into()))
+                    }
+                    (SKIP_DIRECTIVE, value) => Some(SelectionCondition::Skip(value.clone().into())),
+                    _ => None
+                }
+            })
+            .collect()
+    }
+
+    fn uses_variables(&self) -> bool {
+        match self {
+            SelectionCondition::Include(value) | SelectionCondition::Skip(value) => {
+                value.uses_variables()
+            }
+            SelectionCondition::Any(alternatives) => alternatives
+                .iter()
+                .flatten()
+                .any(SelectionCondition::uses_variables)
+        }
+    }
+
+    /// The conditions of a field selected twice, with conditions `a` and `b`.
+    fn merge(a: &[SelectionCondition], b: &[SelectionCondition]) -> Vec<SelectionCondition> {
+        if a.is_empty() || b.is_empty() {
+            return Vec::new();
+        }
+        if a == b {
+            return a.to_vec();
+        }
+
+        let mut alternatives = Vec::new();
+        for conditions in &[a, b] {
+            match conditions {
+                [SelectionCondition::Any(any)] => alternatives.extend(any.iter().cloned()),
+                conditions => alternatives.push(conditions.to_vec())
+            }
+        }
+        vec![SelectionCondition::Any(alternatives)]
+    }
+}
+
 /// A single object field as part of a selection.
 #[derive(Clone, Debug, PartialEq)]
 pub struct SelectionField<'query> {
     pub alias: Option<&'query str>,
     pub name: &'query str,
     pub fields: Selection<'query>,
-    pub arguments: Vec<(String, ArgumentValue)>
+    pub arguments: Vec<(String, ArgumentValue)>,
+    /// Whether the field has the `@client` directive and is resolved locally.
+    pub client: bool,
+    /// The field is only selected if all of these hold.
+    pub conditions: Vec<SelectionCondition>
 }
 
 /// A spread fragment in a selection (e.g. `...MyFragment`).
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct SelectionFragmentSpread<'query> {
-    pub fragment_name: &'query str
+    pub fragment_name: &'query str,
+    /// The fragment is only selected if all of these hold.
+    pub conditions: Vec<SelectionCondition>
 }
 
 /// An inline fragment as part of a selection (e.g. `...on MyThing { name }`).
 #[derive(Clone, Debug, PartialEq)]
 pub struct SelectionInlineFragment<'query> {
     pub on: &'query str,
-    pub fields: Selection<'query>
+    pub fields: Selection<'query>,
+    /// The fragment is only selected if all of these hold.
+    pub conditions: Vec<SelectionCondition>
 }
 
 /// An element in a query selection.
@@ -41,6 +114,21 @@ impl<'query> SelectionItem<'query> {
         }
         None
     }
+
+    fn conditions_mut(&mut self) -> &mut Vec<SelectionCondition> {
+        match self {
+            SelectionItem::Field(field) => &mut field.conditions,
+            SelectionItem::FragmentSpread(spread) => &mut spread.conditions,
+            SelectionItem::InlineFragment(inline_fragment) => &mut inline_fragment.conditions
+        }
+    }
+
+    /// The item, only selected if the conditions of the fragment containing it hold too.
+    pub(crate) fn with_conditions(&self, conditions: &[SelectionCondition]) -> Self {
+        let mut item = self.clone();
+        item.conditions_mut().extend(conditions.iter().cloned());
+        item
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -60,9 +148,9 @@ impl<'query> Selection<'query> {
         (&self)
             .into_iter()
             .filter_map(|f| match f {
-                SelectionItem::FragmentSpread(SelectionFragmentSpread { fragment_name }) => {
-                    Some(fragment_name)
-                }
+                SelectionItem::FragmentSpread(SelectionFragmentSpread {
+                    fragment_name, ..
+                }) => Some(fragment_name),
                 _ => None
             })
             .filter_map(|fragment_name| {
@@ -79,25 +167,45 @@ impl<'query> Selection<'query> {
         context: &'s crate::query::QueryContext<'_, '_>,
         selected_variants: &mut BTreeMap<&'s str, Selection<'s>>,
         // the name of the type the selection applies to
-        selection_on: &str
+        selection_on: &str,
+        // the conditions of the fragments containing the selection
+        conditions: &[SelectionCondition]
     ) -> Result<(), CodegenError> {
+        fn select_variant<'s>(
+            selected_variants: &mut BTreeMap<&'s str, Selection<'s>>,
+            on: &'s str,
+            selection: &Selection<'s>,
+            conditions: &[SelectionCondition]
+        ) {
+            let items = selection
+                .0
+                .iter()
+                .map(|item| item.with_conditions(conditions));
+            selected_variants
+                .entry(on)
+                .or_insert_with(|| Selection(Vec::with_capacity(selection.0.len())))
+                .0
+                .extend(items);
+        }
+
         for item in self.0.iter() {
             match item {
                 SelectionItem::Field(_) => (),
                 SelectionItem::InlineFragment(inline_fragment) => {
-                    selected_variants
-                        .entry(inline_fragment.on)
-                        .and_modify(|entry| entry.0.extend(inline_fragment.fields.0.clone()))
-                        .or_insert_with(|| {
-                            let mut items = Vec::with_capacity(inline_fragment.fields.0.len());
-                            items.extend(inline_fragment.fields.0.clone());
-                            Selection(items)
-                        });
+                    let conditions = [conditions, &inline_fragment.conditions].concat();
+                    select_variant(
+                        selected_variants,
+                        inline_fragment.on,
+                        &inline_fragment.fields,
+                        &conditions
+                    );
                 }
-                SelectionItem::FragmentSpread(SelectionFragmentSpread { fragment_name }) => {
+                SelectionItem::FragmentSpread(spread) => {
+                    let fragment_name = spread.fragment_name;
                     let fragment = context.fragments.get(fragment_name).ok_or_else(|| {
                         CodegenError::TypeError(format!("Unknown fragment: {}", &fragment_name))
                     })?;
+                    let conditions = [conditions, &spread.conditions].concat();
 
                     // The fragment can either be on the union/interface itself, or on one of its variants (type-refining fragment).
                     if fragment.on.name() == selection_on {
@@ -105,18 +213,17 @@ impl<'query> Selection<'query> {
                         fragment.selection.selected_variants_on_union_inner(
                             context,
                             selected_variants,
-                            selection_on
+                            selection_on,
+                            &conditions
                         )?;
                     } else {
                         // Type-refining fragment
-                        selected_variants
-                            .entry(fragment.on.name())
-                            .and_modify(|entry| entry.0.extend(fragment.selection.0.clone()))
-                            .or_insert_with(|| {
-                                let mut items = Vec::with_capacity(fragment.selection.0.len());
-                                items.extend(fragment.selection.0.clone());
-                                Selection(items)
-                            });
+                        select_variant(
+                            selected_variants,
+                            fragment.on.name(),
+                            &fragment.selection,
+                            &conditions
+                        );
                     }
                 }
             }
@@ -138,11 +245,104 @@ impl<'query> Selection<'query> {
     ) -> Result<BTreeMap<&'s str, Selection<'s>>, CodegenError> {
         let mut selected_variants = BTreeMap::new();
 
-        self.selected_variants_on_union_inner(context, &mut selected_variants, selection_on)?;
+        self.selected_variants_on_union_inner(context, &mut selected_variants, selection_on, &[])?;
 
         Ok(selected_variants)
     }
 
+    /// Merges the inline fragments that always apply to a selection on `type_name` into the
+    /// selection itself: those on the type, or on an interface or union it belongs to. Fields
+    /// selected more than once end up as a single field with the merged subselections.
+    ///
+    /// Inline fragments on other types are kept as is. On interfaces, they select the fields of
+    /// the implementing types; on objects, they never match.
+    pub(crate) fn flatten_inline_fragments(
+        &self,
+        context: &crate::query::QueryContext<'_, '_>,
+        type_name: &str
+    ) -> Selection<'query> {
+        let mut flattened = Selection(Vec::with_capacity(self.0.len()));
+        flattened.push_flattened(self, context, type_name, &[]);
+        flattened
+    }
+
+    // Implementation helper for `flatten_inline_fragments`.
+    fn push_flattened(
+        &mut self,
+        selection: &Selection<'query>,
+        context: &crate::query::QueryContext<'_, '_>,
+        type_name: &str,
+        // the conditions of the inline fragments containing the selection
+        conditions: &[SelectionCondition]
+    ) {
+        for item in selection.0.iter() {
+            let item = item.with_conditions(conditions);
+            match item {
+                SelectionItem::InlineFragment(inline_fragment)
+                    if type_condition_applies(context, inline_fragment.on, type_name) =>
+                {
+                    self.push_flattened(
+                        &inline_fragment.fields,
+                        context,
+                        type_name,
+                        &inline_fragment.conditions
+                    );
+                }
+                SelectionItem::Field(mut field) => {
+                    let key = field.alias.unwrap_or(field.name);
+                    let existing = self.0.iter_mut().find_map(|item| match item {
+                        SelectionItem::Field(existing)
+                            if existing.alias.unwrap_or(existing.name) == key =>
+                        {
+                            Some(existing)
+                        }
+                        _ => None
+                    });
+                    match existing {
+                        Some(existing) => {
+                            let conditions =
+                                SelectionCondition::merge(&existing.conditions, &field.conditions);
+                            // Subfields only selected by one of the fields keep its conditions
+                            for field in [&mut *existing, &mut field].iter_mut() {
+                                if field.conditions != conditions {
+                                    let field_conditions = field.conditions.clone();
+                                    for item in field.fields.0.iter_mut() {
+                                        item.conditions_mut().extend(field_conditions.clone());
+                                    }
+                                }
+                            }
+                            existing.conditions = conditions;
+                            existing.fields.0.extend(field.fields.0);
+                        }
+                        None => self.0.push(SelectionItem::Field(field))
+                    }
+                }
+                SelectionItem::FragmentSpread(spread) => {
+                    let existing = self.0.iter_mut().find_map(|item| match item {
+                        SelectionItem::FragmentSpread(existing)
+                            if existing.fragment_name == spread.fragment_name =>
+                        {
+                            Some(existing)
+                        }
+                        _ => None
+                    });
+                    match existing {
+                        Some(existing) => {
+                            existing.conditions =
+                                SelectionCondition::merge(&existing.conditions, &spread.conditions)
+                        }
+                        None => self.0.push(SelectionItem::FragmentSpread(spread))
+                    }
+                }
+                item => {
+                    if !self.0.contains(&item) {
+                        self.0.push(item);
+                    }
+                }
+            }
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn new_empty() -> Selection<'static> {
         Selection(Vec::new())
@@ -163,19 +363,72 @@ impl<'query> Selection<'query> {
         })
     }
 
+    /// Whether any of the selected fields or fragments use variables in their arguments or
+    /// `@skip`/`@include` conditions.
+    pub(crate) fn uses_variables(&self) -> bool {
+        self.0.iter().any(|item| match item {
+            SelectionItem::Field(field) => {
+                field
+                    .arguments
+                    .iter()
+                    .any(|(_, value)| value.uses_variables())
+                    || field
+                        .conditions
+                        .iter()
+                        .any(SelectionCondition::uses_variables)
+                    || field.fields.uses_variables()
+            }
+            SelectionItem::FragmentSpread(spread) => spread
+                .conditions
+                .iter()
+                .any(SelectionCondition::uses_variables),
+            SelectionItem::InlineFragment(inline_fragment) => {
+                inline_fragment
+                    .conditions
+                    .iter()
+                    .any(SelectionCondition::uses_variables)
+                    || inline_fragment.fields.uses_variables()
+            }
+        })
+    }
+
     pub(crate) fn len(&self) -> usize {
         self.0.len()
     }
 
     pub(crate) fn require_items<'s>(&self, context: &crate::query::QueryContext<'query, 's>) {
         self.0.iter().for_each(|item| {
-            if let SelectionItem::FragmentSpread(SelectionFragmentSpread { fragment_name }) = item {
+            if let SelectionItem::FragmentSpread(SelectionFragmentSpread {
+                fragment_name, ..
+            }) = item
+            {
                 context.require_fragment(fragment_name);
             }
         })
     }
 }
 
+/// Whether a fragment on `on` applies to every value of `type_name`.
+fn type_condition_applies(
+    context: &crate::query::QueryContext<'_, '_>,
+    on: &str,
+    type_name: &str
+) -> bool {
+    on == type_name
+        || context
+            .schema
+            .interfaces
+            .get(on)
+            .map(|iface| iface.implemented_by.contains(type_name))
+            .unwrap_or(false)
+        || context
+            .schema
+            .unions
+            .get(on)
+            .map(|unn| unn.variants.contains(type_name))
+            .unwrap_or(false)
+}
+
 impl<'query> std::convert::From<&'query SelectionSet> for Selection<'query> {
     fn from(selection_set: &SelectionSet) -> Selection<'_> {
         use graphql_parser::query::Selection;
@@ -193,22 +446,42 @@ impl<'query> std::convert::From<&'query SelectionSet> for Selection<'query> {
                         .iter()
                         .cloned()
                         .map(|(key, value)| (key, value.into()))
-                        .collect()
+                        .collect(),
+                    client: f
+                        .directives
+                        .iter()
+                        .any(|directive| directive.name == CLIENT_DIRECTIVE),
+                    conditions: SelectionCondition::from_directives(&f.directives)
                 }),
                 Selection::FragmentSpread(spread) => {
                     SelectionItem::FragmentSpread(SelectionFragmentSpread {
-                        fragment_name: &spread.fragment_name
+                        fragment_name: &spread.fragment_name,
+                        conditions: SelectionCondition::from_directives(&spread.directives)
                     })
                 }
                 Selection::InlineFragment(inline) => {
-                    let graphql_parser::query::TypeCondition::On(ref name) = inline
-                        .type_condition
-                        .as_ref()
-                        .expect("Missing `on` clause.");
-                    SelectionItem::InlineFragment(SelectionInlineFragment {
-                        on: &name,
-                        fields: (&inline.selection_set).into()
-                    })
+                    let conditions = SelectionCondition::from_directives(&inline.directives);
+                    match &inline.type_condition {
+                        Some(graphql_parser::query::TypeCondition::On(name)) => {
+                            SelectionItem::InlineFragment(SelectionInlineFragment {
+                                on: name,
+                                fields: (&inline.selection_set).into(),
+                                conditions
+                            })
+                        }
+                        // Without a type condition, the fragment always applies.
+                        None => {
+                            let fields: crate::selection::Selection<'_> =
+                                (&inline.selection_set).into();
+                            items.extend(
+                                fields
+                                    .0
+                                    .iter()
+                                    .map(|item| item.with_conditions(&conditions))
+                            );
+                            continue;
+                        }
+                    }
                 }
             };
             items.push(converted);
@@ -252,7 +525,8 @@ mod tests {
         selection
             .0
             .push(SelectionItem::FragmentSpread(SelectionFragmentSpread {
-                fragment_name: "MyFragment"
+                fragment_name: "MyFragment",
+                conditions: Vec::new()
             }));
 
         let mut fragment_selection = Selection::new_empty();
@@ -262,7 +536,9 @@ mod tests {
                 alias: None,
                 name: "__typename",
                 fields: Selection::new_empty(),
-                arguments: Vec::new()
+                arguments: Vec::new(),
+                client: false,
+                conditions: Vec::new()
             }));
 
         let schema = crate::schema::Schema::new();
@@ -274,7 +550,8 @@ mod tests {
                 name: "MyFragment",
                 on: crate::fragments::FragmentTarget::Object(&obj),
                 selection: fragment_selection,
-                is_required: std::cell::Cell::new(false)
+                is_required: std::cell::Cell::new(false),
+                shared: false
             }
         );
 
@@ -323,27 +600,35 @@ mod tests {
                 alias: None,
                 name: "animal",
                 arguments: Vec::new(),
+                client: false,
                 fields: Selection(vec![
                     SelectionItem::Field(SelectionField {
                         alias: None,
                         name: "isCat",
                         fields: Selection(Vec::new()),
-                        arguments: Vec::new()
+                        arguments: Vec::new(),
+                        client: false,
+                        conditions: Vec::new()
                     }),
                     SelectionItem::Field(SelectionField {
                         alias: None,
                         name: "isHorse",
                         fields: Selection(Vec::new()),
-                        arguments: Vec::new()
+                        arguments: Vec::new(),
+                        client: false,
+                        conditions: Vec::new()
                     }),
                     SelectionItem::FragmentSpread(SelectionFragmentSpread {
-                        fragment_name: "Timestamps"
+                        fragment_name: "Timestamps",
+                        conditions: Vec::new()
                     }),
                     SelectionItem::Field(SelectionField {
                         alias: None,
                         name: "barks",
                         fields: Selection(Vec::new()),
-                        arguments: Vec::new()
+                        arguments: Vec::new(),
+                        client: false,
+                        conditions: Vec::new()
                     }),
                     SelectionItem::InlineFragment(SelectionInlineFragment {
                         on: "Dog",
@@ -351,23 +636,157 @@ mod tests {
                             alias: None,
                             name: "rating",
                             fields: Selection(Vec::new()),
-                            arguments: Vec::new()
-                        })])
+                            arguments: Vec::new(),
+                            client: false,
+                            conditions: Vec::new()
+                        })]),
+                        conditions: Vec::new()
                     }),
                     SelectionItem::Field(SelectionField {
                         alias: None,
                         name: "pawsCount",
                         fields: Selection(Vec::new()),
-                        arguments: Vec::new()
+                        arguments: Vec::new(),
+                        client: false,
+                        conditions: Vec::new()
                     }),
                     SelectionItem::Field(SelectionField {
                         alias: Some("aliased"),
                         name: "sillyName",
                         fields: Selection(Vec::new()),
-                        arguments: Vec::new()
+                        arguments: Vec::new(),
+                        client: false,
+                        conditions: Vec::new()
                     }),
-                ])
+                ]),
+                conditions: Vec::new()
             })])
         );
     }
+
+    #[test]
+    fn flatten_inline_fragments_merges_fields() {
+        let query = r##"
+        query {
+          hero {
+            friends { name }
+            ... { id }
+            ... on Character { friends { id } }
+            ... on Droid { primaryFunction }
+          }
+        }
+        "##;
+        let parsed = graphql_parser::parse_query(query).unwrap();
+        let selection_set = match &parsed.definitions[0] {
+            graphql_parser::query::Definition::Operation(
+                graphql_parser::query::OperationDefinition::Query(q)
+            ) => &q.selection_set,
+            _ => unreachable!()
+        };
+        let selection: Selection<'_> = selection_set.into();
+        let hero = match &selection.0[0] {
+            SelectionItem::Field(hero) => &hero.fields,
+            _ => unreachable!()
+        };
+
+        let mut schema = crate::schema::Schema::new();
+        let mut iface = crate::interfaces::GqlInterface::new("Character", None);
+        iface.implemented_by.insert("Droid");
+        schema.interfaces.insert("Character", iface);
+        let context = crate::query::QueryContext::new_empty(&schema);
+
+        let field = |name, fields| {
+            SelectionItem::Field(SelectionField {
+                alias: None,
+                name,
+                fields,
+                arguments: Vec::new(),
+                client: false,
+                conditions: Vec::new()
+            })
+        };
+        assert_eq!(
+            hero.flatten_inline_fragments(&context, "Character"),
+            Selection(vec![
+                field(
+                    "friends",
+                    Selection(vec![
+                        field("name", Selection::new_empty()),
+                        field("id", Selection::new_empty()),
+                    ])
+                ),
+                field("id", Selection::new_empty()),
+                SelectionItem::InlineFragment(SelectionInlineFragment {
+                    on: "Droid",
+                    fields: Selection(vec![field("primaryFunction", Selection::new_empty())]),
+                    conditions: Vec::new()
+                }),
+            ])
+        );
+    }
+
+    #[test]
+    fn flatten_inline_fragments_merges_conditions() {
+        let query = r##"
+        query Hero($flag: Boolean!) {
+          hero {
+            name @include(if: $flag)
+            ... @skip(if: $flag) {
+              name
+              friends { name }
+            }
+            friends @include(if: true) { id }
+          }
+        }
+        "##;
+        let parsed = graphql_parser::parse_query(query).unwrap();
+        let selection_set = match &parsed.definitions[0] {
+            graphql_parser::query::Definition::Operation(
+                graphql_parser::query::OperationDefinition::Query(q)
+            ) => &q.selection_set,
+            _ => unreachable!()
+        };
+        let selection: Selection<'_> = selection_set.into();
+        let hero = match &selection.0[0] {
+            SelectionItem::Field(hero) => &hero.fields,
+            _ => unreachable!()
+        };
+
+        let schema = crate::schema::Schema::new();
+        let context = crate::query::QueryContext::new_empty(&schema);
+
+        let include = SelectionCondition::Include(ArgumentValue::Variable("flag".to_string()));
+        let skip = SelectionCondition::Skip(ArgumentValue::Variable("flag".to_string()));
+        let field = |name, fields, conditions| {
+            SelectionItem::Field(SelectionField {
+                alias: None,
+                name,
+                fields,
+                arguments: Vec::new(),
+                client: false,
+                conditions
+            })
+        };
+        assert_eq!(
+            hero.flatten_inline_fragments(&context, "Character"),
+            Selection(vec![
+                field(
+                    "name",
+                    Selection::new_empty(),
+                    vec![SelectionCondition::Any(vec![
+                        vec![include.clone()],
+                        vec![skip.clone()],
+                    ])]
+                ),
+                field(
+                    "friends",
+                    Selection(vec![
+                        field("name", Selection::new_empty(), vec![skip]),
+                        field("id", Selection::new_empty(), Vec::new()),
+                    ]),
+                    Vec::new()
+                ),
+            ])
+        );
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{
     deprecation::{DeprecationStatus, DeprecationStrategy},
-    fragments::GqlFragment,
+    fragments::{shared_fragments_path, FragmentTarget, GqlFragment},
     objects::GqlObjectField,
     query::QueryContext,
     selection::*,
@@ -173,10 +173,12 @@ pub(crate) fn render_object_field(
 }
 
 pub(crate) fn typescript_definitions_for_selection(
+    type_name: &str,
     fields: &[GqlObjectField<'_>],
     context: &QueryContext<'_, '_>,
     selection: &Selection<'_>
 ) -> Result<Vec<String>, CodegenError> {
+    let selection = selection.flatten_inline_fragments(context, type_name);
     let tokens: Vec<String> = (&selection)
         .into_iter()
         .map(|selected| {
@@ -205,11 +207,13 @@ pub(crate) fn typescript_definitions_for_selection(
 }
 
 pub(crate) fn field_impls_for_selection(
+    type_name: &str,
     fields: &[GqlObjectField<'_>],
     context: &QueryContext<'_, '_>,
     selection: &Selection<'_>,
     prefix: &str
 ) -> Result<(Vec<TokenStream>, HashSet<String>), CodegenError> {
+    let selection = selection.flatten_inline_fragments(context, type_name);
     let results: Vec<(TokenStream, HashSet<String>)> = (&selection)
         .into_iter()
         .map(|selected| {
@@ -293,6 +297,16 @@ impl ToRust for Vec<(String, ArgumentValue)> {
 }
 
 impl ArgumentValue {
+    /// Whether the value is or contains a variable.
+    pub(crate) fn uses_variables(&self) -> bool {
+        match self {
+            ArgumentValue::Variable(_) => true,
+            ArgumentValue::List(list) => list.iter().any(ArgumentValue::uses_variables),
+            ArgumentValue::Object(map) => map.values().any(ArgumentValue::uses_variables),
+            _ => false
+        }
+    }
+
     fn format(&self, name: &str) -> (String, Vec<Ident>) {
         let mut placeholder_idents = Vec::new();
         let formatted = match self {
@@ -367,6 +381,7 @@ pub(crate) fn typescript_fields_for_selection(
     selection: &Selection<'_>,
     prefix: &str
 ) -> Result<Vec<String>, CodegenError> {
+    let selection = selection.flatten_inline_fragments(context, type_name);
     let field_defs: Result<Vec<String>, CodegenError> = (&selection)
         .into_iter()
         .map(|item| match item {
@@ -399,9 +414,15 @@ pub(crate) fn typescript_fields_for_selection(
                     format!("{}.{}", prefix.to_camel_case(), alias.to_camel_case())
                 };
                 let ty = schema_field.type_.to_typescript(context, &ty_name);
+                // Fields that can be skipped may be missing from the response
+                let field_name = if f.conditions.is_empty() {
+                    alias.to_string()
+                } else {
+                    format!("{}?", alias)
+                };
 
                 Ok(render_typescript_field(
-                    alias,
+                    &field_name,
                     &ty,
                     schema_field.description.as_ref().cloned(),
                     &schema_field.deprecation,
@@ -420,7 +441,7 @@ pub(crate) fn typescript_fields_for_selection(
                         ))
                     })?;
                 let fields = typescript_fields_for_selection(
-                    fragment.fragment_name,
+                    type_name,
                     schema_fields,
                     context,
                     &fragment_from_context.selection,
@@ -428,9 +449,9 @@ pub(crate) fn typescript_fields_for_selection(
                 )?;
                 Ok(Some(fields.join(",")))
             }
-            SelectionItem::InlineFragment(_) => Err(CodegenError::UnimplementedError(
-                "inline fragment on object field".to_string()
-            ))
+            // Inline fragments that apply to the type have been flattened, the others are on
+            // implementing types, which interfaces turn into variants.
+            SelectionItem::InlineFragment(_) => Ok(None)
         })
         .filter_map(|x| x.transpose())
         .collect();
@@ -444,8 +465,12 @@ pub(crate) fn response_fields_for_selection(
     context: &QueryContext<'_, '_>,
     selection: &Selection<'_>,
     prefix: &str
-) -> Result<(Vec<TokenStream>, Vec<TokenStream>), CodegenError> {
+) -> Result<(TokenStream, Vec<TokenStream>), CodegenError> {
+    let selection = selection.flatten_inline_fragments(context, type_name);
+    // Selectors are pushed in order, the cache relies on them lining up with the fields.
     let mut selectors = Vec::new();
+    let mut conditional_selectors = Vec::new();
+    let mut fragment_selections = Vec::new();
 
     let field_defs: Result<Vec<TokenStream>, CodegenError> = (&selection)
         .into_iter()
@@ -475,12 +500,47 @@ pub(crate) fn response_fields_for_selection(
                     })?;
                 let prefix = format!("{}{}", prefix.to_camel_case(), alias.to_camel_case());
                 let ty = schema_field.type_.to_rust(context, &prefix);
-                let field_selector =
-                    schema_field
-                        .type_
-                        .field_selector(context, &prefix, name, f.arguments.clone());
-
-                selectors.push(field_selector);
+                let condition = selection_condition(&f.conditions, context);
+                // Local fields aren't part of the server response, so they can't be cached
+                if !f.client {
+                    let field_selector = schema_field.type_.field_selector(
+                        context,
+                        &prefix,
+                        name,
+                        f.arguments.clone()
+                    );
+                    match &condition {
+                        Some(condition) => conditional_selectors.push(quote! {
+                            if #condition {
+                                selection.push(#field_selector);
+                            }
+                        }),
+                        None if conditional_selectors.is_empty() => selectors.push(field_selector),
+                        None => {
+                            conditional_selectors.push(quote!(selection.push(#field_selector);))
+                        }
+                    }
+                }
+
+                // Skipped fields are missing from the response. Nullable fields need to tell
+                // a missing field apart from `null` so they can be serialized back the same way.
+                let (ty, attributes) = match (&condition, schema_field.type_.is_optional()) {
+                    (None, _) => (ty, quote!()),
+                    (Some(_), false) => (
+                        quote!(Option<#ty>),
+                        quote!(#[serde(default, skip_serializing_if = "Option::is_none")])
+                    ),
+                    (Some(_), true) => (
+                        quote!(Option<#ty>),
+                        quote! {
+                            #[serde(
+                                default,
+                                deserialize_with = "::artemis::codegen::deserialize_some",
+                                skip_serializing_if = "Option::is_none"
+                            )]
+                        }
+                    )
+                };
 
                 Ok(render_object_field(
                     alias,
@@ -488,7 +548,8 @@ pub(crate) fn response_fields_for_selection(
                     schema_field.description.as_ref().cloned(),
                     &schema_field.deprecation,
                     &context.deprecation_strategy
-                ))
+                )
+                .map(|field| quote!(#attributes #field)))
             }
             SelectionItem::FragmentSpread(fragment) => {
                 let field_name =
@@ -503,25 +564,36 @@ pub(crate) fn response_fields_for_selection(
                             &fragment.fragment_name
                         ))
                     })?;
-                let fragment_selectors =
-                    get_fragment_selectors(fragment_from_context, schema_fields, context);
-                let type_name = Ident::new(&fragment.fragment_name, Span::call_site());
+                let condition = selection_condition(&fragment.conditions, context);
+                let selection = fragment_selection(fragment_from_context, type_name, context);
+                fragment_selections.push(match &condition {
+                    Some(condition) => quote! {
+                        if #condition {
+                            selection.extend(#selection);
+                        }
+                    },
+                    None => quote!(selection.extend(#selection);)
+                });
+                let type_name = fragment_from_context.type_path();
                 let type_name = if fragment_from_context.is_recursive() {
                     quote!(Box<#type_name>)
                 } else {
                     quote!(#type_name)
                 };
-
-                selectors.extend(fragment_selectors);
+                let type_name = if condition.is_some() {
+                    quote!(Option<#type_name>)
+                } else {
+                    type_name
+                };
 
                 Ok(Some(quote! {
                     #[serde(flatten)]
                     pub #field_name: #type_name
                 }))
             }
-            SelectionItem::InlineFragment(_) => Err(CodegenError::UnimplementedError(
-                "inline fragment on object field".to_string()
-            ))
+            // Inline fragments that apply to the type have been flattened, the others are on
+            // implementing types, which interfaces turn into variants.
+            SelectionItem::InlineFragment(_) => Ok(None)
         })
         .filter_map(|x| match x {
             // Remove empty fields so callers always know a field has some
@@ -531,54 +603,106 @@ pub(crate) fn response_fields_for_selection(
         })
         .collect();
 
-    Ok((selectors, field_defs?))
+    let selection = if conditional_selectors.is_empty() && fragment_selections.is_empty() {
+        quote!(vec![#(#selectors),*])
+    } else {
+        quote!({
+            let mut selection = vec![#(#selectors),*];
+            #(#conditional_selectors)*
+            #(#fragment_selections)*
+            selection
+        })
+    };
+
+    Ok((selection, field_defs?))
 }
 
-fn get_fragment_selectors(
-    fragment: &GqlFragment<'_>,
-    schema_fields: &[GqlObjectField<'_>],
+/// The expression deciding whether an item with the given `@skip`/`@include` conditions is
+/// selected, or `None` if it always is.
+fn selection_condition(
+    conditions: &[SelectionCondition],
     context: &QueryContext<'_, '_>
-) -> Vec<TokenStream> {
-    fragment
-        .selection
-        .0
-        .iter()
-        .flat_map(|selector| match selector {
-            SelectionItem::Field(field) => {
-                let name = field.name;
-                let alias = field.alias.unwrap_or(name);
+) -> Option<TokenStream> {
+    if conditions.is_empty() {
+        return None;
+    }
 
-                let schema_field = schema_fields
-                    .iter()
-                    .find(|field| field.name == name)
-                    .ok_or_else(|| CodegenError::TypeError("".to_string()))
-                    .unwrap();
-                let alias = alias.to_camel_case();
-                let field_selector = schema_field.type_.field_selector(
-                    context,
-                    &alias,
-                    name,
-                    field.arguments.to_vec()
-                );
+    let conditions = conditions.iter().map(|condition| match condition {
+        SelectionCondition::Include(value) => condition_value(value, true, context),
+        SelectionCondition::Skip(value) => {
+            let value = condition_value(value, false, context);
+            quote!(!#value)
+        }
+        SelectionCondition::Any(alternatives) => {
+            let alternatives = alternatives.iter().map(|conditions| {
+                selection_condition(conditions, context).unwrap_or_else(|| quote!(true))
+            });
+            quote!((#(#alternatives)||*))
+        }
+    });
+    Some(quote!(#(#conditions)&&*))
+}
 
-                vec![field_selector]
-            }
-            SelectionItem::FragmentSpread(fragment) => {
-                let fragment_from_context = context
-                    .fragments
-                    .get(&fragment.fragment_name)
-                    .ok_or_else(|| {
-                        CodegenError::TypeError(format!(
-                            "Unknown fragment: {}",
-                            &fragment.fragment_name
-                        ))
-                    })
-                    .unwrap();
-                get_fragment_selectors(fragment_from_context, schema_fields, context)
+/// The boolean value of the `if` argument of `@skip`/`@include`. Unset variables fall back to
+/// their default, or to `default`, which is the value that doesn't skip the item.
+fn condition_value(
+    value: &ArgumentValue,
+    default: bool,
+    context: &QueryContext<'_, '_>
+) -> TokenStream {
+    let variable = match value {
+        ArgumentValue::Boolean(value) => return quote!(#value),
+        ArgumentValue::Variable(name) => context
+            .variables
+            .iter()
+            .find(|variable| variable.name == name),
+        _ => None
+    };
+    let variable = match variable {
+        Some(variable) => variable,
+        None => return quote!(#default)
+    };
+
+    let field_name = keyword_replace(&variable.name.to_snake_case());
+    let field_name = Ident::new(&field_name, Span::call_site());
+    if variable.ty.is_optional() {
+        let default = match variable.default {
+            Some(Value::Boolean(value)) => *value,
+            _ => default
+        };
+        quote!(variables.#field_name.unwrap_or(#default))
+    } else {
+        quote!(variables.#field_name)
+    }
+}
+
+/// The selection of a fragment spread in a selection on `type_name`.
+///
+/// Fragments on interfaces select different fields depending on the concrete type. Spread on an
+/// object, the type is known, otherwise it's the `typename` of the interface's own selection.
+fn fragment_selection(
+    fragment: &GqlFragment<'_>,
+    type_name: &str,
+    context: &QueryContext<'_, '_>
+) -> TokenStream {
+    let fragment_path = fragment.type_path();
+    // Shared fragments can't use variables, so they take the shared module's empty ones.
+    let variables = if fragment.shared {
+        let fragments = shared_fragments_path();
+        quote!(&#fragments::Variables)
+    } else {
+        quote!(variables)
+    };
+    match fragment.on {
+        FragmentTarget::Object(_) => quote!(#fragment_path::selection(#variables)),
+        FragmentTarget::Interface(_) | FragmentTarget::Union(_) => {
+            if context.schema.interfaces.contains_key(&type_name) {
+                quote!(#fragment_path::selection(typename, #variables))
+            } else {
+                quote!(#fragment_path::selection(#type_name, #variables))
             }
-            SelectionItem::InlineFragment(_) => panic!("Inline fragment on object field")
-        })
-        .collect()
+        }
+    }
 }
 
 /// Given the GraphQL schema name for an object/interface/input object field and
@@ -598,24 +722,68 @@ mod tests {
         deprecation::DeprecationStatus,
         field_type::FieldType,
         fragments::{FragmentTarget, GqlFragment},
+        interfaces::GqlInterface,
         objects::{GqlObject, GqlObjectField},
         query::QueryContext,
-        selection::{Selection, SelectionField, SelectionFragmentSpread, SelectionItem},
-        shared::get_fragment_selectors
+        selection::{
+            Selection, SelectionCondition, SelectionField, SelectionFragmentSpread,
+            SelectionInlineFragment, SelectionItem
+        },
+        shared::{fragment_selection, response_fields_for_selection, ArgumentValue},
+        variables::Variable
     };
     use quote::quote;
     use std::cell::Cell;
 
     #[test]
-    fn fragment_selector_returns_flattened_selectors() {
+    fn object_fragment_selection() {
         let schema = crate::schema::Schema::new();
         let ctx = QueryContext::new_empty(&schema);
-        let fragment_on = GqlObject {
-            name: "Test",
+        let fragment_on = GqlObject::new("Test", None);
+        let fragment = GqlFragment {
+            name: "Fragment",
+            is_required: Cell::new(true),
+            shared: false,
+            on: FragmentTarget::Object(&fragment_on),
+            selection: Selection::new_empty()
+        };
+
+        let selection = fragment_selection(&fragment, "Test", &ctx);
+        let expected = quote!(Fragment::selection(variables));
+
+        assert_eq!(selection.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn interface_fragment_selection_passes_typename() {
+        let mut schema = crate::schema::Schema::new();
+        let mut iface = GqlInterface::new("Named", None);
+        iface.implemented_by.insert("Test");
+        schema.interfaces.insert("Named", iface.clone());
+        let ctx = QueryContext::new_empty(&schema);
+        let fragment = GqlFragment {
+            name: "Fragment",
             is_required: Cell::new(true),
-            description: None,
-            fields: Vec::new()
+            shared: false,
+            on: FragmentTarget::Interface(&iface),
+            selection: Selection::new_empty()
         };
+
+        // Spread on an object, the typename is known
+        let selection = fragment_selection(&fragment, "Test", &ctx);
+        let expected = quote!(Fragment::selection("Test", variables));
+        assert_eq!(selection.to_string(), expected.to_string());
+
+        // Spread on an interface, it's only known at runtime
+        let selection = fragment_selection(&fragment, "Named", &ctx);
+        let expected = quote!(Fragment::selection(typename, variables));
+        assert_eq!(selection.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn response_fields_flatten_inline_fragments() {
+        let schema = crate::schema::Schema::new();
+        let ctx = QueryContext::new_empty(&schema);
         let schema_fields = vec![
             GqlObjectField {
                 name: "field_1",
@@ -634,54 +802,67 @@ mod tests {
             name: "",
             arguments: Vec::new(),
             alias: None,
-            fields: Selection(Vec::new())
+            fields: Selection(Vec::new()),
+            client: false,
+            conditions: Vec::new()
         };
-        let fragment = GqlFragment {
-            name: "Fragment",
-            is_required: Cell::new(true),
-            on: FragmentTarget::Object(&fragment_on),
-            selection: Selection(vec![
-                SelectionItem::Field(SelectionField {
-                    name: "field_1",
-                    ..base_field.clone()
-                }),
-                SelectionItem::Field(SelectionField {
-                    name: "field_2",
+        let selection = Selection(vec![
+            SelectionItem::Field(SelectionField {
+                name: "field_1",
+                ..base_field.clone()
+            }),
+            SelectionItem::InlineFragment(SelectionInlineFragment {
+                on: "Test",
+                fields: Selection(vec![
+                    SelectionItem::Field(SelectionField {
+                        name: "field_1",
+                        ..base_field.clone()
+                    }),
+                    SelectionItem::Field(SelectionField {
+                        name: "field_2",
+                        ..base_field.clone()
+                    }),
+                ]),
+                conditions: Vec::new()
+            }),
+            // Never matches an object of type `Test`
+            SelectionItem::InlineFragment(SelectionInlineFragment {
+                on: "Other",
+                fields: Selection(vec![SelectionItem::Field(SelectionField {
+                    name: "field_3",
                     ..base_field
-                }),
-            ])
-        };
-
-        let selector: Vec<String> = get_fragment_selectors(&fragment, &schema_fields, &ctx)
-            .into_iter()
-            .map(|x| x.to_string())
-            .collect();
-        let expected = vec![
-            quote!(::artemis::codegen::FieldSelector::Scalar(
-                "field_1",
+                })]),
+                conditions: Vec::new()
+            }),
+        ]);
+
+        let (selection, fields) =
+            response_fields_for_selection("Test", &schema_fields, &ctx, &selection, "Test")
+                .unwrap();
+        let expected = quote!(vec![
+            ::artemis::codegen::FieldSelector::Scalar(
+                ::std::borrow::Cow::Borrowed("field_1"),
                 String::new()
-            ))
-            .to_string(),
-            quote!(::artemis::codegen::FieldSelector::Scalar(
-                "field_2",
+            ),
+            ::artemis::codegen::FieldSelector::Scalar(
+                ::std::borrow::Cow::Borrowed("field_2"),
                 String::new()
-            ))
-            .to_string(),
-        ];
+            )
+        ]);
 
-        assert_eq!(selector, expected);
+        assert_eq!(selection.to_string(), expected.to_string());
+        assert_eq!(fields.len(), 2);
     }
 
     #[test]
-    fn nested_fragment_selector_returns_flattened_selectors() {
+    fn response_fields_evaluate_conditions() {
         let schema = crate::schema::Schema::new();
         let mut ctx = QueryContext::new_empty(&schema);
-        let fragment_on = GqlObject {
-            name: "Test",
-            is_required: Cell::new(true),
-            description: None,
-            fields: Vec::new()
-        };
+        ctx.variables = vec![Variable {
+            name: "flag",
+            ty: FieldType::new("Boolean"),
+            default: None
+        }];
         let schema_fields = vec![
             GqlObjectField {
                 name: "field_1",
@@ -695,82 +876,100 @@ mod tests {
                 deprecation: DeprecationStatus::Current,
                 type_: FieldType::new("ID")
             },
-            GqlObjectField {
-                name: "field_3",
-                description: None,
-                deprecation: DeprecationStatus::Current,
-                type_: FieldType::new("ID")
-            },
-            GqlObjectField {
-                name: "field_test",
-                description: None,
-                deprecation: DeprecationStatus::Current,
-                type_: FieldType::new("Test")
-            },
         ];
-        let base_field = SelectionField {
-            name: "",
-            arguments: Vec::new(),
-            alias: None,
-            fields: Selection(Vec::new())
-        };
-        let fragment = GqlFragment {
-            name: "Fragment",
-            is_required: Cell::new(true),
-            on: FragmentTarget::Object(&fragment_on),
-            selection: Selection(vec![
-                SelectionItem::Field(SelectionField {
-                    name: "field_1",
-                    ..base_field.clone()
-                }),
-                SelectionItem::FragmentSpread(SelectionFragmentSpread {
-                    fragment_name: "Fragment2"
-                }),
-            ])
-        };
-        let fragment2 = GqlFragment {
-            name: "Fragment2",
-            is_required: Cell::new(true),
-            on: FragmentTarget::Object(&fragment_on),
-            selection: Selection(vec![
-                SelectionItem::Field(SelectionField {
-                    name: "field_2",
-                    ..base_field.clone()
-                }),
-                SelectionItem::Field(SelectionField {
-                    name: "field_3",
-                    ..base_field.clone()
-                }),
-            ])
-        };
-
-        ctx.fragments.insert("Fragment", fragment);
-        ctx.fragments.insert("Fragment2", fragment2);
-
-        let selector: Vec<String> =
-            get_fragment_selectors(ctx.fragments.get("Fragment").unwrap(), &schema_fields, &ctx)
-                .into_iter()
-                .map(|x| x.to_string())
-                .collect();
-        let expected = vec![
-            quote!(::artemis::codegen::FieldSelector::Scalar(
-                "field_1",
-                String::new()
-            ))
-            .to_string(),
-            quote!(::artemis::codegen::FieldSelector::Scalar(
-                "field_2",
-                String::new()
-            ))
-            .to_string(),
-            quote!(::artemis::codegen::FieldSelector::Scalar(
-                "field_3",
+        let selection = Selection(vec![
+            SelectionItem::Field(SelectionField {
+                name: "field_1",
+                arguments: Vec::new(),
+                alias: None,
+                fields: Selection(Vec::new()),
+                client: false,
+                conditions: Vec::new()
+            }),
+            SelectionItem::Field(SelectionField {
+                name: "field_2",
+                arguments: Vec::new(),
+                alias: None,
+                fields: Selection(Vec::new()),
+                client: false,
+                conditions: vec![SelectionCondition::Include(ArgumentValue::Variable(
+                    "flag".to_string()
+                ))]
+            }),
+        ]);
+
+        let (selection, fields) =
+            response_fields_for_selection("Test", &schema_fields, &ctx, &selection, "Test")
+                .unwrap();
+        let expected = quote!({
+            let mut selection = vec![::artemis::codegen::FieldSelector::Scalar(
+                ::std::borrow::Cow::Borrowed("field_1"),
                 String::new()
-            ))
-            .to_string(),
-        ];
+            )];
+            if variables.flag.unwrap_or(true) {
+                selection.push(::artemis::codegen::FieldSelector::Scalar(
+                    ::std::borrow::Cow::Borrowed("field_2"),
+                    String::new()
+                ));
+            }
+            selection
+        });
+
+        assert_eq!(selection.to_string(), expected.to_string());
+        // A nullable field that may be skipped is `Some(None)` if it's `null`
+        let field_2 = fields[1].to_string();
+        assert!(field_2.contains("deserialize_some"));
+        assert!(field_2.ends_with("pub field_2 : Option < Option < ID > >"));
+    }
+
+    #[test]
+    fn response_fields_select_nested_fragments() {
+        let schema = crate::schema::Schema::new();
+        let mut ctx = QueryContext::new_empty(&schema);
+        let fragment_on = GqlObject::new("Test", None);
+        let spread = |fragment_name| {
+            Selection(vec![SelectionItem::FragmentSpread(
+                SelectionFragmentSpread {
+                    fragment_name,
+                    conditions: Vec::new()
+                }
+            )])
+        };
+        let fragments = vec![("Outer", spread("Inner")), ("Inner", Selection(Vec::new()))];
+        for (name, selection) in fragments {
+            ctx.fragments.insert(
+                name,
+                GqlFragment {
+                    name,
+                    is_required: Cell::new(false),
+                    shared: false,
+                    on: FragmentTarget::Object(&fragment_on),
+                    selection
+                }
+            );
+        }
 
-        assert_eq!(selector, expected);
+        let (selection, fields) =
+            response_fields_for_selection("Test", &[], &ctx, &spread("Outer"), "Test").unwrap();
+        let expected = quote!({
+            let mut selection = vec![];
+            selection.extend(Outer::selection(variables));
+            selection
+        });
+        assert_eq!(selection.to_string(), expected.to_string());
+        assert_eq!(fields.len(), 1);
+
+        // The outer fragment's own selection includes the one it spreads
+        let outer = &ctx.fragments["Outer"];
+        let (selection, _) =
+            response_fields_for_selection("Test", &[], &ctx, &outer.selection, "Outer").unwrap();
+        let expected = quote!({
+            let mut selection = vec![];
+            selection.extend(Inner::selection(variables));
+            selection
+        });
+        assert_eq!(selection.to_string(), expected.to_string());
+        assert!(ctx.fragments["Inner"].is_required.get());
     }
 
     #[test]
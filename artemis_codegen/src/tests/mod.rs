@@ -1,3 +1,8 @@
+use crate::{codegen, schema::Schema, CodegenMode, GraphQLClientCodegenOptions};
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::path::{Path, PathBuf};
+
 mod github;
 
 #[test]
@@ -20,6 +25,7 @@ fn schema_with_keywords_works() {
             query_string,
             schema: &schema,
             query_document: &query,
+            shared_fragments: &[],
             operation,
             options: &options
         }
@@ -41,3 +47,338 @@ fn schema_with_keywords_works() {
         };
     }
 }
+
+/// The path to a file in this directory.
+fn fixture(file_name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/tests")
+        .join(file_name)
+}
+
+/// Generates the modules for the operations in `query` against an already built schema, with
+/// the shared fragment files in `options`.
+fn generate_with_schema(
+    query: &str,
+    schema: &Schema<'_>,
+    options: GraphQLClientCodegenOptions
+) -> String {
+    let query = graphql_parser::parse_query(query).expect("Parse query");
+    let shared_fragments = crate::parse_fragment_files(&options).expect("Parse fragments");
+    crate::generate_modules(&query, &shared_fragments, schema, options)
+        .expect("Generate modules")
+        .0
+        .to_string()
+}
+
+/// Generates the modules for the operations in `query` against the star wars schema, with the
+/// client schema extensions and shared fragment files in `options`.
+fn generate(query: &str, options: GraphQLClientCodegenOptions) -> String {
+    let (parsed_schema, client_schemas) =
+        crate::parse_schema(&fixture("star_wars_schema.graphql"), &options)
+            .expect("Parse star wars schema");
+    let schema = crate::build_schema(&parsed_schema, &client_schemas).expect("Build schema");
+    generate_with_schema(query, &schema, options)
+}
+
+/// Whether the generated code contains `tokens`.
+fn contains(generated_code: &str, tokens: TokenStream) -> bool {
+    generated_code.contains(&tokens.to_string())
+}
+
+/// The fields of a generated struct, as `name: Type`.
+fn struct_fields(generated_code: &str, struct_name: &str) -> Option<Vec<String>> {
+    fn find_struct(items: &[syn::Item], struct_name: &str) -> Option<syn::ItemStruct> {
+        items.iter().find_map(|item| match item {
+            syn::Item::Struct(item) if item.ident == struct_name => Some(item.clone()),
+            syn::Item::Mod(module) => module
+                .content
+                .as_ref()
+                .and_then(|(_, items)| find_struct(items, struct_name)),
+            _ => None
+        })
+    }
+
+    let file: syn::File = syn::parse_str(generated_code).expect("Parse generated code");
+    let item = find_struct(&file.items, struct_name)?;
+    let fields = item
+        .fields
+        .iter()
+        .map(|field| {
+            let name = &field.ident;
+            let ty = &field.ty;
+            quote!(#name: #ty).to_string()
+        })
+        .collect();
+    Some(fields)
+}
+
+#[test]
+fn client_fields_work() {
+    let mut options = GraphQLClientCodegenOptions::new(CodegenMode::Cli);
+    options.add_client_schema_file(fixture("client_schema.graphql"));
+    let generated_code = generate(
+        r#"
+        query ClientQuery {
+          selectedTab @client
+          human(id: "1000") {
+            name
+            isFavorite @client
+          }
+        }
+        "#,
+        options
+    );
+
+    let scalar_selector = |name: &str| {
+        quote! {
+            ::artemis::codegen::FieldSelector::Scalar(
+                ::std::borrow::Cow::Borrowed(#name),
+                String::new()
+            )
+        }
+    };
+    // Client fields are part of the response, but not of the cached selection
+    assert!(contains(&generated_code, quote!(pub selected_tab: String)));
+    assert!(contains(&generated_code, quote!(pub is_favorite: Boolean)));
+    assert!(contains(&generated_code, scalar_selector("name")));
+    assert!(!contains(&generated_code, scalar_selector("selectedTab")));
+    assert!(!contains(&generated_code, scalar_selector("isFavorite")));
+}
+
+#[test]
+fn inline_fragments_work() {
+    let generated_code = generate(
+        r#"
+        query InlineFragmentQuery {
+          hero {
+            __typename
+            ... on Character {
+              name
+            }
+            ... on Droid {
+              primaryFunction
+            }
+          }
+          human(id: "1000") {
+            ... on Human {
+              homePlanet
+            }
+          }
+        }
+        "#,
+        GraphQLClientCodegenOptions::new(CodegenMode::Cli)
+    );
+
+    // Inline fragments on the type itself are flattened
+    let hero_fields = struct_fields(&generated_code, "InlineFragmentQueryHero").unwrap();
+    assert_eq!(hero_fields[0], quote!(name: String).to_string());
+    assert!(contains(&generated_code, quote!(pub home_planet: Option<String>)));
+    // Inline fragments on implementing types become variants
+    assert!(contains(&generated_code, quote!(Droid(InlineFragmentQueryHeroOnDroid))));
+    assert!(contains(
+        &generated_code,
+        quote!("Droid" => InlineFragmentQueryHeroOnDroid::selection(variables))
+    ));
+    assert!(generated_code.contains("primaryFunction: Maybe<String>"));
+}
+
+#[test]
+fn shared_fragments_work() {
+    let mut options = GraphQLClientCodegenOptions::new(CodegenMode::Cli);
+    options.add_fragment_file(fixture("human_name_fragment.graphql"));
+    let generated_code = generate(
+        r#"
+        query SharedFragmentQuery {
+          human(id: "1000") {
+            ...HumanName
+          }
+        }
+        "#,
+        options.clone()
+    );
+
+    // The query refers to the fragment in the shared module instead of generating it
+    assert!(contains(
+        &generated_code,
+        quote!(pub human_name: super::super::fragments::HumanName)
+    ));
+    assert!(struct_fields(&generated_code, "HumanName").is_none());
+
+    let fragments_code =
+        crate::generate_fragments_token_stream(&fixture("star_wars_schema.graphql"), options)
+            .expect("Generate fragments module")
+            .to_string();
+    assert_eq!(
+        struct_fields(&fragments_code, "HumanName"),
+        Some(vec![quote!(name: String).to_string()])
+    );
+    assert_eq!(struct_fields(&fragments_code, "Variables"), Some(Vec::new()));
+}
+
+#[test]
+fn shared_fragments_cant_use_variables() {
+    use crate::{codegen, schema::Schema, CodegenMode, GraphQLClientCodegenOptions};
+    use graphql_parser;
+
+    let fragments = graphql_parser::parse_query(
+        r#"
+        fragment HeroName on Query {
+          hero(episode: $episode) {
+            __typename
+            name
+          }
+        }
+    "#
+    )
+    .expect("Parse fragments");
+    let schema = graphql_parser::parse_schema(include_str!("star_wars_schema.graphql"))
+        .expect("Parse star wars schema");
+    let schema = Schema::from(&schema);
+
+    let options = GraphQLClientCodegenOptions::new(CodegenMode::Cli);
+    let error = codegen::response_for_fragments(&schema, &[fragments], &options).unwrap_err();
+    assert!(error.to_string().contains("HeroName"));
+}
+
+#[test]
+fn shared_types_work() {
+    let mut options = GraphQLClientCodegenOptions::new(CodegenMode::Cli);
+    options.shared_types = true;
+    let schema = graphql_parser::parse_schema(include_str!("star_wars_schema.graphql"))
+        .expect("Parse star wars schema");
+    let schema = Schema::from(&schema);
+    let generated_code = generate_with_schema(
+        r#"
+        query HeroQuery($episode: Episode) {
+          hero(episode: $episode) {
+            __typename
+            name
+          }
+        }
+        "#,
+        &schema,
+        options.clone()
+    );
+
+    // The query module re-exports the enum instead of generating its own
+    assert!(contains(&generated_code, quote!(pub use super::super::types::*;)));
+    assert!(!contains(&generated_code, quote!(pub enum Episode)));
+
+    // The types module only has the types the query needs
+    let types_code = codegen::response_for_types(&schema, &options)
+        .expect("Generate types module")
+        .to_string();
+    assert!(contains(&types_code, quote!(pub enum Episode)));
+    assert!(!contains(&types_code, quote!(pub enum LengthUnit)));
+}
+
+#[test]
+fn maybe_undefined_inputs_work() {
+    let mut options = GraphQLClientCodegenOptions::new(CodegenMode::Cli);
+    options.maybe_undefined = true;
+    let generated_code = generate(
+        r#"
+        mutation CreateReview(
+          $episode: Episode
+          $review: ReviewInput!
+          $episodes: [Episode] = [null]
+        ) {
+          createReview(episode: $episode, review: $review) {
+            stars
+          }
+        }
+        "#,
+        options
+    );
+
+    // Nullable variables and input fields can be left out
+    assert!(contains(
+        &generated_code,
+        quote! {
+            #[serde(default, skip_serializing_if = "::artemis::MaybeUndefined::is_undefined")]
+            pub episode: ::artemis::MaybeUndefined<Episode>
+        }
+    ));
+    assert!(contains(
+        &generated_code,
+        quote! {
+            #[serde(default, skip_serializing_if = "::artemis::MaybeUndefined::is_undefined")]
+            pub commentary: ::artemis::MaybeUndefined<String>
+        }
+    ));
+    // Non-null ones can't
+    assert!(contains(&generated_code, quote!(pub review: ReviewInput)));
+    assert!(contains(&generated_code, quote!(pub stars: Int)));
+    // Null list elements in default values are still `None`
+    assert!(contains(
+        &generated_code,
+        quote!(::artemis::MaybeUndefined::Value(vec![None,]))
+    ));
+    // The TypeScript definitions mark them as optional
+    assert!(generated_code.contains("episode?: Maybe<Episode>"));
+    assert!(generated_code.contains("commentary?: Maybe<String>"));
+}
+
+#[test]
+fn query_and_schema_files_are_tracked() {
+    let mut options = GraphQLClientCodegenOptions::new(CodegenMode::Cli);
+    options.set_query_file("queries/tracked.graphql".into());
+    options.set_schema_file("schema.graphql".into());
+    let generated_code = generate(
+        r#"
+        query TrackedQuery {
+          human(id: "1000") {
+            name
+          }
+        }
+        "#,
+        options
+    );
+
+    assert!(contains(
+        &generated_code,
+        quote!(include_str!("queries/tracked.graphql"))
+    ));
+    assert!(contains(&generated_code, quote!(include_bytes!("schema.graphql"))));
+}
+
+#[test]
+fn interface_and_union_variants_are_sorted() {
+    let generated_code = generate(
+        r#"
+        query HeroQuery {
+          hero {
+            __typename
+            name
+          }
+          search(text: "R2") {
+            __typename
+            ... on Starship {
+              name
+            }
+            ... on Human {
+              name
+            }
+            ... on Droid {
+              name
+            }
+          }
+        }
+        "#,
+        GraphQLClientCodegenOptions::new(CodegenMode::Cli)
+    );
+
+    // The generated code doesn't depend on hash set iteration order
+    assert!(contains(
+        &generated_code,
+        quote!(pub enum HeroQueryHeroOn { Droid, Human, })
+    ));
+    assert!(contains(
+        &generated_code,
+        quote! {
+            "Droid" => HeroQuerySearchOnDroid::selection(variables),
+            "Human" => HeroQuerySearchOnHuman::selection(variables),
+            "Starship" => HeroQuerySearchOnStarship::selection(variables),
+        }
+    ));
+}
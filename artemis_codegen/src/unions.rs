@@ -176,6 +176,7 @@ impl<'schema> GqlUnion<'schema> {
         selection: &Selection<'_>,
         prefix: &str
     ) -> Result<String, CodegenError> {
+        let selection = &selection.flatten_inline_fragments(query_context, self.name);
         let typename_field = selection.extract_typename(query_context);
 
         if typename_field.is_none() {
@@ -240,6 +241,7 @@ impl<'schema> GqlUnion<'schema> {
         selection: &Selection<'_>,
         prefix: &str
     ) -> Result<(TokenStream, HashSet<String>), CodegenError> {
+        let selection = &selection.flatten_inline_fragments(query_context, self.name);
         let typename_field = selection.extract_typename(query_context);
 
         if typename_field.is_none() {
@@ -278,10 +280,11 @@ impl<'schema> GqlUnion<'schema> {
         let query_info = if query_context.include_query_info {
             let selections_by_type: Vec<_> = used_variants
                 .iter()
-                .collect::<HashSet<_>>()
+                .collect::<BTreeSet<_>>()
                 .iter()
+                .filter(|variant| query_context.schema.objects.contains_key(**variant))
                 .map(|variant| {
-                    let ident = Ident::new(variant, Span::call_site());
+                    let ident = Ident::new(&format!("{}On{}", prefix, variant), Span::call_site());
                     quote! {
                         #variant => #ident::selection(variables)
                     }
@@ -289,9 +292,11 @@ impl<'schema> GqlUnion<'schema> {
                 .collect();
             quote! {
                 impl #struct_name {
+                    #[allow(unused_variables)]
                     fn selection(typename: &str, variables: &Variables) -> Vec<::artemis::codegen::FieldSelector> {
                         match typename {
-                            #(#selections_by_type),*
+                            #(#selections_by_type,)*
+                            _ => Vec::new()
                         }
                     }
                 }
@@ -336,8 +341,11 @@ mod tests {
                     alias: None,
                     name: "firstName",
                     fields: Selection::new_empty(),
-                    arguments: Vec::new()
-                })])
+                    arguments: Vec::new(),
+                    client: false,
+                    conditions: Vec::new()
+                })]),
+                conditions: Vec::new()
             }),
             SelectionItem::InlineFragment(SelectionInlineFragment {
                 on: "Organization",
@@ -345,8 +353,11 @@ mod tests {
                     alias: None,
                     name: "title",
                     fields: Selection::new_empty(),
-                    arguments: Vec::new()
-                })])
+                    arguments: Vec::new(),
+                    client: false,
+                    conditions: Vec::new()
+                })]),
+                conditions: Vec::new()
             }),
         ];
         let selection = Selection::from_vec(fields);
@@ -433,7 +444,9 @@ mod tests {
                 alias: None,
                 name: "__typename",
                 fields: Selection::new_empty(),
-                arguments: Vec::new()
+                arguments: Vec::new(),
+                client: false,
+                conditions: Vec::new()
             }),
             SelectionItem::InlineFragment(SelectionInlineFragment {
                 on: "User",
@@ -441,8 +454,11 @@ mod tests {
                     alias: None,
                     name: "firstName",
                     fields: Selection::new_empty(),
-                    arguments: Vec::new()
-                })])
+                    arguments: Vec::new(),
+                    client: false,
+                    conditions: Vec::new()
+                })]),
+                conditions: Vec::new()
             }),
             SelectionItem::InlineFragment(SelectionInlineFragment {
                 on: "Organization",
@@ -450,8 +466,11 @@ mod tests {
                     alias: None,
                     name: "title",
                     fields: Selection::new_empty(),
-                    arguments: Vec::new()
-                })])
+                    arguments: Vec::new(),
+                    client: false,
+                    conditions: Vec::new()
+                })]),
+                conditions: Vec::new()
             }),
         ];
         let schema = crate::schema::Schema::new();
@@ -585,10 +604,12 @@ mod tests {
             }
 
             impl Meow {
+                #[allow(unused_variables)]
                 fn selection(typename: &str, variables: &Variables) -> Vec<::artemis::codegen::FieldSelector> {
                     match typename {
-                        "Organization" => Organization::selection(variables),
-                        "User" => User::selection(variables)
+                        "Organization" => MeowOnOrganization::selection(variables),
+                        "User" => MeowOnUser::selection(variables),
+                        _ => Vec::new()
                     }
                 }
             }
@@ -604,7 +625,9 @@ mod tests {
                 alias: None,
                 name: "__typename",
                 fields: Selection::new_empty(),
-                arguments: Vec::new()
+                arguments: Vec::new(),
+                client: false,
+                conditions: Vec::new()
             }),
             SelectionItem::InlineFragment(SelectionInlineFragment {
                 on: "SomeNonUnionType",
@@ -612,8 +635,11 @@ mod tests {
                     alias: None,
                     name: "field",
                     fields: Selection::new_empty(),
-                    arguments: Vec::new()
-                })])
+                    arguments: Vec::new(),
+                    client: false,
+                    conditions: Vec::new()
+                })]),
+                conditions: Vec::new()
             }),
         ];
         let schema = crate::schema::Schema::new();
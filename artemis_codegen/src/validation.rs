@@ -0,0 +1,403 @@
+use crate::{objects::GqlObjectField, schema::Schema};
+use graphql_parser::{
+    query::{
+        Definition, Directive, Document, FragmentDefinition, OperationDefinition, Selection,
+        SelectionSet, Type, TypeCondition, Value, VariableDefinition
+    },
+    Pos
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt
+};
+
+/// An error in a query document that codegen would otherwise only report without a location.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ValidationError {
+    pub message: String,
+    /// The position of the offending definition or selection.
+    pub position: Pos
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Checks that the types, fields, fragments and variables referenced in a query document exist.
+/// This doesn't cover everything the GraphQL spec validates (e.g. field arguments), only the
+/// mistakes that would make code generation fail.
+pub(crate) fn validate_document(
+    document: &Document,
+    schema: &Schema<'_>
+) -> Result<(), ValidationError> {
+    let fragments = document
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Fragment(fragment) => Some((fragment.name.as_str(), fragment)),
+            Definition::Operation(_) => None
+        })
+        .collect();
+    let validator = Validator { schema, fragments };
+
+    for definition in &document.definitions {
+        match definition {
+            Definition::Fragment(fragment) => {
+                let TypeCondition::On(on) = &fragment.type_condition;
+                validator.check_composite_type(on, fragment.position)?;
+                validator.validate_selection_set(on, &fragment.selection_set)?;
+            }
+            Definition::Operation(operation) => validator.validate_operation(operation)?
+        }
+    }
+
+    Ok(())
+}
+
+struct Validator<'a, 'schema> {
+    schema: &'a Schema<'schema>,
+    fragments: HashMap<&'a str, &'a FragmentDefinition>
+}
+
+impl<'a, 'schema> Validator<'a, 'schema> {
+    fn validate_operation(
+        &self,
+        operation: &'a OperationDefinition
+    ) -> Result<(), ValidationError> {
+        let schema = self.schema;
+        let (root, position, variables, selection_set) = match operation {
+            OperationDefinition::Query(query) => (
+                schema.query_type.unwrap_or("Query"),
+                query.position,
+                &query.variable_definitions,
+                &query.selection_set
+            ),
+            OperationDefinition::Mutation(mutation) => (
+                schema.mutation_type.unwrap_or("Mutation"),
+                mutation.position,
+                &mutation.variable_definitions,
+                &mutation.selection_set
+            ),
+            OperationDefinition::Subscription(subscription) => (
+                schema.subscription_type.unwrap_or("Subscription"),
+                subscription.position,
+                &subscription.variable_definitions,
+                &subscription.selection_set
+            ),
+            OperationDefinition::SelectionSet(selection_set) => {
+                return Err(ValidationError {
+                    message: "Bare selection sets are not supported, name the operation"
+                        .to_string(),
+                    position: selection_set.span.0
+                })
+            }
+        };
+
+        if !schema.objects.contains_key(root) {
+            return Err(ValidationError {
+                message: format!("The schema doesn't define the root type `{}`", root),
+                position
+            });
+        }
+        for variable in variables {
+            self.validate_variable(variable)?;
+        }
+
+        self.validate_selection_set(root, selection_set)?;
+
+        let defined = variables
+            .iter()
+            .map(|variable| variable.name.as_str())
+            .collect();
+        self.check_variable_uses(selection_set, &defined, &mut HashSet::new())
+    }
+
+    fn validate_variable(&self, variable: &VariableDefinition) -> Result<(), ValidationError> {
+        let mut ty = &variable.var_type;
+        let name = loop {
+            match ty {
+                Type::NamedType(name) => break name.as_str(),
+                Type::ListType(inner) | Type::NonNullType(inner) => ty = inner
+            }
+        };
+
+        let schema = self.schema;
+        let is_input = crate::schema::DEFAULT_SCALARS.contains(&name)
+            || schema.scalars.contains_key(name)
+            || schema.enums.contains_key(name)
+            || schema.inputs.contains_key(name);
+        if is_input {
+            Ok(())
+        } else {
+            Err(ValidationError {
+                message: format!(
+                    "Unknown input type `{}` for variable `${}`",
+                    name, variable.name
+                ),
+                position: variable.position
+            })
+        }
+    }
+
+    fn check_composite_type(&self, name: &str, position: Pos) -> Result<(), ValidationError> {
+        let schema = self.schema;
+        if schema.objects.contains_key(name)
+            || schema.interfaces.contains_key(name)
+            || schema.unions.contains_key(name)
+        {
+            Ok(())
+        } else {
+            Err(ValidationError {
+                message: format!("Unknown object, interface or union type `{}`", name),
+                position
+            })
+        }
+    }
+
+    /// The fields of a type, or `None` if it's a union, which only has `__typename`.
+    fn fields(&self, type_name: &str) -> Option<&'a [GqlObjectField<'schema>]> {
+        let schema = self.schema;
+        if let Some(object) = schema.objects.get(type_name) {
+            Some(&object.fields)
+        } else if let Some(interface) = schema.interfaces.get(type_name) {
+            Some(&interface.fields)
+        } else {
+            None
+        }
+    }
+
+    fn validate_selection_set(
+        &self,
+        type_name: &str,
+        selection_set: &SelectionSet
+    ) -> Result<(), ValidationError> {
+        for item in &selection_set.items {
+            match item {
+                Selection::Field(field) => {
+                    if field.name == crate::constants::TYPENAME_FIELD {
+                        continue;
+                    }
+
+                    let fields = self.fields(type_name).ok_or_else(|| ValidationError {
+                        message: format!(
+                            "Cannot select `{}` on union `{}` directly, use an inline fragment",
+                            field.name, type_name
+                        ),
+                        position: field.position
+                    })?;
+                    let schema_field = fields
+                        .iter()
+                        .find(|schema_field| schema_field.name == field.name)
+                        .ok_or_else(|| {
+                            let available: Vec<_> = fields.iter().map(|field| field.name).collect();
+                            ValidationError {
+                                message: format!(
+                                    "Could not find field `{}` on `{}`. Available fields: `{}`.",
+                                    field.name,
+                                    type_name,
+                                    available.join("`, `")
+                                ),
+                                position: field.position
+                            }
+                        })?;
+
+                    let field_type = schema_field.type_.inner_name_str();
+                    let is_composite = self.check_composite_type(field_type, field.position);
+                    match (is_composite, field.selection_set.items.is_empty()) {
+                        (Ok(()), true) => {
+                            return Err(ValidationError {
+                                message: format!(
+                                    "Field `{}` of type `{}` must have a selection of subfields",
+                                    field.name, field_type
+                                ),
+                                position: field.position
+                            })
+                        }
+                        (Ok(()), false) => {
+                            self.validate_selection_set(field_type, &field.selection_set)?
+                        }
+                        (Err(_), false) => {
+                            return Err(ValidationError {
+                                message: format!(
+                                    "Field `{}` of type `{}` can't have a selection of subfields",
+                                    field.name, field_type
+                                ),
+                                position: field.position
+                            })
+                        }
+                        (Err(_), true) => {}
+                    }
+                }
+                Selection::FragmentSpread(spread) => {
+                    if !self.fragments.contains_key(spread.fragment_name.as_str()) {
+                        return Err(ValidationError {
+                            message: format!("Unknown fragment: {}", spread.fragment_name),
+                            position: spread.position
+                        });
+                    }
+                }
+                Selection::InlineFragment(fragment) => match &fragment.type_condition {
+                    Some(TypeCondition::On(on)) => {
+                        self.check_composite_type(on, fragment.position)?;
+                        self.validate_selection_set(on, &fragment.selection_set)?;
+                    }
+                    None => self.validate_selection_set(type_name, &fragment.selection_set)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that all variables used in the selection set and the fragments it spreads are
+    /// defined by the operation.
+    fn check_variable_uses(
+        &self,
+        selection_set: &'a SelectionSet,
+        defined: &HashSet<&str>,
+        visited_fragments: &mut HashSet<&'a str>
+    ) -> Result<(), ValidationError> {
+        let check_directives = |directives: &[Directive]| {
+            for directive in directives {
+                for (_, value) in &directive.arguments {
+                    check_value(value, defined, directive.position)?;
+                }
+            }
+            Ok(())
+        };
+
+        for item in &selection_set.items {
+            match item {
+                Selection::Field(field) => {
+                    for (_, value) in &field.arguments {
+                        check_value(value, defined, field.position)?;
+                    }
+                    check_directives(&field.directives)?;
+                    self.check_variable_uses(&field.selection_set, defined, visited_fragments)?;
+                }
+                Selection::FragmentSpread(spread) => {
+                    check_directives(&spread.directives)?;
+                    let name = spread.fragment_name.as_str();
+                    if let Some(fragment) = self.fragments.get(name) {
+                        if visited_fragments.insert(name) {
+                            self.check_variable_uses(
+                                &fragment.selection_set,
+                                defined,
+                                visited_fragments
+                            )?;
+                        }
+                    }
+                }
+                Selection::InlineFragment(fragment) => {
+                    check_directives(&fragment.directives)?;
+                    self.check_variable_uses(&fragment.selection_set, defined, visited_fragments)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn check_value(
+    value: &Value,
+    defined: &HashSet<&str>,
+    position: Pos
+) -> Result<(), ValidationError> {
+    match value {
+        Value::Variable(name) if !defined.contains(name.as_str()) => Err(ValidationError {
+            message: format!("Variable `${}` is not defined by the operation", name),
+            position
+        }),
+        Value::List(values) => values
+            .iter()
+            .try_for_each(|value| check_value(value, defined, position)),
+        Value::Object(fields) => fields
+            .values()
+            .try_for_each(|value| check_value(value, defined, position)),
+        _ => Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(query: &str) -> Result<(), ValidationError> {
+        let schema =
+            graphql_parser::parse_schema(include_str!("tests/star_wars_schema.graphql")).unwrap();
+        let schema = Schema::from(&schema);
+        let query = graphql_parser::parse_query(query).unwrap();
+        validate_document(&query, &schema)
+    }
+
+    #[test]
+    fn valid_document() {
+        let result = validate(
+            r#"
+            query Hero($episode: Episode) {
+                hero(episode: $episode) {
+                    __typename
+                    name
+                    ... on Droid {
+                        primaryFunction
+                    }
+                    ...HumanFields
+                }
+            }
+
+            fragment HumanFields on Human {
+                homePlanet
+            }
+            "#
+        );
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn unknown_field() {
+        let error = validate("query Hero {\n  hero {\n    name\n    age\n  }\n}").unwrap_err();
+
+        assert!(error
+            .message
+            .starts_with("Could not find field `age` on `Character`."));
+        assert_eq!(error.position, Pos { line: 4, column: 5 });
+    }
+
+    #[test]
+    fn missing_subselection() {
+        let error = validate("query Hero { hero }").unwrap_err();
+
+        assert_eq!(
+            error.message,
+            "Field `hero` of type `Character` must have a selection of subfields"
+        );
+    }
+
+    #[test]
+    fn unknown_type_condition() {
+        let error = validate("query Hero { hero { ... on Wookie { name } } }").unwrap_err();
+
+        assert_eq!(
+            error.message,
+            "Unknown object, interface or union type `Wookie`"
+        );
+    }
+
+    #[test]
+    fn undefined_variable() {
+        let error = validate(
+            "query Hero { ...Fields }\nfragment Fields on Query { hero(episode: $episode) { name } }"
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.message,
+            "Variable `$episode` is not defined by the operation"
+        );
+        assert_eq!(error.position.line, 2);
+    }
+}
@@ -1,4 +1,5 @@
-use crate::{field_type::FieldType, query::QueryContext};
+use crate::{enums::ENUMS_PREFIX, field_type::FieldType, query::QueryContext};
+use heck::SnakeCase;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
 use std::collections::BTreeMap;
@@ -11,6 +12,13 @@ pub struct Variable<'query> {
 }
 
 impl<'query> Variable<'query> {
+    /// The name of the function returning the default value, if the variable has one.
+    pub(crate) fn default_value_constructor_name(&self) -> Option<Ident> {
+        self.default
+            .as_ref()
+            .map(|_| Ident::new(&format!("default_{}", self.name), Span::call_site()))
+    }
+
     pub(crate) fn generate_default_value_constructor(
         &self,
         context: &QueryContext<'_, '_>
@@ -18,8 +26,8 @@ impl<'query> Variable<'query> {
         context.schema.require(&self.ty.inner_name_str());
         match &self.default {
             Some(default) => {
-                let fn_name = Ident::new(&format!("default_{}", self.name), Span::call_site());
-                let ty = self.ty.to_rust(context, "");
+                let fn_name = self.default_value_constructor_name()?;
+                let ty = self.ty.to_rust_input(context, false);
                 let value = graphql_parser_value_to_literal(
                     default,
                     context,
@@ -49,7 +57,7 @@ impl<'query> std::convert::From<&'query graphql_parser::query::VariableDefinitio
     }
 }
 
-fn graphql_parser_value_to_literal(
+pub(crate) fn graphql_parser_value_to_literal(
     value: &graphql_parser::query::Value,
     context: &QueryContext<'_, '_>,
     ty: &FieldType<'_>,
@@ -57,6 +65,16 @@ fn graphql_parser_value_to_literal(
 ) -> TokenStream {
     use graphql_parser::query::Value;
 
+    // Only the outermost layer of a nullable type is a `MaybeUndefined`, list elements stay
+    // `Option`s.
+    if let Value::Null = value {
+        return if is_optional && context.maybe_undefined {
+            quote!(::artemis::MaybeUndefined::Null)
+        } else {
+            quote!(None)
+        };
+    }
+
     let inner = match value {
         Value::Boolean(b) => {
             if *b {
@@ -67,13 +85,26 @@ fn graphql_parser_value_to_literal(
         }
         Value::String(s) => quote!(#s.to_string()),
         Value::Variable(_) => panic!("variable in variable"),
-        Value::Null => panic!("null as default value"),
+        Value::Null => unreachable!("null is handled above"),
         Value::Float(f) => quote!(#f),
         Value::Int(i) => {
             let i = i.as_i64();
-            quote!(#i)
+            // Integer literals are valid for floats too
+            if ty.inner_name_str() == "Float" {
+                let f = i.map(|i| i as f64);
+                quote!(#f)
+            } else {
+                quote!(#i)
+            }
+        }
+        Value::Enum(en) => {
+            let norm = context.normalization;
+            let enum_name = norm.enum_name(format!("{}{}", ENUMS_PREFIX, ty.inner_name_str()));
+            let enum_name = Ident::new(&enum_name, Span::call_site());
+            let variant = norm.enum_variant(crate::shared::keyword_replace(en));
+            let variant = Ident::new(&variant, Span::call_site());
+            quote!(#enum_name::#variant)
         }
-        Value::Enum(en) => quote!(#en),
         Value::List(inner) => {
             let elements = inner
                 .iter()
@@ -87,7 +118,9 @@ fn graphql_parser_value_to_literal(
         Value::Object(obj) => render_object_literal(obj, ty, context)
     };
 
-    if is_optional {
+    if is_optional && context.maybe_undefined {
+        quote!(::artemis::MaybeUndefined::Value(#inner))
+    } else if is_optional {
         quote!(Some(#inner))
     } else {
         inner
@@ -100,7 +133,9 @@ fn render_object_literal(
     context: &QueryContext<'_, '_>
 ) -> TokenStream {
     let type_name = ty.inner_name_str();
-    let constructor = Ident::new(&type_name, Span::call_site());
+    let norm = context.normalization;
+    let constructor = norm.input_name(crate::shared::keyword_replace(type_name));
+    let constructor = Ident::new(&constructor, Span::call_site());
     let schema_type = context
         .schema
         .inputs
@@ -110,8 +145,13 @@ fn render_object_literal(
         .fields
         .iter()
         .map(|(name, field)| {
-            let field_name = Ident::new(&name, Span::call_site());
-            let provided_value = object.get(name.to_owned());
+            let field_name = crate::shared::keyword_replace(&name.to_snake_case());
+            let field_name = norm.field_name(field_name);
+            let field_name = Ident::new(&field_name, Span::call_site());
+            // Fields that aren't in the literal get the default value from the schema
+            let provided_value = object
+                .get(name.to_owned())
+                .or_else(|| schema_type.default_values.get(name));
             match provided_value {
                 Some(default_value) => {
                     let value = graphql_parser_value_to_literal(
@@ -122,6 +162,9 @@ fn render_object_literal(
                     );
                     quote!(#field_name: #value)
                 }
+                None if context.maybe_undefined => {
+                    quote!(#field_name: ::artemis::MaybeUndefined::Undefined)
+                }
                 None => quote!(#field_name: None)
             }
         })